@@ -1,7 +1,9 @@
-// Copyright 2023-2024 Sean Kelleher. All rights reserved.
+// Copyright 2023-2025 Sean Kelleher. All rights reserved.
 // Use of this source code is governed by an MIT
 // licence that can be found in the LICENCE file.
 
+use crate::lexer::Position;
+
 #[derive(Clone, Debug)]
 pub enum Prog {
     Body{stmts: Block},
@@ -9,8 +11,10 @@ pub enum Prog {
 
 pub type Block = Vec<Stmt>;
 
+pub type Stmt = (RawStmt, Span);
+
 #[derive(Clone, Debug)]
-pub enum Stmt {
+pub enum RawStmt {
     Block{block: Block},
 
     Expr{expr: Expr},
@@ -38,6 +42,10 @@ pub enum Stmt {
         stmts: Block,
     },
     Return{loc: Location, expr: Expr},
+
+    // `Error` is a statement that couldn't be parsed. Programs that contain
+    // `Error` statements aren't evaluated.
+    Error,
 }
 
 #[derive(Clone,Debug)]
@@ -48,7 +56,25 @@ pub struct Branch {
 
 pub type Location = (usize, usize);
 
-pub type Expr = (RawExpr, Location);
+// `Span` is the region of the source that an AST node was parsed from. `start`
+// and `end` refer to the first and last characters of the node, but note that
+// `end.offset` is the byte after the node, so that `start.offset` and
+// `end.offset` can be used to slice the source.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    // `loc` returns the line and column of the start of the span, which is
+    // the location used when reporting errors for the node.
+    pub fn loc(&self) -> Location {
+        self.start.loc()
+    }
+}
+
+pub type Expr = (RawExpr, Span);
 
 #[derive(Clone, Debug)]
 pub enum RawExpr {
@@ -57,9 +83,9 @@ pub enum RawExpr {
     Bool{b: bool},
     Int{n: i64},
     // `interpolation_slots` is `None` iff the string isn't interpolated,
-    // otherwise it contains start/end indices of substrings to be evaluated
-    // during interpolation.
-    Str{s: String, interpolation_slots: Option<Vec<(usize, usize)>>},
+    // otherwise it contains the parsed slots to be evaluated during
+    // interpolation.
+    Str{s: String, interpolation_slots: Option<Vec<InterpolationSlot>>},
 
     Var{name: String},
 
@@ -86,6 +112,56 @@ pub enum RawExpr {
     Call{func: Box<Expr>, args: Vec<ListItem>},
 }
 
+// `InterpolationSlot` is a `${...}` slot in an interpolated string, where
+// `start` and `end` are the indices of the slot (including its delimiters) in
+// the string.
+#[derive(Clone, Debug)]
+pub struct InterpolationSlot {
+    pub start: usize,
+    pub end: usize,
+    pub expr: Expr,
+    pub spec: FormatSpec,
+}
+
+// `FormatSpec` is a parsed interpolation slot format specifier, such as the
+// `>5` in `${n:>5}`. The syntax is a subset of the syntax used by Rust's
+// `format!`:
+//
+//     [[fill]align][sign]['#']['0'][width]['.' precision][type]
+//
+// where `align` is one of `<`, `^` or `>`, `sign` is `+`, and `type` is one of
+// `x`, `X`, `o`, `b` (for rendering `int`s in different bases) or `?` (for
+// rendering values using `repr`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FormatSpec {
+    pub fill: Option<char>,
+    pub align: Option<Align>,
+    pub sign: bool,
+    pub alternate: bool,
+    pub zero: bool,
+    pub width: Option<usize>,
+    pub precision: Option<usize>,
+    pub kind: FormatKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Centre,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FormatKind {
+    #[default]
+    Display,
+    Repr,
+    LowerHex,
+    UpperHex,
+    Octal,
+    Binary,
+}
+
 #[derive(Clone, Debug)]
 pub enum BinaryOp {
     Sum,
@@ -108,6 +184,31 @@ pub enum BinaryOp {
     RefNe,
 }
 
+impl BinaryOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Sum => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Gte => ">=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Lte => "<=",
+
+            BinaryOp::RefEq => "===",
+            BinaryOp::RefNe => "!==",
+        }
+    }
+}
+
 #[derive(Clone,Debug)]
 pub struct ListItem {
     pub expr: Expr,
@@ -119,3 +220,70 @@ pub enum PropItem {
     Pair{name: Expr, value: Expr},
     Single{expr: Expr, is_spread: bool, collect: bool},
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::lexer::Lexer;
+    use crate::parser::ProgParser;
+    use crate::syntax_error;
+
+    #[test]
+    fn test_parses_spans() {
+        let src = "x := foo(1, 2) + 3\nprint($\"é ${x * 2}\")\n";
+
+        let tokens = syntax_error::tokens(Lexer::new(src));
+        let mut errors = vec![];
+        let Prog::Body{stmts} =
+            ProgParser::new().parse(&mut errors, tokens)
+                .expect("couldn't parse source");
+        assert!(errors.is_empty(), "unexpected syntax errors: {errors:?}");
+        let text = |span: &Span| &src[span.start.offset..span.end.offset];
+
+        let (decl, decl_span) = &stmts[0];
+        assert_eq!(text(decl_span), "x := foo(1, 2) + 3");
+        assert_eq!(decl_span.loc(), (1, 1));
+        assert_eq!(decl_span.end.loc(), (1, 18));
+
+        let (lhs, rhs) =
+            match decl {
+                RawStmt::Declare{lhs, rhs} => (lhs, rhs),
+                _ => panic!("expected declaration, got {decl:?}"),
+            };
+        assert_eq!(text(&lhs.1), "x");
+        assert_eq!(text(&rhs.1), "foo(1, 2) + 3");
+
+        let (sum_lhs, sum_rhs) =
+            match &rhs.0 {
+                RawExpr::BinaryOp{lhs, rhs, ..} => (lhs, rhs),
+                _ => panic!("expected binary operation, got {rhs:?}"),
+            };
+        assert_eq!(text(&sum_lhs.1), "foo(1, 2)");
+        assert_eq!(text(&sum_rhs.1), "3");
+
+        let (print, print_span) = &stmts[1];
+        assert_eq!(text(print_span), "print($\"é ${x * 2}\")");
+        assert_eq!(print_span.loc(), (2, 1));
+
+        let slots =
+            match print {
+                RawStmt::Expr{expr: (RawExpr::Call{args, ..}, _)} => {
+                    match &args[0].expr.0 {
+                        RawExpr::Str{interpolation_slots: Some(slots), ..} => {
+                            slots
+                        },
+                        v => {
+                            panic!("expected interpolated string, got {v:?}");
+                        },
+                    }
+                },
+                _ => {
+                    panic!("expected call, got {print:?}");
+                },
+            };
+        let slot_span = &slots[0].expr.1;
+        assert_eq!(text(slot_span), "x * 2");
+        assert_eq!(slot_span.loc(), (2, 13));
+    }
+}
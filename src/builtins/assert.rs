@@ -0,0 +1,206 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeSet;
+use std::error::Error as StdError;
+
+use snafu::ResultExt;
+
+use super::fns;
+use crate::eval;
+use crate::eval::EvaluationContext;
+use crate::eval::error::AssertArgsFailed;
+use crate::eval::error::AssertNoThisFailed;
+use crate::eval::error::AssertStrFailed;
+use crate::eval::error::Error;
+use crate::eval::error::Result;
+use crate::eval::error::render_type;
+use crate::eval::error::requested_exit_code;
+use crate::eval::value;
+use crate::eval::value::SourcedValue;
+use crate::eval::value::Value;
+use crate::lexer::Location;
+use crate::lock_deref;
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn assert(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    fns::assert_arg_range("assert", 1, 2, &args)
+        .context(AssertArgsFailed)?;
+
+    fns::assert_no_this(this.as_ref())
+        .context(AssertNoThisFailed)?;
+
+    let ok =
+        match args[0].v {
+            Value::Bool(b) => b,
+            ref v => return Err(Error::BuiltinFuncErr{msg: format!(
+                "`assert` condition must be a 'bool', got '{}'",
+                render_type(v),
+            )}),
+        };
+
+    let msg =
+        match args.get(1) {
+            Some(msg) => {
+                Some(
+                    fns::assert_str("msg", msg)
+                        .context(AssertStrFailed)?,
+                )
+            },
+            None => {
+                None
+            },
+        };
+
+    if !ok {
+        return Err(Error::AssertionFailed{msg});
+    }
+
+    Ok(value::new_null())
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn assert_eq(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    fns::assert_args("assert_eq", 2, &args)
+        .context(AssertArgsFailed)?;
+
+    fns::assert_no_this(this.as_ref())
+        .context(AssertNoThisFailed)?;
+
+    let (actual, expected) = (&args[0].v, &args[1].v);
+    if let Some(path) = difference(actual, expected) {
+        return Err(Error::AssertEqFailed{
+            actual: fns::render_repr(actual),
+            expected: fns::render_repr(expected),
+            path,
+        });
+    }
+
+    Ok(value::new_null())
+}
+
+// `assert_throws` calls the function in `args`, and returns the message of
+// the error that the call failed with.
+#[allow(clippy::needless_pass_by_value)]
+pub fn assert_throws(
+    context: &EvaluationContext,
+    args: Vec<SourcedValue>,
+    (line, col): Location,
+)
+    -> Result<SourcedValue>
+{
+    fns::assert_args("assert_throws", 1, &args)
+        .context(AssertArgsFailed)?;
+
+    let f = args[0].clone();
+    let is_func =
+        matches!(
+            f.v,
+            Value::BuiltinFunc{..}
+                | Value::EvalBuiltinFunc{..}
+                | Value::Func(_),
+        );
+    if !is_func {
+        return Err(Error::BuiltinFuncErr{msg: format!(
+            "`assert_throws` can only call a 'func', got '{}'",
+            render_type(&f.v),
+        )});
+    }
+
+    match eval::call_value(context, f, vec![], (&line, &col)) {
+        Ok(v) => {
+            Err(Error::AssertThrowsFailed{value: fns::render_repr(&v.v)})
+        },
+        Err(e) => {
+            // Calls to `exit` aren't failures, so they aren't caught.
+            if requested_exit_code(&e).is_some() {
+                return Err(e);
+            }
+
+            // We return the message of the underlying error, without the
+            // context that was added as it was propagated.
+            let mut cause: &dyn StdError = &e;
+            while let Some(source) = cause.source() {
+                cause = source;
+            }
+
+            Ok(value::new_str_from_string(cause.to_string()))
+        },
+    }
+}
+
+// `difference` returns the path to the first value in `actual` that differs
+// from the corresponding value in `expected`, or `None` if `actual` and
+// `expected` are equal. The path is empty if `actual` and `expected` differ
+// at the top level.
+fn difference(actual: &Value, expected: &Value) -> Option<String> {
+    match (actual, expected) {
+        (Value::List(xs), Value::List(ys)) => {
+            if value::ref_eq(xs, ys) {
+                return None;
+            }
+            // We clone the lists so that they aren't locked while their items
+            // are compared, in case an item refers to the list itself.
+            let xs = lock_deref!(xs).clone();
+            let ys = lock_deref!(ys).clone();
+
+            for (i, (x, y)) in xs.iter().zip(&ys).enumerate() {
+                if let Some(path) = difference(&x.v, &y.v) {
+                    return Some(format!("[{i}]{path}"));
+                }
+            }
+
+            if xs.len() == ys.len() {
+                None
+            } else {
+                Some(String::new())
+            }
+        },
+
+        (Value::Object(xs), Value::Object(ys)) => {
+            if value::ref_eq(xs, ys) {
+                return None;
+            }
+            let xs = lock_deref!(xs).clone();
+            let ys = lock_deref!(ys).clone();
+
+            let names: BTreeSet<&String> =
+                xs.keys().chain(ys.keys()).collect();
+            for name in names {
+                match (xs.get(name), ys.get(name)) {
+                    (Some(x), Some(y)) => {
+                        if let Some(path) = difference(&x.v, &y.v) {
+                            return Some(format!(".'{name}'{path}"));
+                        }
+                    },
+                    _ => {
+                        return Some(format!(".'{name}'"));
+                    },
+                }
+            }
+
+            None
+        },
+
+        (Value::Func(a), Value::Func(b)) => {
+            if value::ref_eq(a, b) {
+                None
+            } else {
+                Some(String::new())
+            }
+        },
+
+        _ => {
+            if fns::render_repr(actual) == fns::render_repr(expected) {
+                None
+            } else {
+                Some(String::new())
+            }
+        },
+    }
+}
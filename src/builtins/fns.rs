@@ -2,12 +2,18 @@
 // Use of this source code is governed by an MIT
 // licence that can be found in the LICENCE file.
 
+use std::fmt::Write as FmtWrite;
+use std::io;
+use std::io::Write;
+use std::sync::Mutex;
+
 use snafu::ResultExt;
 
 use crate::eval::error::AssertArgsFailed;
 use crate::eval::error::AssertNoThisFailed;
 use crate::eval::error::Error;
 use crate::eval::error::Result;
+use crate::eval::error::render_type;
 use crate::eval::value;
 use crate::eval::value::Func;
 use crate::eval::value::SourcedValue;
@@ -18,20 +24,155 @@ use crate::lock_deref;
 pub fn print(this: Option<SourcedValue>, args: Vec<SourcedValue>)
     -> Result<SourcedValue>
 {
-    assert_args("print", 1, &args)
-        .context(AssertArgsFailed)?;
+    emit(Stream::Stdout, this.as_ref(), " ", &args, true)
+}
 
-    assert_no_this(this.as_ref())
+#[allow(clippy::needless_pass_by_value)]
+pub fn write(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    emit(Stream::Stdout, this.as_ref(), " ", &args, false)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn print_sep(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    let (sep, args) = split_sep("print_sep", &args)?;
+
+    emit(Stream::Stdout, this.as_ref(), &sep, args, true)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn eprint(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    emit(Stream::Stderr, this.as_ref(), " ", &args, true)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn ewrite(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    emit(Stream::Stderr, this.as_ref(), " ", &args, false)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn eprint_sep(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    let (sep, args) = split_sep("eprint_sep", &args)?;
+
+    emit(Stream::Stderr, this.as_ref(), &sep, args, true)
+}
+
+#[derive(Clone, Copy)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+// `Redirect` receives output that would otherwise be written to a standard
+// stream.
+pub type Redirect = Box<dyn FnMut(Stream, &str) -> io::Result<()> + Send>;
+
+static REDIRECT: Mutex<Option<Redirect>> = Mutex::new(None);
+
+// `redirect_output` causes all subsequent output from builtin functions to be
+// passed to `redirect` instead of being written to the standard streams. This
+// is used when the standard streams are used for other purposes, such as
+// communicating with a debugger.
+pub fn redirect_output(redirect: Redirect) {
+    *REDIRECT.lock().unwrap() = Some(redirect);
+}
+
+// `emit` writes the rendered `args` to `stream`, separated by `sep`, and
+// followed by a newline if `newline` is `true`.
+fn emit(
+    stream: Stream,
+    this: Option<&SourcedValue>,
+    sep: &str,
+    args: &[SourcedValue],
+    newline: bool,
+)
+    -> Result<SourcedValue>
+{
+    assert_no_this(this)
         .context(AssertNoThisFailed)?;
 
-    let s = render(&args[0])?;
+    let mut rendered_args = vec![];
+    for arg in args {
+        rendered_args.push(render(arg)?);
+    }
+
+    let mut s = rendered_args.join(sep);
+    if newline {
+        s.push('\n');
+    }
+
+    // The standard output is line-buffered and the standard error isn't
+    // buffered, so we only need to flush output that doesn't end in a newline
+    // for it to be visible, and for the relative order of output to stdout and
+    // stderr to be maintained. `process::exit` flushes the standard output
+    // before exiting.
+    let mut maybe_redirect = REDIRECT.lock().unwrap();
+    let result =
+        match (maybe_redirect.as_mut(), stream) {
+            (Some(redirect), _) => {
+                redirect(stream, &s)
+            },
+            (None, Stream::Stdout) => {
+                let mut stdout = io::stdout().lock();
 
-    println!("{s}");
+                stdout.write_all(s.as_bytes())
+                    .and_then(|()| {
+                        if newline {
+                            Ok(())
+                        } else {
+                            stdout.flush()
+                        }
+                    })
+            },
+            (None, Stream::Stderr) => {
+                io::stderr().lock().write_all(s.as_bytes())
+            },
+        };
+
+    if let Err(e) = result {
+        return Err(Error::BuiltinFuncErr{msg: format!(
+            "couldn't write output: {e}",
+        )});
+    }
 
     Ok(value::new_null())
 }
 
-fn render(v: &SourcedValue) -> Result<String> {
+// `split_sep` returns the separator passed as the first argument of a
+// `*_sep` function, along with the remaining arguments.
+fn split_sep<'a>(fn_name: &str, args: &'a [SourcedValue])
+    -> Result<(String, &'a [SourcedValue])>
+{
+    let (sep, rest) =
+        match args.split_first() {
+            Some(v) => v,
+            None => return Err(Error::BuiltinFuncErr{msg: format!(
+                "`{fn_name}` requires a separator as its first argument",
+            )}),
+        };
+
+    let sep =
+        match &sep.v {
+            Value::Str(raw_sep) => decode_str("sep", raw_sep)?,
+            v => return Err(Error::BuiltinFuncErr{msg: format!(
+                "`{fn_name}` separator must be a 'string', got '{}'",
+                render_type(v),
+            )}),
+        };
+
+    Ok((sep, rest))
+}
+
+pub fn render(v: &SourcedValue) -> Result<String> {
     let mut s = String::new();
 
     match v.v.clone() {
@@ -79,20 +220,148 @@ fn render(v: &SourcedValue) -> Result<String> {
             s += "}";
         },
 
-        Value::BuiltinFunc{name, ..} => {
+        Value::BuiltinFunc{name, ..} | Value::EvalBuiltinFunc{name, ..} => {
             s += &format!("<built-in function '{name}'>");
         },
 
+        Value::BuiltinIter{name, ..} => {
+            let _ = write!(s, "<built-in iterator '{name}'>");
+        },
+
         Value::Func(f) => {
             let Func{name, ..} = &lock_deref!(f);
 
-            s += &format!("<function '{name:?}'>");
+            if let Some(name) = name {
+                let _ = write!(s, "<function '{name}'>");
+            } else {
+                s += "<unnamed function>";
+            }
         },
     }
 
     Ok(s.to_string())
 }
 
+#[allow(clippy::needless_pass_by_value)]
+pub fn repr(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    assert_args("repr", 1, &args)
+        .context(AssertArgsFailed)?;
+
+    assert_no_this(this.as_ref())
+        .context(AssertNoThisFailed)?;
+
+    Ok(value::new_str_from_string(render_repr(&args[0].v)))
+}
+
+// `render_repr` renders `v` using Seed literal syntax where possible, so that
+// values of different types can't be confused with each other (e.g. `"1"` and
+// `1`). Functions don't have a literal syntax, so they're rendered using a
+// description of their name and arity.
+pub fn render_repr(v: &Value) -> String {
+    match v {
+        Value::Null => {
+            "null".to_string()
+        },
+
+        Value::Bool(b) => {
+            b.to_string()
+        },
+
+        Value::Int(n) => {
+            n.to_string()
+        },
+
+        Value::Str(raw_str) => {
+            render_repr_str(&String::from_utf8_lossy(raw_str))
+        },
+
+        Value::List(items) => {
+            let rendered_items: Vec<String> =
+                lock_deref!(items)
+                    .iter()
+                    .map(|item| render_repr(&item.v))
+                    .collect();
+
+            format!("[{}]", rendered_items.join(", "))
+        },
+
+        Value::Object(props) => {
+            let rendered_props: Vec<String> =
+                lock_deref!(props)
+                    .iter()
+                    .map(|(name, prop)| format!(
+                        "{}: {}",
+                        render_repr_str(name),
+                        render_repr(&prop.v),
+                    ))
+                    .collect();
+
+            format!("{{{}}}", rendered_props.join(", "))
+        },
+
+        Value::BuiltinFunc{name, ..} | Value::EvalBuiltinFunc{name, ..} => {
+            format!("<built-in function '{name}'>")
+        },
+
+        Value::BuiltinIter{name, ..} => {
+            format!("<built-in iterator '{name}'>")
+        },
+
+        Value::Func(f) => {
+            let Func{name, args, collect_args, ..} = &lock_deref!(f);
+
+            let arity =
+                if *collect_args {
+                    format!("{}+", args.len() - 1)
+                } else {
+                    args.len().to_string()
+                };
+
+            if let Some(name) = name {
+                format!("<function '{name}' (arity {arity})>")
+            } else {
+                format!("<unnamed function (arity {arity})>")
+            }
+        },
+    }
+}
+
+// `render_repr_str` renders `s` as a string literal, using the escape
+// sequences supported by the lexer.
+fn render_repr_str(s: &str) -> String {
+    let mut rendered = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '\\' => {
+                rendered += "\\\\";
+            },
+            '"' => {
+                rendered += "\\\"";
+            },
+            '$' => {
+                rendered += "\\$";
+            },
+            '\n' => {
+                rendered += "\\n";
+            },
+            '\r' => {
+                rendered += "\\r";
+            },
+            c if c.is_ascii_control() => {
+                let _ = write!(rendered, "\\x{:02x}", c as u32);
+            },
+            c => {
+                rendered.push(c);
+            },
+        }
+    }
+    rendered.push('"');
+
+    rendered
+}
+
 // `assert_args` asserts that the correct number of arguments were passed for
 // built-in functions.
 pub fn assert_args(fn_name: &str, exp_args: usize, args: &[SourcedValue])
@@ -115,6 +384,34 @@ pub fn assert_args(fn_name: &str, exp_args: usize, args: &[SourcedValue])
     Ok(())
 }
 
+// `assert_arg_range` asserts that the number of arguments passed to a built-in
+// function is between `min` and `max`, inclusive.
+pub fn assert_arg_range(
+    fn_name: &str,
+    min: usize,
+    max: usize,
+    args: &[SourcedValue],
+)
+    -> Result<()>
+{
+    let args_len = args.len();
+
+    if args_len < min || args_len > max {
+        let range =
+            if max == min + 1 {
+                format!("{min} or {max}")
+            } else {
+                format!("between {min} and {max}")
+            };
+
+        return Err(Error::BuiltinFuncErr{msg: format!(
+            "`{fn_name}` takes {range} arguments (got {args_len})",
+        )})
+    }
+
+    Ok(())
+}
+
 pub fn assert_no_this(this: Option<&SourcedValue>) -> Result<()> {
     if this.is_none() {
         Ok(())
@@ -133,12 +430,7 @@ pub fn assert_this(this: Option<SourcedValue>) -> Result<SourcedValue> {
 
 pub fn assert_str(val_name: &str, v: &SourcedValue) -> Result<String> {
     if let Value::Str(raw_str) = &v.v {
-        match String::from_utf8(raw_str.clone()) {
-            Ok(s) => Ok(s),
-            Err(e) => Err(Error::BuiltinFuncErr{msg: format!(
-                "couldn't convert `{val_name}` string to UTF-8: {e}",
-            )}),
-        }
+        decode_str(val_name, raw_str)
     } else {
         // TODO Add type information for the received type.
         let m = "dev err: expected 'string'";
@@ -146,3 +438,14 @@ pub fn assert_str(val_name: &str, v: &SourcedValue) -> Result<String> {
         Err(Error::Dev{msg: m.to_string()})
     }
 }
+
+// `decode_str` returns `raw_str`, which is the value of `val_name`, as a
+// `String`, or an error if it isn't valid UTF-8.
+pub fn decode_str(val_name: &str, raw_str: &[u8]) -> Result<String> {
+    match String::from_utf8(raw_str.to_vec()) {
+        Ok(s) => Ok(s),
+        Err(e) => Err(Error::BuiltinFuncErr{msg: format!(
+            "couldn't convert `{val_name}` string to UTF-8: {e}",
+        )}),
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+use snafu::ResultExt;
+
+use super::fns;
+use crate::eval::error::AssertArgsFailed;
+use crate::eval::error::AssertNoThisFailed;
+use crate::eval::error::Error;
+use crate::eval::error::Result;
+use crate::eval::error::render_type;
+use crate::eval::value;
+use crate::eval::value::SourcedValue;
+use crate::eval::value::Value;
+use crate::json;
+use crate::json::Json;
+use crate::lock_deref;
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn json_encode(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    fns::assert_arg_range("json_encode", 1, 2, &args)
+        .context(AssertArgsFailed)?;
+
+    fns::assert_no_this(this.as_ref())
+        .context(AssertNoThisFailed)?;
+
+    let indent =
+        if let Some(opts) = args.get(1) {
+            encode_indent(opts)?
+        } else {
+            None
+        };
+
+    let v = value_to_json(&args[0].v)?;
+
+    Ok(value::new_str_from_string(json::encode(&v, indent)))
+}
+
+fn encode_indent(opts: &SourcedValue) -> Result<Option<usize>> {
+    let props =
+        match &opts.v {
+            Value::Object(props) => props,
+            v => return Err(Error::BuiltinFuncErr{msg: format!(
+                "`json_encode` options must be an 'object', got '{}'",
+                render_type(v),
+            )}),
+        };
+
+    let mut indent = None;
+    for (name, prop) in &lock_deref!(props) {
+        if name != "indent" {
+            return Err(Error::BuiltinFuncErr{msg: format!(
+                "'{name}' isn't a valid `json_encode` option",
+            )});
+        }
+
+        let n =
+            match prop.v {
+                Value::Int(n) => n,
+                ref v => return Err(Error::BuiltinFuncErr{msg: format!(
+                    "`indent` must be an 'int', got '{}'",
+                    render_type(v),
+                )}),
+            };
+
+        match n.try_into() {
+            Ok(n) => {
+                indent = Some(n);
+            },
+            Err(_) => {
+                return Err(Error::BuiltinFuncErr{msg: format!(
+                    "`indent` can't be negative (got {n})",
+                )});
+            },
+        }
+    }
+
+    Ok(indent)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn json_decode(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    fns::assert_args("json_decode", 1, &args)
+        .context(AssertArgsFailed)?;
+
+    fns::assert_no_this(this.as_ref())
+        .context(AssertNoThisFailed)?;
+
+    let src =
+        match &args[0].v {
+            Value::Str(raw_src) => fns::decode_str("src", raw_src)?,
+            v => return Err(Error::BuiltinFuncErr{msg: format!(
+                "`json_decode` can only decode a 'string', got '{}'",
+                render_type(v),
+            )}),
+        };
+
+    match json::decode(&src) {
+        Ok(v) => {
+            Ok(json_to_value(v))
+        },
+        Err(json::DecodeError{line, col, msg}) => {
+            Err(Error::BuiltinFuncErr{msg: format!(
+                "invalid JSON at {line}:{col}: {msg}",
+            )})
+        },
+    }
+}
+
+// `value_to_json` returns an error if `v` contains a value that can't be
+// represented as JSON, such as a function or a non-UTF-8 string.
+pub fn value_to_json(v: &Value) -> Result<Json> {
+    match v {
+        Value::Null => {
+            Ok(Json::Null)
+        },
+        Value::Bool(b) => {
+            Ok(Json::Bool(*b))
+        },
+        Value::Int(n) => {
+            Ok(Json::Int(*n))
+        },
+        Value::Str(raw_str) => {
+            match String::from_utf8(raw_str.clone()) {
+                Ok(s) => Ok(Json::Str(s)),
+                Err(e) => Err(Error::BuiltinFuncErr{msg: format!(
+                    "couldn't encode string as JSON: {e}",
+                )}),
+            }
+        },
+        Value::List(items) => {
+            let mut json_items = vec![];
+            for item in &lock_deref!(items) {
+                json_items.push(value_to_json(&item.v)?);
+            }
+
+            Ok(Json::List(json_items))
+        },
+        Value::Object(props) => {
+            let mut json_props = BTreeMap::new();
+            for (name, prop) in &lock_deref!(props) {
+                json_props.insert(name.clone(), value_to_json(&prop.v)?);
+            }
+
+            Ok(Json::Object(json_props))
+        },
+        Value::BuiltinFunc{..}
+        | Value::EvalBuiltinFunc{..}
+        | Value::Func(_)
+        | Value::BuiltinIter{..} => {
+            Err(Error::BuiltinFuncErr{msg: format!(
+                "can't encode '{}' as JSON",
+                render_type(v),
+            )})
+        },
+    }
+}
+
+pub fn json_to_value(v: Json) -> SourcedValue {
+    match v {
+        Json::Null => {
+            value::new_null()
+        },
+        Json::Bool(b) => {
+            value::new_bool(b)
+        },
+        Json::Int(n) => {
+            value::new_int(n)
+        },
+        Json::Str(s) => {
+            value::new_str_from_string(s)
+        },
+        Json::List(items) => {
+            value::new_list(items.into_iter().map(json_to_value).collect())
+        },
+        Json::Object(props) => {
+            let props =
+                props
+                    .into_iter()
+                    .map(|(name, prop)| (name, json_to_value(prop)))
+                    .collect();
+
+            value::new_object(props)
+        },
+    }
+}
@@ -1,6 +1,10 @@
-// Copyright 2023-2024 Sean Kelleher. All rights reserved.
+// Copyright 2023-2025 Sean Kelleher. All rights reserved.
 // Use of this source code is governed by an MIT
 // licence that can be found in the LICENCE file.
 
+pub mod assert;
 pub mod fns;
+pub mod json;
+pub mod process;
+pub mod stdin;
 pub mod type_functions;
@@ -0,0 +1,134 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeMap;
+use std::env;
+
+use snafu::ResultExt;
+
+use super::fns;
+use crate::eval::error::AssertArgsFailed;
+use crate::eval::error::AssertNoThisFailed;
+use crate::eval::error::Error;
+use crate::eval::error::Result;
+use crate::eval::error::render_type;
+use crate::eval::value;
+use crate::eval::value::SourcedValue;
+use crate::eval::value::Value;
+
+// `new_args` returns the value of `args`, which contains the command-line
+// arguments that were passed after the path of the script.
+pub fn new_args(script_args: &[String]) -> SourcedValue {
+    value::new_list(
+        script_args.iter()
+            .map(|arg| value::new_str_from_string(arg.clone()))
+            .collect(),
+    )
+}
+
+// `new_env` returns the value of `env`, which contains functions for reading
+// the environment variables of the interpreter.
+pub fn new_env() -> SourcedValue {
+    let funcs: [(&str, value::BuiltinFunc); 2] = [
+        ("get", env_get),
+        ("vars", env_vars),
+    ];
+
+    value::new_object(
+        funcs.into_iter()
+            .map(|(name, f)| {
+                let qualified_name = format!("env.{name}");
+
+                (name.to_string(), value::new_built_in_func(qualified_name, f))
+            })
+            .collect(),
+    )
+}
+
+// `env_get` returns the value of the environment variable with the given name,
+// or `null` if it isn't set or isn't valid Unicode. `this` is ignored, because
+// it's `env` when `env_get` is called as `env.get`.
+#[allow(clippy::needless_pass_by_value)]
+pub fn env_get(_this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    fns::assert_args("env.get", 1, &args)
+        .context(AssertArgsFailed)?;
+
+    let name =
+        match &args[0].v {
+            Value::Str(raw_name) => fns::decode_str("name", raw_name)?,
+            v => return Err(Error::BuiltinFuncErr{msg: format!(
+                "`env.get` name must be a 'string', got '{}'",
+                render_type(v),
+            )}),
+        };
+
+    // `env::var` panics if `name` is empty or contains `=` or NUL, so such
+    // names are treated as being unset.
+    if name.is_empty() || name.contains(['=', '\0']) {
+        return Ok(value::new_null());
+    }
+
+    match env::var(&name) {
+        Ok(v) => Ok(value::new_str_from_string(v)),
+        Err(_) => Ok(value::new_null()),
+    }
+}
+
+// `env_vars` returns an object that maps the names of the environment
+// variables to their values. Variables whose names or values aren't valid
+// Unicode are skipped. `this` is ignored, as with `env_get`.
+#[allow(clippy::needless_pass_by_value)]
+pub fn env_vars(_this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    fns::assert_args("env.vars", 0, &args)
+        .context(AssertArgsFailed)?;
+
+    let vars: BTreeMap<String, SourcedValue> =
+        env::vars_os()
+            .filter_map(|(name, v)| {
+                match (name.into_string(), v.into_string()) {
+                    (Ok(name), Ok(v)) => {
+                        Some((name, value::new_str_from_string(v)))
+                    },
+                    _ => {
+                        None
+                    },
+                }
+            })
+            .collect();
+
+    Ok(value::new_object(vars))
+}
+
+// `exit` stops the evaluation of the script, and causes the interpreter to
+// exit with the given exit code. Output is flushed as it's written, so no
+// output is lost.
+#[allow(clippy::needless_pass_by_value)]
+pub fn exit(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    fns::assert_args("exit", 1, &args)
+        .context(AssertArgsFailed)?;
+
+    fns::assert_no_this(this.as_ref())
+        .context(AssertNoThisFailed)?;
+
+    let code =
+        match args[0].v {
+            Value::Int(n) if (0..=255).contains(&n) => n,
+            Value::Int(n) => return Err(Error::BuiltinFuncErr{msg: format!(
+                "`exit` code must be between 0 and 255, got {n}",
+            )}),
+            ref v => return Err(Error::BuiltinFuncErr{msg: format!(
+                "`exit` code must be an 'int', got '{}'",
+                render_type(v),
+            )}),
+        };
+
+    // `code` is between 0 and 255, so the conversion can't fail.
+    Err(Error::Exit{code: i32::try_from(code).unwrap_or_default()})
+}
@@ -0,0 +1,114 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::io;
+use std::io::BufRead;
+use std::io::Read;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use snafu::ResultExt;
+
+use super::fns;
+use crate::eval::error::AssertArgsFailed;
+use crate::eval::error::AssertNoThisFailed;
+use crate::eval::error::Error;
+use crate::eval::error::Result;
+use crate::eval::value;
+use crate::eval::value::SourcedValue;
+
+static DETACHED: AtomicBool = AtomicBool::new(false);
+
+// `detach_input` causes all subsequent reads by builtin functions to behave as
+// if the end of the input has been reached. This is used when the standard
+// input is used for other purposes, such as communicating with a debugger.
+pub fn detach_input() {
+    DETACHED.store(true, Ordering::SeqCst);
+}
+
+// `read_line` returns the next line of the standard input, without its line
+// ending, or `null` if the end of the input has been reached.
+#[allow(clippy::needless_pass_by_value)]
+pub fn read_line(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    fns::assert_args("read_line", 0, &args)
+        .context(AssertArgsFailed)?;
+
+    fns::assert_no_this(this.as_ref())
+        .context(AssertNoThisFailed)?;
+
+    match next_line()? {
+        Some(line) => Ok(line),
+        None => Ok(value::new_null()),
+    }
+}
+
+// `read_all` returns the rest of the standard input, which is empty if the end
+// of the input has been reached.
+#[allow(clippy::needless_pass_by_value)]
+pub fn read_all(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    fns::assert_args("read_all", 0, &args)
+        .context(AssertArgsFailed)?;
+
+    fns::assert_no_this(this.as_ref())
+        .context(AssertNoThisFailed)?;
+
+    let mut s = vec![];
+    if !DETACHED.load(Ordering::SeqCst) {
+        if let Err(e) = io::stdin().lock().read_to_end(&mut s) {
+            return Err(new_read_error(&e));
+        }
+    }
+
+    Ok(value::new_str(s))
+}
+
+// `lines` returns an iterator over the remaining lines of the standard input.
+// Lines are only read as a `for` loop needs them.
+#[allow(clippy::needless_pass_by_value)]
+pub fn lines(this: Option<SourcedValue>, args: Vec<SourcedValue>)
+    -> Result<SourcedValue>
+{
+    fns::assert_args("lines", 0, &args)
+        .context(AssertArgsFailed)?;
+
+    fns::assert_no_this(this.as_ref())
+        .context(AssertNoThisFailed)?;
+
+    Ok(value::new_built_in_iter("lines".to_string(), next_line))
+}
+
+// `next_line` returns the next line of the standard input, without its line
+// ending, or `None` if the end of the input has been reached.
+fn next_line() -> Result<Option<SourcedValue>> {
+    if DETACHED.load(Ordering::SeqCst) {
+        return Ok(None);
+    }
+
+    let mut line = vec![];
+    let n =
+        match io::stdin().lock().read_until(b'\n', &mut line) {
+            Ok(n) => n,
+            Err(e) => return Err(new_read_error(&e)),
+        };
+    if n == 0 {
+        return Ok(None);
+    }
+
+    if line.ends_with(b"\n") {
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+    }
+
+    Ok(Some(value::new_str(line)))
+}
+
+fn new_read_error(e: &io::Error) -> Error {
+    Error::BuiltinFuncErr{msg: format!("couldn't read input: {e}")}
+}
@@ -63,6 +63,12 @@ pub fn type_functions() -> TypeFunctions {
                 value::new_built_in_func("func->type".to_string(), any_type),
             ),
         ]),
+        iters: new_func_map(vec![
+            (
+                "type".to_string(),
+                value::new_built_in_func("iter->type".to_string(), any_type),
+            ),
+        ]),
     }
 }
 
@@ -119,7 +125,10 @@ fn render_type(v: &Value) -> String {
             Value::List(_) => "list",
             Value::Object(_) => "object",
 
-            Value::BuiltinFunc{..} | Value::Func{..} => "func",
+            Value::BuiltinFunc{..}
+            | Value::EvalBuiltinFunc{..}
+            | Value::Func{..} => "func",
+            Value::BuiltinIter{..} => "iter",
         };
 
     s.to_string()
@@ -0,0 +1,297 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Write;
+
+use crate::ast::Block;
+use crate::ast::Expr;
+use crate::ast::ListItem;
+use crate::ast::Location;
+use crate::ast::Prog;
+use crate::ast::PropItem;
+use crate::ast::RawExpr;
+use crate::ast::RawStmt;
+use crate::ast::Stmt;
+use crate::eval::hook::Hook;
+use crate::eval::scope::ScopeStack;
+
+// `Coverage` is a `Hook` that records how many times each statement of a
+// program is evaluated, and how many times each branch of each `if` statement
+// is taken.
+pub struct Coverage {
+    path: String,
+    // `stmts` maps the location of each statement to the number of times that
+    // it has been evaluated.
+    stmts: BTreeMap<Location, usize>,
+    // `branches` maps the location of each `if` statement to the number of
+    // times that each of its branches has been taken, where the last branch
+    // is the `else` branch.
+    branches: BTreeMap<Location, Vec<usize>>,
+}
+
+impl Coverage {
+    // `new` returns a `Coverage` for `prog`, which is the script at `path`.
+    pub fn new(path: &str, Prog::Body{stmts}: &Prog) -> Self {
+        let mut coverage = Coverage{
+            path: path.to_string(),
+            stmts: BTreeMap::new(),
+            branches: BTreeMap::new(),
+        };
+        coverage.add_stmts(stmts);
+
+        coverage
+    }
+
+    fn add_stmts(&mut self, stmts: &Block) {
+        for stmt in stmts {
+            self.add_stmt(stmt);
+        }
+    }
+
+    fn add_stmt(&mut self, (raw_stmt, span): &Stmt) {
+        if let RawStmt::Error = raw_stmt {
+            return;
+        }
+        self.stmts.insert(span.loc(), 0);
+
+        match raw_stmt {
+            RawStmt::Block{block} => {
+                self.add_stmts(block);
+            },
+            RawStmt::Expr{expr} | RawStmt::Return{expr, ..} => {
+                self.add_expr(expr);
+            },
+            RawStmt::Declare{lhs, rhs} |
+            RawStmt::Assign{lhs, rhs} |
+            RawStmt::OpAssign{lhs, rhs, ..} => {
+                self.add_expr(lhs);
+                self.add_expr(rhs);
+            },
+            RawStmt::If{branches, else_stmts} => {
+                self.branches.insert(span.loc(), vec![0; branches.len() + 1]);
+                for branch in branches {
+                    self.add_expr(&branch.cond);
+                    self.add_stmts(&branch.stmts);
+                }
+                if let Some(stmts) = else_stmts {
+                    self.add_stmts(stmts);
+                }
+            },
+            RawStmt::While{cond, stmts} => {
+                self.add_expr(cond);
+                self.add_stmts(stmts);
+            },
+            RawStmt::For{lhs, iter, stmts} => {
+                self.add_expr(lhs);
+                self.add_expr(iter);
+                self.add_stmts(stmts);
+            },
+            RawStmt::Func{stmts, ..} => {
+                self.add_stmts(stmts);
+            },
+            RawStmt::Break{..} | RawStmt::Continue{..} | RawStmt::Error => {
+            },
+        }
+    }
+
+    // `add_expr` adds the statements of the function literals in `expr`.
+    fn add_expr(&mut self, (raw_expr, _): &Expr) {
+        match raw_expr {
+            RawExpr::Null |
+            RawExpr::Bool{..} |
+            RawExpr::Int{..} |
+            RawExpr::Var{..} => {
+            },
+            RawExpr::Str{interpolation_slots, ..} => {
+                for slot in interpolation_slots.iter().flatten() {
+                    self.add_expr(&slot.expr);
+                }
+            },
+            RawExpr::BinaryOp{lhs, rhs, ..} => {
+                self.add_expr(lhs);
+                self.add_expr(rhs);
+            },
+            RawExpr::Range{start, end} => {
+                self.add_expr(start);
+                self.add_expr(end);
+            },
+            RawExpr::List{items, ..} => {
+                for ListItem{expr, ..} in items {
+                    self.add_expr(expr);
+                }
+            },
+            RawExpr::Index{expr, location} => {
+                self.add_expr(expr);
+                self.add_expr(location);
+            },
+            RawExpr::RangeIndex{expr, start, end} => {
+                self.add_expr(expr);
+                for e in start.iter().chain(end.iter()) {
+                    self.add_expr(e);
+                }
+            },
+            RawExpr::Object{props} => {
+                for prop in props {
+                    match prop {
+                        PropItem::Pair{name, value} => {
+                            self.add_expr(name);
+                            self.add_expr(value);
+                        },
+                        PropItem::Single{expr, ..} => {
+                            self.add_expr(expr);
+                        },
+                    }
+                }
+            },
+            RawExpr::Prop{expr, ..} => {
+                self.add_expr(expr);
+            },
+            RawExpr::Func{stmts, ..} => {
+                self.add_stmts(stmts);
+            },
+            RawExpr::Call{func, args} => {
+                self.add_expr(func);
+                for ListItem{expr, ..} in args {
+                    self.add_expr(expr);
+                }
+            },
+        }
+    }
+
+    // `write_lcov` writes the coverage to `w` in the `lcov` tracefile format.
+    // The hit count of a line is the largest hit count of the statements that
+    // start on that line.
+    pub fn write_lcov<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "TN:")?;
+        writeln!(w, "SF:{}", self.path)?;
+
+        let mut branches_hit = 0;
+        let mut num_branches = 0;
+        for (block, ((line, _), counts)) in self.branches.iter().enumerate() {
+            // `lcov` uses `-` for the branches of a statement that was never
+            // evaluated.
+            let evaluated = counts.iter().any(|n| *n > 0);
+            for (branch, n) in counts.iter().enumerate() {
+                let taken =
+                    if evaluated { n.to_string() } else { "-".to_string() };
+                writeln!(w, "BRDA:{line},{block},{branch},{taken}")?;
+
+                num_branches += 1;
+                if *n > 0 {
+                    branches_hit += 1;
+                }
+            }
+        }
+        writeln!(w, "BRF:{num_branches}")?;
+        writeln!(w, "BRH:{branches_hit}")?;
+
+        let mut lines: BTreeMap<usize, usize> = BTreeMap::new();
+        for ((line, _), n) in &self.stmts {
+            let hits = lines.entry(*line).or_default();
+            *hits = (*hits).max(*n);
+        }
+        for (line, hits) in &lines {
+            writeln!(w, "DA:{line},{hits}")?;
+        }
+        let lines_hit = lines.values().filter(|hits| **hits > 0).count();
+        writeln!(w, "LF:{}", lines.len())?;
+        writeln!(w, "LH:{lines_hit}")?;
+
+        writeln!(w, "end_of_record")
+    }
+}
+
+impl Hook for Coverage {
+    fn before_stmt(&mut self, _scopes: &ScopeStack, (_, span): &Stmt) {
+        if let Some(n) = self.stmts.get_mut(&span.loc()) {
+            *n += 1;
+        }
+    }
+
+    fn branch_taken(&mut self, if_loc: Location, branch: usize) {
+        let maybe_n =
+            self.branches.get_mut(&if_loc)
+                .and_then(|counts| counts.get_mut(branch));
+        if let Some(n) = maybe_n {
+            *n += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_write_lcov() {
+        let src = "\
+fn sign(n) {
+    if n < 0 {
+        return -1;
+    } else if n == 0 {
+        return 0;
+    }
+    return 1;
+}
+fn unused(f) {
+    if f { return 1; }
+}
+x := [sign(-2), sign(3)];
+";
+        let prog = crate::parse_script(src)
+            .expect("couldn't parse source");
+        let coverage = Arc::new(Mutex::new(Coverage::new("test.sd", &prog)));
+
+        crate::eval_script(
+            PathBuf::new(),
+            Path::new("test.sd"),
+            &[],
+            &prog,
+            Some(coverage.clone()),
+        )
+            .expect("couldn't evaluate source");
+
+        let coverage =
+            Arc::try_unwrap(coverage)
+                .ok()
+                .expect("coverage shouldn't be shared")
+                .into_inner()
+                .expect("coverage shouldn't be poisoned");
+        let mut lcov = vec![];
+        coverage.write_lcov(&mut lcov)
+            .expect("couldn't write lcov");
+        assert_eq!(
+            String::from_utf8_lossy(&lcov),
+            "\
+TN:
+SF:test.sd
+BRDA:2,0,0,1
+BRDA:2,0,1,0
+BRDA:2,0,2,1
+BRDA:10,1,0,-
+BRDA:10,1,1,-
+BRF:5
+BRH:2
+DA:1,1
+DA:2,2
+DA:3,1
+DA:5,0
+DA:7,1
+DA:9,1
+DA:10,0
+DA:12,1
+LF:8
+LH:6
+end_of_record
+",
+        );
+    }
+}
@@ -0,0 +1,964 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::io::Error as IoError;
+use std::io::Write;
+use std::process;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+use snafu::ResultExt;
+use snafu::Snafu;
+
+use crate::builtins::fns;
+use crate::builtins::fns::Stream;
+use crate::eval::hook::Hook;
+use crate::eval::value::SourcedValue;
+use crate::eval::value::Value;
+use crate::json;
+use crate::json::Json;
+use crate::lock_deref;
+use crate::rpc;
+use super::Client;
+use super::Debugger;
+use super::Frame;
+use super::Resume;
+use super::StopReason;
+
+// Seed programs are evaluated on a single thread, which is identified by this
+// ID.
+const THREAD_ID: i64 = 1;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    RpcFailed{source: rpc::Error},
+    #[snafu(display("couldn't decode message: {}:{}: {}", line, col, msg))]
+    DecodeMessageFailed{line: usize, col: usize, msg: String},
+}
+
+pub type SharedConnection = Arc<Mutex<Connection>>;
+
+// `Connection` is a connection to a client of the Debug Adapter Protocol.
+pub struct Connection {
+    // `requests` receives the requests that are read from the client by a
+    // separate thread, so that a `pause` request can be received while the
+    // script is being evaluated.
+    requests: Receiver<Result<Request, Error>>,
+    // `pending` contains the requests that were received while checking for
+    // a `pause` request, and that haven't been handled yet.
+    pending: VecDeque<Request>,
+    w: Box<dyn Write + Send>,
+    seq: i64,
+}
+
+impl Connection {
+    pub fn new(r: Box<dyn BufRead + Send>, w: Box<dyn Write + Send>)
+        -> SharedConnection
+    {
+        let (tx, requests) = mpsc::channel();
+        thread::spawn(move || send_requests(r, &tx));
+
+        Arc::new(Mutex::new(Connection{
+            requests,
+            pending: VecDeque::new(),
+            w,
+            seq: 0,
+        }))
+    }
+
+    // `read_request` returns the next request from the client, or `None` if
+    // the connection is closed.
+    fn read_request(&mut self) -> Result<Option<Request>, Error> {
+        if let Some(req) = self.pending.pop_front() {
+            return Ok(Some(req));
+        }
+
+        match self.requests.recv() {
+            Ok(result) => result.map(Some),
+            // The reading thread only stops once the connection is closed or
+            // can't be read from.
+            Err(_) => Ok(None),
+        }
+    }
+
+    // `pause_requested` returns `true` if a `pause` request has been received
+    // from the client, in which case it responds to the request. It doesn't
+    // wait for requests, and other requests that have been received are
+    // handled the next time that evaluation stops.
+    fn pause_requested(&mut self) -> Result<bool, Error> {
+        while let Ok(result) = self.requests.try_recv() {
+            let req = result?;
+            if req.command == "pause" {
+                self.respond(&req, json::new_object(vec![]))?;
+
+                return Ok(true);
+            }
+
+            self.pending.push_back(req);
+        }
+
+        Ok(false)
+    }
+
+    fn send(&mut self, mut props: Vec<(&str, Json)>) -> Result<(), Error> {
+        self.seq += 1;
+        props.push(("seq", Json::Int(self.seq)));
+
+        rpc::write_message(&mut self.w, &json::new_object(props))
+            .context(RpcFailed)?;
+
+        Ok(())
+    }
+
+    fn respond(&mut self, req: &Request, body: Json) -> Result<(), Error> {
+        self.send(vec![
+            ("type", Json::Str("response".to_string())),
+            ("request_seq", Json::Int(req.seq)),
+            ("success", Json::Bool(true)),
+            ("command", Json::Str(req.command.clone())),
+            ("body", body),
+        ])
+    }
+
+    fn respond_with_error(&mut self, req: &Request, msg: &str)
+        -> Result<(), Error>
+    {
+        self.send(vec![
+            ("type", Json::Str("response".to_string())),
+            ("request_seq", Json::Int(req.seq)),
+            ("success", Json::Bool(false)),
+            ("command", Json::Str(req.command.clone())),
+            ("message", Json::Str(msg.to_string())),
+        ])
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> Result<(), Error> {
+        self.send(vec![
+            ("type", Json::Str("event".to_string())),
+            ("event", Json::Str(event.to_string())),
+            ("body", body),
+        ])
+    }
+
+    // `output` sends `text` to the client as output of the script.
+    pub fn output(&mut self, stream: Stream, text: &str) -> Result<(), Error> {
+        let category =
+            match stream {
+                Stream::Stdout => "stdout",
+                Stream::Stderr => "stderr",
+            };
+
+        self.event("output", json::new_object(vec![
+            ("category", Json::Str(category.to_string())),
+            ("output", Json::Str(text.to_string())),
+        ]))
+    }
+
+    // `respond_to_common` responds to `req` if it can be handled at any
+    // point in a debugging session, and returns `true` if it did.
+    fn respond_to_common(&mut self, req: &Request) -> Result<bool, Error> {
+        match req.command.as_str() {
+            "threads" => {
+                let thread = json::new_object(vec![
+                    ("id", Json::Int(THREAD_ID)),
+                    ("name", Json::Str("main".to_string())),
+                ]);
+
+                self.respond(req, json::new_object(vec![
+                    ("threads", Json::List(vec![thread])),
+                ]))?;
+            },
+            "setExceptionBreakpoints" => {
+                self.respond(req, json::new_object(vec![]))?;
+            },
+            "disconnect" | "terminate" => {
+                // The script is evaluated in the same process as the debug
+                // adapter, so we stop it by exiting.
+                self.respond(req, json::new_object(vec![]))?;
+                process::exit(0);
+            },
+            _ => {
+                return Ok(false);
+            },
+        }
+
+        Ok(true)
+    }
+}
+
+struct Request {
+    seq: i64,
+    command: String,
+    args: Json,
+}
+
+// `send_requests` reads requests from `r` and sends them to `tx`, until `r` is
+// closed or can't be read from.
+fn send_requests(
+    mut r: Box<dyn BufRead + Send>,
+    tx: &Sender<Result<Request, Error>>,
+) {
+    loop {
+        match read_request(&mut r) {
+            Ok(Some(req)) => {
+                if tx.send(Ok(req)).is_err() {
+                    return;
+                }
+            },
+            Ok(None) => {
+                return;
+            },
+            Err(e) => {
+                let _ = tx.send(Err(e));
+
+                return;
+            },
+        }
+    }
+}
+
+// `read_request` returns the next request in `r`, or `None` if `r` is closed.
+fn read_request<R: BufRead>(r: &mut R) -> Result<Option<Request>, Error> {
+    let maybe_content = rpc::read_message(r)
+        .context(RpcFailed)?;
+
+    let content =
+        match maybe_content {
+            Some(content) => content,
+            None => return Ok(None),
+        };
+
+    let msg =
+        match json::decode(&content) {
+            Ok(msg) => {
+                msg
+            },
+            Err(json::DecodeError{line, col, msg}) => {
+                return Err(Error::DecodeMessageFailed{line, col, msg});
+            },
+        };
+
+    let command =
+        msg.get("command")
+            .and_then(Json::as_str)
+            .unwrap_or("")
+            .to_string();
+    let seq = msg.get("seq").and_then(Json::as_int).unwrap_or(0);
+    let args = msg.get("arguments").cloned().unwrap_or(Json::Null);
+
+    Ok(Some(Request{seq, command, args}))
+}
+
+// `Launch` contains the configuration of a debugging session, which is
+// received before the script is evaluated.
+struct Launch {
+    breakpoints: BTreeSet<usize>,
+    stop_on_entry: bool,
+}
+
+// `redirect_output` sends all subsequent output of the script to the client
+// over `conn`, because the standard output is used to communicate with the
+// client.
+pub fn redirect_output(conn: SharedConnection) {
+    fns::redirect_output(Box::new(move |stream, text| {
+        conn.try_lock()
+            .unwrap()
+            .output(stream, text)
+            .map_err(|e| IoError::other(e.to_string()))
+    }));
+}
+
+// `serve` runs a debugging session over `conn` for the script at `path`.
+// `run` is called with the debugger once the client has configured the
+// session, and should evaluate the script and return its exit code. `serve`
+// returns the exit code of the script, or 0 if the client disconnected before
+// the script was run.
+pub fn serve<F>(conn: &SharedConnection, path: &str, run: F)
+    -> Result<i32, Error>
+where
+    F: FnOnce(Arc<Mutex<dyn Hook>>) -> i32,
+{
+    let launch =
+        match configure(conn)? {
+            Some(launch) => launch,
+            None => return Ok(0),
+        };
+
+    let debugger = Debugger::new(
+        Dap::new(conn.clone(), path),
+        launch.breakpoints,
+        launch.stop_on_entry,
+    );
+    let exit_code = run(Arc::new(Mutex::new(debugger)));
+
+    finish(conn, exit_code)?;
+
+    Ok(exit_code)
+}
+
+// `configure` handles the requests that initialise a debugging session, and
+// returns the configuration of the session once the client has finished
+// configuring it. It returns `None` if the connection is closed before then.
+fn configure(conn: &SharedConnection) -> Result<Option<Launch>, Error> {
+    let mut conn = conn.try_lock().unwrap();
+
+    let mut launch =
+        Launch{breakpoints: BTreeSet::new(), stop_on_entry: false};
+    let mut launched = false;
+    let mut configured = false;
+    while !launched || !configured {
+        let req =
+            match conn.read_request()? {
+                Some(req) => req,
+                None => return Ok(None),
+            };
+
+        if conn.respond_to_common(&req)? {
+            continue;
+        }
+
+        match req.command.as_str() {
+            "initialize" => {
+                conn.respond(&req, json::new_object(vec![
+                    ("supportsConfigurationDoneRequest", Json::Bool(true)),
+                ]))?;
+                conn.event("initialized", json::new_object(vec![]))?;
+            },
+            "launch" => {
+                launch.stop_on_entry =
+                    req.args.get("stopOnEntry") == Some(&Json::Bool(true));
+                launched = true;
+
+                conn.respond(&req, json::new_object(vec![]))?;
+            },
+            "setBreakpoints" => {
+                let body = set_breakpoints(&req, &mut launch.breakpoints);
+
+                conn.respond(&req, body)?;
+            },
+            "configurationDone" => {
+                configured = true;
+
+                conn.respond(&req, json::new_object(vec![]))?;
+            },
+            _ => {
+                let msg = format!("unsupported command '{}'", req.command);
+
+                conn.respond_with_error(&req, &msg)?;
+            },
+        }
+    }
+
+    Ok(Some(launch))
+}
+
+// `finish` tells the client that the script exited with `exit_code`, and then
+// handles requests until the client disconnects.
+fn finish(conn: &SharedConnection, exit_code: i32) -> Result<(), Error> {
+    let mut conn = conn.try_lock().unwrap();
+
+    conn.event("exited", json::new_object(vec![
+        ("exitCode", Json::Int(i64::from(exit_code))),
+    ]))?;
+    conn.event("terminated", json::new_object(vec![]))?;
+
+    while let Some(req) = conn.read_request()? {
+        if req.command == "disconnect" {
+            conn.respond(&req, json::new_object(vec![]))?;
+
+            break;
+        }
+
+        if !conn.respond_to_common(&req)? {
+            conn.respond_with_error(&req, "the script has exited")?;
+        }
+    }
+
+    Ok(())
+}
+
+// `set_breakpoints` replaces `breakpoints` with the breakpoints in `req`, and
+// returns the body of the response to `req`.
+fn set_breakpoints(req: &Request, breakpoints: &mut BTreeSet<usize>) -> Json {
+    breakpoints.clear();
+
+    let mut verified = vec![];
+    if let Some(Json::List(reqs)) = req.args.get("breakpoints") {
+        for bp in reqs {
+            let maybe_line =
+                bp.get("line")
+                    .and_then(Json::as_int)
+                    .and_then(|line| usize::try_from(line).ok());
+
+            if let Some(line) = maybe_line {
+                breakpoints.insert(line);
+                verified.push(json::new_object(vec![
+                    ("verified", Json::Bool(true)),
+                    ("line", Json::Int(to_i64(line))),
+                ]));
+            }
+        }
+    }
+
+    json::new_object(vec![("breakpoints", Json::List(verified))])
+}
+
+// `Dap` is a `Client` that is controlled over the Debug Adapter Protocol.
+struct Dap {
+    conn: SharedConnection,
+    path: String,
+    // `handles` contains the values that the client can request the
+    // variables of, where the `variablesReference` of each value is its index
+    // plus one. `handles` is cleared whenever evaluation is resumed.
+    handles: Vec<Handle>,
+    // `detached` is `true` if the connection to the client was lost, in
+    // which case evaluation is no longer stopped.
+    detached: bool,
+}
+
+enum Handle {
+    Frame(usize),
+    Value(Value),
+}
+
+impl Dap {
+    // `new` returns a `Dap` that communicates over `conn`, where `path` is the
+    // path of the script that is being debugged.
+    fn new(conn: SharedConnection, path: &str) -> Self {
+        Dap{conn, path: path.to_string(), handles: vec![], detached: false}
+    }
+
+    // `handle` handles `req`, and returns how evaluation should be resumed if
+    // `req` resumes evaluation.
+    fn handle(
+        &mut self,
+        conn: &mut Connection,
+        req: &Request,
+        frames: &[Frame],
+        breakpoints: &mut BTreeSet<usize>,
+    )
+        -> Result<Option<Resume>, Error>
+    {
+        if conn.respond_to_common(req)? {
+            return Ok(None);
+        }
+
+        let maybe_resume =
+            match req.command.as_str() {
+                "continue" => Some(Resume::Continue),
+                "next" => Some(Resume::StepOver),
+                "stepIn" => Some(Resume::StepIn),
+                "stepOut" => Some(Resume::StepOut),
+                _ => None,
+            };
+        if let Some(resume) = maybe_resume {
+            conn.respond(req, json::new_object(vec![
+                ("allThreadsContinued", Json::Bool(true)),
+            ]))?;
+
+            return Ok(Some(resume));
+        }
+
+        let result =
+            match req.command.as_str() {
+                "setBreakpoints" => {
+                    Ok(set_breakpoints(req, breakpoints))
+                },
+                "stackTrace" => {
+                    Ok(self.stack_trace(frames))
+                },
+                "scopes" => {
+                    self.scopes(req, frames)
+                },
+                "variables" => {
+                    self.variables(req, frames)
+                },
+                "evaluate" => {
+                    self.evaluate(req, frames)
+                },
+                // We're already paused, so there's nothing to do.
+                "pause" => {
+                    Ok(json::new_object(vec![]))
+                },
+                _ => {
+                    Err(format!("unsupported command '{}'", req.command))
+                },
+            };
+
+        match result {
+            Ok(body) => {
+                conn.respond(req, body)?;
+            },
+            Err(msg) => {
+                conn.respond_with_error(req, &msg)?;
+            },
+        }
+
+        Ok(None)
+    }
+
+    fn stack_trace(&self, frames: &[Frame]) -> Json {
+        let source = json::new_object(vec![
+            ("path", Json::Str(self.path.clone())),
+        ]);
+
+        let stack_frames =
+            frames.iter()
+                .rev()
+                .enumerate()
+                .map(|(i, frame)| {
+                    let (line, col) = frame.loc;
+
+                    json::new_object(vec![
+                        ("id", Json::Int(to_i64(i))),
+                        ("name", Json::Str(frame.func.clone())),
+                        ("source", source.clone()),
+                        ("line", Json::Int(to_i64(line))),
+                        ("column", Json::Int(to_i64(col))),
+                    ])
+                })
+                .collect();
+
+        json::new_object(vec![
+            ("stackFrames", Json::List(stack_frames)),
+            ("totalFrames", Json::Int(to_i64(frames.len()))),
+        ])
+    }
+
+    fn scopes(&mut self, req: &Request, frames: &[Frame])
+        -> Result<Json, String>
+    {
+        let id = frame_index(req, frames)?;
+        self.handles.push(Handle::Frame(id));
+
+        let scope = json::new_object(vec![
+            ("name", Json::Str("Locals".to_string())),
+            ("variablesReference", Json::Int(to_i64(self.handles.len()))),
+            ("expensive", Json::Bool(false)),
+        ]);
+
+        Ok(json::new_object(vec![("scopes", Json::List(vec![scope]))]))
+    }
+
+    fn variables(&mut self, req: &Request, frames: &[Frame])
+        -> Result<Json, String>
+    {
+        let maybe_handle =
+            req.args.get("variablesReference")
+                .and_then(Json::as_int)
+                .and_then(|n| usize::try_from(n).ok())
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|i| self.handles.get(i));
+
+        let vars: Vec<(String, SourcedValue)> =
+            match maybe_handle {
+                Some(Handle::Frame(id)) => {
+                    frames[frames.len() - 1 - id].scopes
+                        .vars()
+                        .into_iter()
+                        // We hide builtin functions because they're defined
+                        // in every program.
+                        .filter(|(_, v)| {
+                            !matches!(
+                                v.v,
+                                Value::BuiltinFunc{..}
+                                    | Value::EvalBuiltinFunc{..},
+                            )
+                        })
+                        .collect()
+                },
+                Some(Handle::Value(Value::List(list))) => {
+                    lock_deref!(list)
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| (format!("[{i}]"), v.clone()))
+                        .collect()
+                },
+                Some(Handle::Value(Value::Object(object))) => {
+                    lock_deref!(object)
+                        .iter()
+                        .map(|(name, v)| (name.clone(), v.clone()))
+                        .collect()
+                },
+                Some(Handle::Value(_)) => {
+                    vec![]
+                },
+                None => {
+                    return Err("invalid variables reference".to_string());
+                },
+            };
+
+        let variables =
+            vars.into_iter()
+                .map(|(name, v)| {
+                    let (value, reference) = self.render_var(&v.v);
+
+                    json::new_object(vec![
+                        ("name", Json::Str(name)),
+                        ("value", Json::Str(value)),
+                        ("variablesReference", Json::Int(to_i64(reference))),
+                    ])
+                })
+                .collect();
+
+        Ok(json::new_object(vec![("variables", Json::List(variables))]))
+    }
+
+    // `evaluate` only supports the names of variables, which are looked up in
+    // the requested frame.
+    fn evaluate(&mut self, req: &Request, frames: &[Frame])
+        -> Result<Json, String>
+    {
+        let id = frame_index(req, frames)?;
+        let expr =
+            req.args.get("expression")
+                .and_then(Json::as_str)
+                .unwrap_or("")
+                .trim();
+
+        let maybe_v =
+            frames[frames.len() - 1 - id].scopes
+                .vars()
+                .into_iter()
+                .find(|(name, _)| name == expr);
+
+        match maybe_v {
+            Some((_, v)) => {
+                let (value, reference) = self.render_var(&v.v);
+
+                Ok(json::new_object(vec![
+                    ("result", Json::Str(value)),
+                    ("variablesReference", Json::Int(to_i64(reference))),
+                ]))
+            },
+            None => {
+                Err(format!("'{expr}' is not defined"))
+            },
+        }
+    }
+
+    // `render_var` returns the rendering of `v`, along with the reference
+    // that the client can use to request the items of `v`, or 0 if `v` has no
+    // items.
+    fn render_var(&mut self, v: &Value) -> (String, usize) {
+        let has_items =
+            match v {
+                Value::List(list) => !lock_deref!(list).is_empty(),
+                Value::Object(object) => !lock_deref!(object).is_empty(),
+                _ => false,
+            };
+
+        let reference =
+            if has_items {
+                self.handles.push(Handle::Value(v.clone()));
+
+                self.handles.len()
+            } else {
+                0
+            };
+
+        (fns::render_repr(v), reference)
+    }
+
+    // `serve_stop` tells the client that evaluation stopped, and then handles
+    // requests until the client resumes evaluation. It returns `None` if the
+    // connection is closed before then.
+    fn serve_stop(
+        &mut self,
+        conn: &mut Connection,
+        reason: StopReason,
+        frames: &[Frame],
+        breakpoints: &mut BTreeSet<usize>,
+    )
+        -> Result<Option<Resume>, Error>
+    {
+        let reason =
+            match reason {
+                StopReason::Entry => "entry",
+                StopReason::Breakpoint => "breakpoint",
+                StopReason::Step => "step",
+                StopReason::Pause => "pause",
+            };
+        conn.event("stopped", json::new_object(vec![
+            ("reason", Json::Str(reason.to_string())),
+            ("threadId", Json::Int(THREAD_ID)),
+            ("allThreadsStopped", Json::Bool(true)),
+        ]))?;
+
+        while let Some(req) = conn.read_request()? {
+            let maybe_resume = self.handle(conn, &req, frames, breakpoints)?;
+            if let Some(resume) = maybe_resume {
+                return Ok(Some(resume));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Client for Dap {
+    fn pause_requested(&mut self) -> bool {
+        if self.detached {
+            return false;
+        }
+
+        let result = self.conn.try_lock().unwrap().pause_requested();
+
+        match result {
+            Ok(paused) => {
+                paused
+            },
+            Err(_) => {
+                self.detached = true;
+
+                false
+            },
+        }
+    }
+
+    fn stopped(
+        &mut self,
+        reason: StopReason,
+        frames: &[Frame],
+        breakpoints: &mut BTreeSet<usize>,
+    )
+        -> Resume
+    {
+        if self.detached {
+            return Resume::Continue;
+        }
+
+        let conn = self.conn.clone();
+        let result =
+            self.serve_stop(
+                &mut conn.try_lock().unwrap(),
+                reason,
+                frames,
+                breakpoints,
+            );
+
+        self.handles.clear();
+
+        match result {
+            Ok(Some(resume)) => {
+                resume
+            },
+            // If the connection to the client is lost then we let the script
+            // run to completion.
+            Ok(None) | Err(_) => {
+                self.detached = true;
+
+                Resume::Continue
+            },
+        }
+    }
+}
+
+// `frame_index` returns the index of the frame that `req` refers to, where
+// the innermost frame has the index 0.
+fn frame_index(req: &Request, frames: &[Frame]) -> Result<usize, String> {
+    let id = req.args.get("frameId").and_then(Json::as_int).unwrap_or(0);
+
+    usize::try_from(id)
+        .ok()
+        .filter(|id| *id < frames.len())
+        .ok_or_else(|| format!("invalid frame ID '{id}'"))
+}
+
+fn to_i64(n: usize) -> i64 {
+    i64::try_from(n).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Cursor;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    // `SharedBuf` is a `Write` whose contents can be read after it has been
+    // passed to a `Connection`.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn request(seq: i64, command: &str, args: &str) -> Json {
+        json::decode(&format!(
+            "{{\"seq\": {seq}, \"type\": \"request\", \
+             \"command\": \"{command}\", \"arguments\": {args}}}",
+        ))
+            .expect("couldn't decode request")
+    }
+
+    // `connect` returns a `Connection` that reads `reqs` and writes to the
+    // returned buffer.
+    fn connect(reqs: &[Json]) -> (SharedConnection, SharedBuf) {
+        let mut input = vec![];
+        for req in reqs {
+            rpc::write_message(&mut input, req)
+                .expect("couldn't write request");
+        }
+        let output = SharedBuf::default();
+        let conn = Connection::new(
+            Box::new(Cursor::new(input)),
+            Box::new(output.clone()),
+        );
+
+        (conn, output)
+    }
+
+    // `render_messages` returns the command or event name of each message in
+    // `output`, followed by its body or error message.
+    fn render_messages(output: &SharedBuf) -> Vec<String> {
+        let output = output.0.lock().unwrap().clone();
+        let mut r = Cursor::new(output);
+        let mut rendered = vec![];
+        while let Some(content) = rpc::read_message(&mut r)
+            .expect("couldn't read message")
+        {
+            let msg =
+                json::decode(&content)
+                    .expect("couldn't decode message");
+            let name =
+                msg.get("command")
+                    .or_else(|| msg.get("event"))
+                    .and_then(Json::as_str)
+                    .unwrap_or("")
+                    .to_string();
+            let body =
+                msg.get("body")
+                    .or_else(|| msg.get("message"))
+                    .map_or_else(String::new, |b| json::encode(b, None));
+
+            rendered.push(format!("{name} {body}"));
+        }
+
+        rendered
+    }
+
+    #[test]
+    fn test_serve() {
+        let src = "\
+fn add(a, b) {
+    c := a + b;
+    return c;
+}
+x := add(1, 2);
+";
+        let reqs = [
+            request(1, "initialize", "{}"),
+            request(2, "launch", "{}"),
+            request(
+                3,
+                "setBreakpoints",
+                "{\"breakpoints\": [{\"line\": 3}]}",
+            ),
+            request(4, "configurationDone", "{}"),
+            request(5, "stackTrace", "{\"threadId\": 1}"),
+            request(6, "scopes", "{\"frameId\": 0}"),
+            request(7, "variables", "{\"variablesReference\": 1}"),
+            request(8, "evaluate", "{\"expression\": \"z\"}"),
+            request(9, "continue", "{\"threadId\": 1}"),
+            request(10, "disconnect", "{}"),
+        ];
+        let (conn, output) = connect(&reqs);
+        let prog = crate::parse_script(src)
+            .expect("couldn't parse source");
+
+        let exit_code =
+            serve(&conn, "/test.sd", |hook| {
+                let result = crate::eval_script(
+                    PathBuf::new(),
+                    Path::new("test.sd"),
+                    &[],
+                    &prog,
+                    Some(hook),
+                );
+
+                i32::from(result.is_err())
+            })
+                .expect("couldn't serve");
+
+        assert_eq!(exit_code, 0);
+        assert_eq!(
+            render_messages(&output),
+            vec![
+                "initialize {\"supportsConfigurationDoneRequest\":true}",
+                "initialized {}",
+                "launch {}",
+                "setBreakpoints {\"breakpoints\":[{\"line\":3,\
+                 \"verified\":true}]}",
+                "configurationDone {}",
+                "stopped {\"allThreadsStopped\":true,\
+                 \"reason\":\"breakpoint\",\"threadId\":1}",
+                "stackTrace {\"stackFrames\":[{\"column\":5,\"id\":0,\
+                 \"line\":3,\"name\":\"add\",\
+                 \"source\":{\"path\":\"/test.sd\"}},\
+                 {\"column\":6,\"id\":1,\"line\":5,\"name\":\"<root>\",\
+                 \"source\":{\"path\":\"/test.sd\"}}],\"totalFrames\":2}",
+                "scopes {\"scopes\":[{\"expensive\":false,\
+                 \"name\":\"Locals\",\"variablesReference\":1}]}",
+                "variables {\"variables\":[\
+                 {\"name\":\"a\",\"value\":\"1\",\"variablesReference\":0},\
+                 {\"name\":\"add\",\
+                 \"value\":\"<function 'add' (arity 2)>\",\
+                 \"variablesReference\":0},\
+                 {\"name\":\"args\",\"value\":\"[]\",\
+                 \"variablesReference\":0},\
+                 {\"name\":\"b\",\"value\":\"2\",\"variablesReference\":0},\
+                 {\"name\":\"c\",\"value\":\"3\",\"variablesReference\":0},\
+                 {\"name\":\"env\",\"value\":\"{\\\"get\\\": \
+                 <built-in function 'env.get'>, \\\"vars\\\": \
+                 <built-in function 'env.vars'>}\",\
+                 \"variablesReference\":2}]}",
+                "evaluate \"'z' is not defined\"",
+                "continue {\"allThreadsContinued\":true}",
+                "exited {\"exitCode\":0}",
+                "terminated {}",
+                "disconnect {}",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_pause_requested() {
+        let reqs = [
+            request(1, "threads", "{}"),
+            request(2, "pause", "{\"threadId\": 1}"),
+        ];
+        let (conn, output) = connect(&reqs);
+        let mut conn = conn.try_lock().unwrap();
+
+        // Requests are read on a separate thread, so we wait until the
+        // `pause` request has been received.
+        while !conn.pause_requested().expect("couldn't check for pause") {
+            thread::yield_now();
+        }
+
+        let req =
+            conn.read_request()
+                .expect("couldn't read request")
+                .expect("expected a request");
+        assert_eq!(req.command, "threads");
+        assert!(matches!(conn.read_request(), Ok(None)));
+        assert_eq!(render_messages(&output), vec!["pause {}"]);
+    }
+}
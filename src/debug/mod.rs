@@ -0,0 +1,409 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeSet;
+
+pub mod dap;
+pub mod terminal;
+
+use crate::ast::Location;
+use crate::ast::Stmt;
+use crate::eval::hook::Hook;
+use crate::eval::scope::ScopeStack;
+use crate::eval::value::SourcedValue;
+
+// `Frame` is the state of a function call that is being evaluated.
+pub struct Frame {
+    pub func: String,
+    // `loc` is the location of the statement that is being evaluated in this
+    // frame, or of the call that is being evaluated if this isn't the
+    // innermost frame.
+    pub loc: Location,
+    // `scopes` contains the variables that are visible at `loc`.
+    pub scopes: ScopeStack,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    Entry,
+    Breakpoint,
+    Step,
+    Pause,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resume {
+    Continue,
+    // `StepIn` stops at the next statement that is evaluated.
+    StepIn,
+    // `StepOver` stops at the next statement that is evaluated in the current
+    // frame or an outer frame.
+    StepOver,
+    // `StepOut` stops at the next statement that is evaluated in an outer
+    // frame.
+    StepOut,
+}
+
+// `Client` is the interface between a `Debugger` and its user.
+pub trait Client {
+    // `pause_requested` is called before each statement that evaluation
+    // doesn't otherwise stop at, and returns `true` if the user has asked for
+    // evaluation to stop there. The default implementation returns `false`.
+    fn pause_requested(&mut self) -> bool {
+        false
+    }
+
+    // `stopped` is called when evaluation stops before a statement, and
+    // returns how evaluation should be resumed. `frames` contains the frames
+    // of the calls that are being evaluated, with the innermost frame last.
+    // `breakpoints` contains the lines that evaluation stops at, and may be
+    // updated before evaluation is resumed.
+    fn stopped(
+        &mut self,
+        reason: StopReason,
+        frames: &[Frame],
+        breakpoints: &mut BTreeSet<usize>,
+    )
+        -> Resume;
+}
+
+// `Debugger` is a `Hook` that stops evaluation at breakpoints and after steps,
+// and passes control to its `Client` when it does.
+pub struct Debugger<C> {
+    client: C,
+    frames: Vec<Frame>,
+    breakpoints: BTreeSet<usize>,
+    mode: Mode,
+}
+
+enum Mode {
+    Entry,
+    Continue,
+    StepIn,
+    // `depth` is the number of frames at the point that stepping started.
+    StepOver{depth: usize},
+    StepOut{depth: usize},
+}
+
+impl<C: Client> Debugger<C> {
+    // `new` returns a `Debugger` that stops before the first statement of the
+    // program if `stop_on_entry` is `true`.
+    pub fn new(client: C, breakpoints: BTreeSet<usize>, stop_on_entry: bool)
+        -> Self
+    {
+        let root = Frame{
+            func: "<root>".to_string(),
+            loc: (0, 0),
+            scopes: ScopeStack::new(vec![]),
+        };
+        let mode = if stop_on_entry { Mode::Entry } else { Mode::Continue };
+
+        Debugger{client, frames: vec![root], breakpoints, mode}
+    }
+}
+
+impl<C: Client> Hook for Debugger<C> {
+    fn before_stmt(&mut self, scopes: &ScopeStack, (_, span): &Stmt) {
+        let loc = span.loc();
+        let depth = self.frames.len();
+
+        // We only stop at a breakpoint when its line is entered, either from
+        // another line or by looping back to an earlier statement, so that
+        // evaluation doesn't stop at every statement on the line.
+        let mut entered_line = true;
+        if let Some(frame) = self.frames.last_mut() {
+            entered_line = frame.loc.0 != loc.0 || loc <= frame.loc;
+            frame.loc = loc;
+            frame.scopes = scopes.clone();
+        }
+
+        let at_breakpoint =
+            entered_line && self.breakpoints.contains(&loc.0);
+        let maybe_reason =
+            match self.mode {
+                Mode::Entry => {
+                    Some(StopReason::Entry)
+                },
+                _ if at_breakpoint => {
+                    Some(StopReason::Breakpoint)
+                },
+                Mode::StepIn => {
+                    Some(StopReason::Step)
+                },
+                Mode::StepOver{depth: d} if depth <= d => {
+                    Some(StopReason::Step)
+                },
+                Mode::StepOut{depth: d} if depth < d => {
+                    Some(StopReason::Step)
+                },
+                _ if self.client.pause_requested() => {
+                    Some(StopReason::Pause)
+                },
+                _ => {
+                    None
+                },
+            };
+
+        if let Some(reason) = maybe_reason {
+            let resume = self.client.stopped(
+                reason,
+                &self.frames,
+                &mut self.breakpoints,
+            );
+
+            self.mode =
+                match resume {
+                    Resume::Continue => Mode::Continue,
+                    Resume::StepIn => Mode::StepIn,
+                    Resume::StepOver => Mode::StepOver{depth},
+                    Resume::StepOut => Mode::StepOut{depth},
+                };
+        }
+    }
+
+    fn enter_call(
+        &mut self,
+        func_name: Option<&str>,
+        call_loc: Location,
+        _args: &[SourcedValue],
+    ) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.loc = call_loc;
+        }
+
+        self.frames.push(Frame{
+            func: func_name.unwrap_or("<unnamed function>").to_string(),
+            loc: (0, 0),
+            scopes: ScopeStack::new(vec![]),
+        });
+    }
+
+    fn exit_call(&mut self, _result: Option<&SourcedValue>) {
+        if self.frames.len() > 1 {
+            self.frames.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::VecDeque;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    const SRC: &str = "\
+fn add(a, b) {
+    c := a + b;
+    return c;
+}
+x := add(1, 2);
+y := add(x, 3);
+";
+
+    // `Recorder` is a `Client` that records where evaluation stopped, and
+    // resumes evaluation using the next of a predefined list of `Resume`s.
+    struct Recorder {
+        resumes: VecDeque<Resume>,
+        stops: Vec<(StopReason, String, Location)>,
+    }
+
+    impl Client for Recorder {
+        fn stopped(
+            &mut self,
+            reason: StopReason,
+            frames: &[Frame],
+            _breakpoints: &mut BTreeSet<usize>,
+        )
+            -> Resume
+        {
+            let frame =
+                frames.last()
+                    .expect("expected a frame");
+            self.stops.push((reason, frame.func.clone(), frame.loc));
+
+            self.resumes.pop_front().unwrap_or(Resume::Continue)
+        }
+    }
+
+    // `debug` evaluates `src` using a `Debugger` with the given
+    // configuration, and returns where evaluation stopped.
+    pub fn debug<C: Client + 'static>(
+        src: &str,
+        client: C,
+        breakpoints: &[usize],
+        stop_on_entry: bool,
+    )
+        -> C
+    {
+        let prog = crate::parse_script(src)
+            .expect("couldn't parse source");
+        let debugger = Arc::new(Mutex::new(Debugger::new(
+            client,
+            breakpoints.iter().copied().collect(),
+            stop_on_entry,
+        )));
+
+        crate::eval_script(
+            PathBuf::new(),
+            Path::new("test.sd"),
+            &[],
+            &prog,
+            Some(debugger.clone()),
+        )
+            .expect("couldn't evaluate source");
+
+        let debugger =
+            Arc::try_unwrap(debugger)
+                .ok()
+                .expect("debugger shouldn't be shared")
+                .into_inner()
+                .expect("debugger shouldn't be poisoned");
+
+        debugger.client
+    }
+
+    #[test]
+    fn test_debugger_stops() {
+        use Resume::Continue;
+        use Resume::StepIn;
+        use Resume::StepOut;
+        use Resume::StepOver;
+        use StopReason::Breakpoint;
+        use StopReason::Entry;
+        use StopReason::Step;
+
+        // Each test contains the breakpoints, whether to stop on entry, the
+        // ways that evaluation is resumed, and the expected stops.
+        type Stop<'a> = (StopReason, &'a str, Location);
+        type Test<'a> = (&'a [usize], bool, &'a [Resume], &'a [Stop<'a>]);
+
+        let tests: &[Test] = &[
+            (
+                &[],
+                false,
+                &[],
+                &[],
+            ),
+            (
+                &[],
+                true,
+                &[StepOver, StepOver],
+                &[
+                    (Entry, "<root>", (1, 1)),
+                    (Step, "<root>", (5, 1)),
+                    (Step, "<root>", (6, 1)),
+                ],
+            ),
+            (
+                &[],
+                true,
+                &[StepOver, StepIn, StepIn, StepIn],
+                &[
+                    (Entry, "<root>", (1, 1)),
+                    (Step, "<root>", (5, 1)),
+                    (Step, "add", (2, 5)),
+                    (Step, "add", (3, 5)),
+                    (Step, "<root>", (6, 1)),
+                ],
+            ),
+            (
+                &[3],
+                false,
+                &[Continue],
+                &[
+                    (Breakpoint, "add", (3, 5)),
+                    (Breakpoint, "add", (3, 5)),
+                ],
+            ),
+            (
+                &[2],
+                false,
+                &[StepOut],
+                &[
+                    (Breakpoint, "add", (2, 5)),
+                    (Step, "<root>", (6, 1)),
+                    (Breakpoint, "add", (2, 5)),
+                ],
+            ),
+            (
+                &[2, 6],
+                false,
+                &[StepOver, StepOver],
+                &[
+                    (Breakpoint, "add", (2, 5)),
+                    (Step, "add", (3, 5)),
+                    (Breakpoint, "<root>", (6, 1)),
+                    (Breakpoint, "add", (2, 5)),
+                ],
+            ),
+        ];
+
+        for (breakpoints, stop_on_entry, resumes, exp) in tests {
+            let recorder = Recorder{
+                resumes: resumes.iter().copied().collect(),
+                stops: vec![],
+            };
+
+            let act = debug(SRC, recorder, breakpoints, *stop_on_entry).stops;
+
+            let exp: Vec<(StopReason, String, Location)> =
+                exp.iter()
+                    .map(|(reason, f, loc)| (*reason, f.to_string(), *loc))
+                    .collect();
+            assert_eq!(act, exp, "incorrect stops for {breakpoints:?}");
+        }
+    }
+
+    // `Pauser` is a `Client` that requests a pause before the first
+    // statement, and records where evaluation stopped.
+    struct Pauser {
+        paused: bool,
+        stops: Vec<(StopReason, Location)>,
+    }
+
+    impl Client for Pauser {
+        fn pause_requested(&mut self) -> bool {
+            !std::mem::replace(&mut self.paused, true)
+        }
+
+        fn stopped(
+            &mut self,
+            reason: StopReason,
+            frames: &[Frame],
+            _breakpoints: &mut BTreeSet<usize>,
+        )
+            -> Resume
+        {
+            let frame =
+                frames.last()
+                    .expect("expected a frame");
+            self.stops.push((reason, frame.loc));
+
+            Resume::Continue
+        }
+    }
+
+    #[test]
+    fn test_debugger_stops_when_paused() {
+        let pauser = Pauser{paused: false, stops: vec![]};
+
+        let act = debug(SRC, pauser, &[], false).stops;
+
+        assert_eq!(act, vec![(StopReason::Pause, (1, 1))]);
+    }
+
+    #[test]
+    fn test_debugger_stops_at_breakpoints_in_loops() {
+        let src = "i := 0;\nwhile i < 3 {\n    i += 1;\n}\n";
+        let recorder = Recorder{resumes: VecDeque::new(), stops: vec![]};
+
+        let act = debug(src, recorder, &[3], false).stops;
+
+        assert_eq!(act.len(), 3, "incorrect stops: {act:?}");
+    }
+}
@@ -0,0 +1,387 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeSet;
+use std::io::BufRead;
+use std::io::Write;
+use std::process;
+
+use crate::builtins::fns;
+use crate::eval::value::Value;
+use super::Client;
+use super::Frame;
+use super::Resume;
+use super::StopReason;
+
+const HELP: &str = "\
+commands:
+    c, continue         continue until the next breakpoint
+    s, step             step to the next statement, entering function calls
+    n, next             step to the next statement in the current function
+    f, finish           step out of the current function
+    b, break [<line>]   set a breakpoint, or list the breakpoints
+    d, delete <line>    delete a breakpoint
+    bt, backtrace       show the active function calls
+    frame <n>           select the frame used by 'print' and 'locals'
+    p, print <name>     show the value of a variable
+    locals              show the variables in the selected frame
+    l, list             show the source around the current line
+    q, quit             stop the script and exit
+    h, help             show this message
+
+an empty line repeats the previous command";
+
+// `CONTEXT_LINES` is the number of lines shown before and after the current
+// line by the `list` command.
+const CONTEXT_LINES: usize = 3;
+
+// `Terminal` is a `Client` that is controlled by commands that are read from
+// `r`, and that writes its output to `w`.
+pub struct Terminal<R, W> {
+    path: String,
+    lines: Vec<String>,
+    r: R,
+    w: W,
+    last_cmd: String,
+}
+
+impl<R: BufRead, W: Write> Terminal<R, W> {
+    // `new` returns a `Terminal` for debugging the script at `path`, whose
+    // source is `src`.
+    pub fn new(path: &str, src: &str, r: R, w: W) -> Self {
+        Terminal{
+            path: path.to_string(),
+            lines: src.lines().map(str::to_string).collect(),
+            r,
+            w,
+            last_cmd: String::new(),
+        }
+    }
+
+    // `say` writes `msg` to the user. Errors are ignored, because there's no
+    // other way to report them to the user.
+    fn say(&mut self, msg: &str) {
+        let _ = writeln!(self.w, "{msg}");
+    }
+
+    // `read_cmd` returns the next command from the user, or `None` if there
+    // are no more commands.
+    fn read_cmd(&mut self) -> Option<String> {
+        let _ = write!(self.w, "(seed) ");
+        let _ = self.w.flush();
+
+        let mut line = String::new();
+        match self.r.read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                return None;
+            },
+            Ok(_) => {
+            },
+        }
+
+        let line = line.trim();
+        if !line.is_empty() {
+            self.last_cmd = line.to_string();
+        }
+
+        Some(self.last_cmd.clone())
+    }
+
+    fn render_frame(&self, frame: &Frame) -> String {
+        let (line, col) = frame.loc;
+
+        format!("{}:{}:{}: in '{}'", self.path, line, col, frame.func)
+    }
+
+    fn render_line(&self, line: usize, current: bool) -> Option<String> {
+        let text = self.lines.get(line.checked_sub(1)?)?;
+        let marker = if current { '>' } else { ' ' };
+
+        Some(format!("{marker} {line:>4} | {text}"))
+    }
+
+    fn list(&mut self, line: usize) {
+        let start = line.saturating_sub(CONTEXT_LINES).max(1);
+        for n in start ..= line + CONTEXT_LINES {
+            if let Some(rendered) = self.render_line(n, n == line) {
+                self.say(&rendered);
+            }
+        }
+    }
+
+    // `run_cmd` runs `cmd`, and returns how evaluation should be resumed if
+    // `cmd` resumes evaluation. `selected` is the index of the frame that is
+    // inspected by commands such as `print`.
+    fn run_cmd(
+        &mut self,
+        cmd: &str,
+        frames: &[Frame],
+        selected: &mut usize,
+        breakpoints: &mut BTreeSet<usize>,
+    )
+        -> Option<Resume>
+    {
+        let (name, arg) =
+            match cmd.split_once(' ') {
+                Some((name, arg)) => (name, arg.trim()),
+                None => (cmd, ""),
+            };
+
+        match (name, arg) {
+            ("c" | "continue", "") => {
+                return Some(Resume::Continue);
+            },
+            ("s" | "step", "") => {
+                return Some(Resume::StepIn);
+            },
+            ("n" | "next", "") => {
+                return Some(Resume::StepOver);
+            },
+            ("f" | "finish", "") => {
+                return Some(Resume::StepOut);
+            },
+            ("b" | "break", "") => {
+                if breakpoints.is_empty() {
+                    self.say("no breakpoints");
+                }
+                for line in breakpoints.iter() {
+                    self.say(&format!("breakpoint at {}:{line}", self.path));
+                }
+            },
+            ("b" | "break", line) => {
+                self.set_breakpoint(line, breakpoints);
+            },
+            ("d" | "delete", line) => {
+                self.delete_breakpoint(line, breakpoints);
+            },
+            ("bt" | "backtrace", "") => {
+                for (i, frame) in frames.iter().rev().enumerate() {
+                    let marker = if i == *selected { '*' } else { ' ' };
+                    let rendered = self.render_frame(frame);
+
+                    self.say(&format!("{marker} #{i} {rendered}"));
+                }
+            },
+            ("frame", n) => {
+                match n.parse::<usize>() {
+                    Ok(n) if n < frames.len() => {
+                        *selected = n;
+                        let rendered =
+                            self.render_frame(&frames[frames.len() - 1 - n]);
+
+                        self.say(&format!("#{n} {rendered}"));
+                    },
+                    _ => {
+                        self.say(&format!("'{n}' isn't a frame number"));
+                    },
+                }
+            },
+            ("p" | "print", name) if !name.is_empty() => {
+                self.print_var(&frames[frames.len() - 1 - *selected], name);
+            },
+            ("locals", "") => {
+                self.print_locals(&frames[frames.len() - 1 - *selected]);
+            },
+            ("l" | "list", "") => {
+                let (line, _) = frames[frames.len() - 1 - *selected].loc;
+
+                self.list(line);
+            },
+            ("q" | "quit", "") => {
+                process::exit(0);
+            },
+            ("h" | "help", "") => {
+                self.say(HELP);
+            },
+            _ => {
+                self.say(&format!(
+                    "unknown command '{cmd}'; type 'help' for a list of \
+                     commands",
+                ));
+            },
+        }
+
+        None
+    }
+
+    fn set_breakpoint(
+        &mut self,
+        line: &str,
+        breakpoints: &mut BTreeSet<usize>,
+    ) {
+        match parse_line(line) {
+            Some(line) => {
+                breakpoints.insert(line);
+                let path = &self.path;
+                self.say(&format!("breakpoint at {path}:{line}"));
+            },
+            None => {
+                self.say(&format!("'{line}' isn't a line number"));
+            },
+        }
+    }
+
+    fn delete_breakpoint(
+        &mut self,
+        line: &str,
+        breakpoints: &mut BTreeSet<usize>,
+    ) {
+        match parse_line(line) {
+            Some(line) if breakpoints.remove(&line) => {
+                self.say(&format!("deleted breakpoint at {line}"));
+            },
+            Some(line) => {
+                self.say(&format!("no breakpoint at line {line}"));
+            },
+            None => {
+                self.say(&format!("'{line}' isn't a line number"));
+            },
+        }
+    }
+
+    fn print_var(&mut self, frame: &Frame, name: &str) {
+        let maybe_v =
+            frame.scopes
+                .vars()
+                .into_iter()
+                .find(|(var, _)| var == name);
+
+        match maybe_v {
+            Some((_, v)) => {
+                self.say(&fns::render_repr(&v.v));
+            },
+            None => {
+                self.say(&format!("'{name}' is not defined"));
+            },
+        }
+    }
+
+    fn print_locals(&mut self, frame: &Frame) {
+        for (name, v) in frame.scopes.vars() {
+            // We hide builtin functions because they're defined in every
+            // program.
+            let is_builtin =
+                matches!(
+                    v.v,
+                    Value::BuiltinFunc{..} | Value::EvalBuiltinFunc{..},
+                );
+            if !is_builtin {
+                let rendered = fns::render_repr(&v.v);
+
+                self.say(&format!("{name} = {rendered}"));
+            }
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> Client for Terminal<R, W> {
+    fn stopped(
+        &mut self,
+        reason: StopReason,
+        frames: &[Frame],
+        breakpoints: &mut BTreeSet<usize>,
+    )
+        -> Resume
+    {
+        let maybe_frame = frames.last();
+        if let Some(frame) = maybe_frame {
+            let descr =
+                match reason {
+                    StopReason::Entry => "entry",
+                    StopReason::Breakpoint => "breakpoint",
+                    StopReason::Step => "step",
+                    StopReason::Pause => "pause",
+                };
+            let rendered = self.render_frame(frame);
+            self.say(&format!("stopped at {descr}: {rendered}"));
+
+            if let Some(line) = self.render_line(frame.loc.0, true) {
+                self.say(&line);
+            }
+        }
+
+        let mut selected = 0;
+        loop {
+            let cmd =
+                match self.read_cmd() {
+                    Some(cmd) => {
+                        cmd
+                    },
+                    // If there are no more commands then we let the script
+                    // run to completion.
+                    None => {
+                        breakpoints.clear();
+
+                        return Resume::Continue;
+                    },
+                };
+
+            let maybe_resume =
+                self.run_cmd(&cmd, frames, &mut selected, breakpoints);
+            if let Some(resume) = maybe_resume {
+                return resume;
+            }
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Option<usize> {
+    line.parse::<usize>()
+        .ok()
+        .filter(|n| *n > 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use crate::debug::test::debug;
+
+    #[test]
+    fn test_terminal() {
+        let src = "\
+fn add(a, b) {
+    c := a + b;
+    return c;
+}
+x := add(1, 2);
+y := [x];
+";
+        let cmds = "b 3\nc\nbt\np c\nframe 1\nlocals\np z\nl\nfoo\nn\n\n";
+        let terminal =
+            Terminal::new("test.sd", src, Cursor::new(cmds), Vec::new());
+
+        let terminal = debug(src, terminal, &[], true);
+
+        assert_eq!(
+            String::from_utf8_lossy(&terminal.w),
+            "\
+stopped at entry: test.sd:1:1: in '<root>'
+>    1 | fn add(a, b) {
+(seed) breakpoint at test.sd:3
+(seed) stopped at breakpoint: test.sd:3:5: in 'add'
+>    3 |     return c;
+(seed) * #0 test.sd:3:5: in 'add'
+  #1 test.sd:5:6: in '<root>'
+(seed) 3
+(seed) #1 test.sd:5:6: in '<root>'
+(seed) add = <function 'add' (arity 2)>
+args = []
+env = {\"get\": <built-in function 'env.get'>, \
+\"vars\": <built-in function 'env.vars'>}
+(seed) 'z' is not defined
+(seed)      2 |     c := a + b;
+     3 |     return c;
+     4 | }
+>    5 | x := add(1, 2);
+     6 | y := [x];
+(seed) unknown command 'foo'; type 'help' for a list of commands
+(seed) stopped at step: test.sd:6:1: in '<root>'
+>    6 | y := [x];
+(seed) ",
+        );
+    }
+}
@@ -0,0 +1,385 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::fmt::Write;
+
+use crate::json;
+use crate::json::Json;
+use crate::lexer::Location;
+
+const BOLD: &str = "\x1b[1m";
+const BOLD_BLUE: &str = "\x1b[1;34m";
+const BOLD_GREEN: &str = "\x1b[1;32m";
+const BOLD_RED: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+// `Diagnostic` is an error that's reported to the user, independent of the
+// format that it's rendered in.
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub msg: String,
+    // `span` is the start and end (inclusive) of the source that caused the
+    // error, if any.
+    pub span: Option<(Location, Location)>,
+    // `loc` is the location that's reported for the error, if it isn't the
+    // start of `span`, such as the operator of a failed binary operation.
+    pub loc: Option<Location>,
+    // `func` is the name of the function that the error occurred in, if the
+    // error occurred in a function.
+    pub func: Option<String>,
+    pub notes: Vec<(String, Option<Location>)>,
+    // `stacktrace` contains the calls that led to the error, starting with
+    // the most recent call.
+    pub stacktrace: Vec<Frame>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+pub struct Frame {
+    pub file: String,
+    pub loc: Location,
+    pub func: String,
+}
+
+impl Diagnostic {
+    pub fn new(code: &'static str, msg: String) -> Self {
+        Diagnostic{
+            severity: Severity::Error,
+            code,
+            msg,
+            span: None,
+            loc: None,
+            func: None,
+            notes: vec![],
+            stacktrace: vec![],
+        }
+    }
+}
+
+// `to_json` returns a JSON representation of `d`, which occurred in `file`,
+// whose source is `src`.
+pub fn to_json(file: &str, src: &str, d: &Diagnostic) -> Json {
+    let notes =
+        d.notes
+            .iter()
+            .map(|(msg, loc)| json::new_object(vec![
+                ("message", Json::Str(msg.clone())),
+                ("span", json_span(src, loc.map(|loc| (loc, loc)))),
+            ]))
+            .collect();
+
+    let stacktrace =
+        d.stacktrace
+            .iter()
+            .map(|frame| json::new_object(vec![
+                ("file", Json::Str(frame.file.clone())),
+                ("line", json_usize(frame.loc.0)),
+                ("col", json_usize(frame.loc.1)),
+                ("function", Json::Str(frame.func.clone())),
+            ]))
+            .collect();
+
+    let severity =
+        match d.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+
+    json::new_object(vec![
+        ("severity", Json::Str(severity.to_string())),
+        ("code", Json::Str(d.code.to_string())),
+        ("message", Json::Str(d.msg.clone())),
+        ("file", Json::Str(file.to_string())),
+        ("span", json_span(src, d.span)),
+        ("function", d.func.clone().map_or(Json::Null, Json::Str)),
+        ("notes", Json::List(notes)),
+        ("stacktrace", Json::List(stacktrace)),
+    ])
+}
+
+fn json_span(src: &str, span: Option<(Location, Location)>) -> Json {
+    match span {
+        Some((start, end)) => {
+            json::new_object(vec![
+                ("start", json_location(src, start)),
+                ("end", json_location(src, end)),
+            ])
+        },
+        None => {
+            Json::Null
+        },
+    }
+}
+
+fn json_location(src: &str, (line, col): Location) -> Json {
+    json::new_object(vec![
+        ("line", json_usize(line)),
+        ("col", json_usize(col)),
+        ("offset", json_usize(offset(src, (line, col)))),
+    ])
+}
+
+// `offset` returns the byte offset of the character at `(line, col)` in
+// `src`. Locations after the end of a line are moved to the end of that line,
+// and locations after the last line are moved to the end of `src`.
+fn offset(src: &str, (line, col): Location) -> usize {
+    let mut line_start = 0;
+    for (i, text) in src.split('\n').enumerate() {
+        if i + 1 == line {
+            let col_offset =
+                text.char_indices()
+                    .nth(col.saturating_sub(1))
+                    .map_or(text.len(), |(offset, _)| offset);
+
+            return line_start + col_offset;
+        }
+        line_start += text.len() + 1;
+    }
+
+    src.len()
+}
+
+fn json_usize(n: usize) -> Json {
+    Json::Int(n.try_into().unwrap_or(i64::MAX))
+}
+
+// `Renderer` renders diagnostics for `src`, in the style of `rustc`. ANSI
+// colour codes are only included in the output if `colour` is `true`.
+pub struct Renderer<'a> {
+    src: &'a str,
+    colour: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(src: &'a str, colour: bool) -> Self {
+        Renderer{src, colour}
+    }
+
+    // `render` renders `d`, which occurred in `file`, as a headline that
+    // contains the location and code of the error, followed by an excerpt of
+    // the source, any notes, and the stacktrace.
+    pub fn render(&self, file: &str, d: &Diagnostic) -> String {
+        let Diagnostic{code, msg, ..} = d;
+        let msg =
+            match d.severity {
+                Severity::Error => msg.clone(),
+                Severity::Warning => format!("warning: {msg}"),
+            };
+        let loc = d.loc.or(d.span.map(|(start, _)| start));
+        let headline =
+            match (loc, &d.func) {
+                (Some((line, col)), Some(f)) => {
+                    format!("{file}:{line}:{col}: in '{f}': {msg} [{code}]")
+                },
+                (Some((line, col)), None) => {
+                    format!("{file}:{line}:{col}: {msg} [{code}]")
+                },
+                (None, _) => {
+                    format!("{file}: {msg} [{code}]")
+                },
+            };
+
+        let mut rendered = self.paint(BOLD, &headline);
+        rendered += "\n";
+
+        if let Some((start, end)) = d.span {
+            rendered += &self.excerpt(start, end);
+        }
+
+        for (note, loc) in &d.notes {
+            rendered += &self.note(note, *loc);
+        }
+
+        if !d.stacktrace.is_empty() {
+            rendered += "Stacktrace:\n";
+            for Frame{file, loc: (line, col), func} in &d.stacktrace {
+                let _ =
+                    writeln!(rendered, "  {file}:{line}:{col}: in '{func}'");
+            }
+        }
+
+        rendered
+    }
+
+    // `excerpt` renders the source line that contains `start`, followed by a
+    // line that underlines the span from `start` to `end` (inclusive). If the
+    // span continues past the end of the line then the rest of the line is
+    // underlined. A source that ends with a newline has an empty last line,
+    // which is where an unexpected EOF after the newline is located, and
+    // locations after the last line are moved to the end of the last line. An
+    // empty string is returned if the source is empty.
+    pub fn excerpt(&self, start: Location, end: Location) -> String {
+        if self.src.is_empty() {
+            return String::new();
+        }
+
+        let lines: Vec<&str> =
+            self.src.split('\n').map(|l| l.trim_end_matches('\r')).collect();
+
+        let (line_num, start_col, end) =
+            if start.0 <= lines.len() {
+                (start.0, start.1, end)
+            } else {
+                let last = lines[lines.len() - 1];
+                let loc = (lines.len(), last.chars().count() + 1);
+
+                (loc.0, loc.1, loc)
+            };
+
+        let line = lines[line_num.saturating_sub(1)];
+        let line_len = line.chars().count();
+        let end_col =
+            if end.0 == line_num {
+                end.1.max(start_col)
+            } else {
+                line_len.max(start_col)
+            };
+
+        // We copy tabs from the source line into the marker line so that the
+        // marker lines up with the source line, regardless of tab width.
+        let indent: String =
+            line.chars()
+                .chain(std::iter::repeat(' '))
+                .take(start_col.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+        let marker = "^".repeat(end_col - start_col + 1);
+
+        let num = line_num.to_string();
+        let pad = " ".repeat(num.len());
+        let bar = self.paint(BOLD_BLUE, "|");
+
+        // We trim the source line so that an empty line isn't rendered with
+        // trailing whitespace.
+        let source_line =
+            format!("{} {line}", self.paint(BOLD_BLUE, &format!("{num} |")));
+
+        format!(
+            "{pad} {bar}\n{}\n{pad} {bar} {indent}{}\n",
+            source_line.trim_end(),
+            self.paint(BOLD_RED, &marker),
+        )
+    }
+
+    // `note` renders `msg` as a note, followed by an excerpt of the source at
+    // `loc`, if `loc` is defined and refers to a line of the source. Line 0 is
+    // used for names that aren't defined in the source, such as builtins.
+    pub fn note(&self, msg: &str, loc: Option<Location>) -> String {
+        let label = self.paint(BOLD_GREEN, "note");
+        let excerpt =
+            match loc {
+                Some(loc) if loc.0 > 0 => self.excerpt(loc, loc),
+                _ => String::new(),
+            };
+
+        format!("{label}: {msg}\n{excerpt}")
+    }
+
+    fn paint(&self, code: &str, s: &str) -> String {
+        if self.colour {
+            format!("{code}{s}{RESET}")
+        } else {
+            s.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn test_renders_excerpts() {
+        let src = "x := 1\n\tprint(xyz)\nfoo(\n";
+        let tests = &[
+            ((1, 1), (1, 1), "  |\n1 | x := 1\n  | ^\n"),
+            ((2, 8), (2, 10), "  |\n2 | \tprint(xyz)\n  | \t      ^^^\n"),
+            ((3, 4), (4, 1), "  |\n3 | foo(\n  |    ^\n"),
+            ((3, 5), (3, 5), "  |\n3 | foo(\n  |     ^\n"),
+            ((4, 1), (4, 1), "  |\n4 |\n  | ^\n"),
+            ((5, 1), (5, 1), "  |\n4 |\n  | ^\n"),
+        ];
+
+        let renderer = Renderer::new(src, false);
+        for (start, end, exp) in tests {
+            let act = renderer.excerpt(*start, *end);
+
+            assert_eq!(&act, exp, "incorrect excerpt for {start:?}");
+        }
+
+        assert_eq!(Renderer::new("", false).excerpt((1, 1), (1, 1)), "");
+    }
+
+    #[test]
+    fn test_renders_diagnostics() {
+        let src = "f := fn() {\n    x\n}\nf()\n";
+        let renderer = Renderer::new(src, false);
+        let mut d = Diagnostic::new("E0101", "'x' is not defined".to_string());
+        d.span = Some(((2, 5), (2, 5)));
+        d.func = Some("f".to_string());
+        d.stacktrace.push(Frame{
+            file: "a.sd".to_string(),
+            loc: (4, 1),
+            func: "<root>".to_string(),
+        });
+
+        assert_eq!(
+            renderer.render("a.sd", &d),
+            "a.sd:2:5: in 'f': 'x' is not defined [E0101]\n\
+             \x20 |\n\
+             2 |     x\n\
+             \x20 |     ^\n\
+             Stacktrace:\n\
+             \x20 a.sd:4:1: in '<root>'\n",
+        );
+        assert_eq!(
+            json::encode(&to_json("a.sd", src, &d), None),
+            "{\"code\":\"E0101\",\"file\":\"a.sd\",\"function\":\"f\",\
+             \"message\":\"'x' is not defined\",\"notes\":[],\
+             \"severity\":\"error\",\
+             \"span\":{\"end\":{\"col\":5,\"line\":2,\"offset\":16},\
+             \"start\":{\"col\":5,\"line\":2,\"offset\":16}},\
+             \"stacktrace\":[{\"col\":1,\"file\":\"a.sd\",\
+             \"function\":\"<root>\",\"line\":4}]}",
+        );
+    }
+
+    #[test]
+    fn test_offsets() {
+        let src = "é := 1\nx\n";
+        let cases = [
+            ((1, 1), 0),
+            ((1, 2), 2),
+            ((2, 1), 8),
+            ((2, 5), 9),
+            ((3, 1), 10),
+            ((4, 1), 10),
+        ];
+
+        for (loc, exp) in cases {
+            assert_eq!(offset(src, loc), exp, "incorrect offset for {loc:?}");
+        }
+    }
+
+    #[test]
+    fn test_renders_colour() {
+        let renderer = Renderer::new("x", true);
+
+        let act = renderer.note("here", Some((1, 1)));
+
+        assert_eq!(
+            act,
+            "\x1b[1;32mnote\x1b[0m: here\n\
+             \x20 \x1b[1;34m|\x1b[0m\n\
+             \x1b[1;34m1 |\x1b[0m x\n\
+             \x20 \x1b[1;34m|\x1b[0m \x1b[1;31m^\x1b[0m\n",
+        );
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use lalrpop_util::ParseError;
+
+use crate::eval::error::Error as EvalError;
+use crate::lexer::LexError;
+use crate::lexer::Position;
+use crate::lexer::Token;
+use crate::lint::WarningKind;
+use crate::syntax_error::SyntaxError;
+
+// Error codes identify the kind of an error, independently of the wording of
+// its message, so that tools that consume diagnostics don't need to match on
+// messages. Codes must never be reused or reassigned once they've been
+// published; new kinds of errors must be given new codes.
+
+pub const READ_SCRIPT_FAILED: &str = "E0001";
+
+pub const INTERNAL: &str = "E0999";
+
+// `lex_error_code` returns the code for `error`.
+pub fn lex_error_code(error: &LexError) -> &'static str {
+    match error {
+        LexError::Unexpected(..) => "E0010",
+        LexError::IntOverflow(..) => "E0011",
+        LexError::UnescapedDollar(..) => "E0012",
+        LexError::InvalidInterpolationStart(..) => "E0013",
+        LexError::InvalidEscapeChar(..) => "E0014",
+        LexError::InvalidHexChar(..) => "E0015",
+    }
+}
+
+// `syntax_error_code` returns the code for `error`. Errors that were raised
+// by the lexer use the code of the underlying `LexError`.
+pub fn syntax_error_code(error: &SyntaxError) -> &'static str {
+    match error {
+        SyntaxError::Lex(e) => lex_error_code(e),
+        SyntaxError::InvalidFormatSpec(..) => "E0016",
+    }
+}
+
+// `parse_error_code` returns the code for `error`.
+pub fn parse_error_code(error: &ParseError<Position, Token, SyntaxError>)
+    -> &'static str
+{
+    match error {
+        ParseError::InvalidToken{..} => "E0020",
+        ParseError::UnrecognizedEof{..} => "E0021",
+        ParseError::UnrecognizedToken{..} => "E0022",
+        ParseError::ExtraToken{..} => "E0023",
+        ParseError::User{error} => syntax_error_code(error),
+    }
+}
+
+// `eval_error_code` returns the code for `error`. Each user-facing variant of
+// `EvalError` has its own code, except for variants that represent the same
+// error in different contexts, which share a code. Codes are never renumbered,
+// so new variants are given new codes after the last assigned code.
+#[allow(clippy::too_many_lines)]
+pub fn eval_error_code(error: &EvalError) -> &'static str {
+    match error {
+        EvalError::CannotCallNonFunc{..} => "E0100",
+
+        EvalError::Undefined{..}
+        | EvalError::OpOnUndefinedIndex{..}
+        | EvalError::OpOnUndefinedProp{..} => "E0101",
+
+        EvalError::ObjectPropShorthandNotVar => "E0102",
+        EvalError::InvalidBindTarget{..} => "E0103",
+        EvalError::AlreadyInBinding{..} => "E0104",
+        EvalError::AlreadyInScope{..} => "E0105",
+        EvalError::IncorrectType{..} => "E0106",
+        EvalError::StringConstructionFailed{..} => "E0107",
+        EvalError::ArgNumMismatch{..} => "E0108",
+        EvalError::TooFewArgs{..} => "E0109",
+
+        EvalError::InvalidOpTypes{..}
+        | EvalError::InvalidEqOpTypes{..} => "E0110",
+
+        EvalError::BreakOutsideLoop => "E0111",
+        EvalError::ContinueOutsideLoop => "E0112",
+        EvalError::ReturnOutsideFunction => "E0113",
+        EvalError::ForIterNotIterable => "E0114",
+        EvalError::ValueNotIndexable => "E0115",
+        EvalError::ValueNotIndexAssignable => "E0116",
+        EvalError::ValueNotRangeIndexAssignable => "E0117",
+        EvalError::AssignToTypeProp => "E0118",
+        EvalError::OutOfStringBounds{..} => "E0119",
+        EvalError::OutOfListBounds{..} => "E0157",
+        EvalError::RangeOutOfStringBounds{..} => "E0158",
+        EvalError::RangeOutOfListBounds{..} => "E0159",
+        EvalError::RangeStartOutOfListBounds{..} => "E0160",
+        EvalError::RangeStartNotBeforeEnd{..} => "E0120",
+        EvalError::RangeEndOutOfListBounds{..} => "E0161",
+        EvalError::ValueNotRangeIndexable => "E0121",
+        EvalError::NegativeIndex{..} => "E0122",
+        EvalError::ListCollectOutsideDestructure => "E0123",
+        EvalError::ObjectCollectOutsideDestructure => "E0162",
+        EvalError::ObjectCollectIsNotLast => "E0124",
+        EvalError::SpreadNonListInList{..} => "E0125",
+        EvalError::SpreadNonObjectInObject{..} => "E0163",
+        EvalError::RangeIndexAssignOnNonIndexable{..} => "E0126",
+        EvalError::ObjectDestructureOnNonObject{..} => "E0127",
+        EvalError::SpreadOnObjectDestructure => "E0128",
+        EvalError::ListDestructureOnNonList{..} => "E0164",
+
+        EvalError::ListDestructureItemMismatch{..}
+        | EvalError::ListCollectTooFew{..} => "E0129",
+
+        EvalError::SpreadInListDestructure{..} => "E0165",
+        EvalError::RangeIndexItemMismatch{..} => "E0166",
+        EvalError::PropNotFound{..} => "E0130",
+        EvalError::TypeFunctionNotFound{..} => "E0131",
+        EvalError::TypeFunctionOnNull => "E0132",
+        EvalError::PropAccessOnNonObject{..} => "E0133",
+        EvalError::FormatSpecNotApplicable{..} => "E0134",
+        EvalError::OpOnRangeIndex => "E0135",
+        EvalError::OpOnObjectDestructure => "E0167",
+        EvalError::OpOnListDestructure => "E0168",
+        EvalError::IntOverflow{..} => "E0136",
+        EvalError::DupParamName{..} => "E0137",
+
+        EvalError::PropSpreadInParamList
+        | EvalError::ItemSpreadInParamList => "E0169",
+
+        EvalError::BuiltinFuncErr{..} => "E0138",
+        EvalError::PropAlreadyInDestructure{..} => "E0152",
+        EvalError::AssertionFailed{..} => "E0153",
+        EvalError::AssertEqFailed{..} => "E0154",
+        EvalError::AssertThrowsFailed{..} => "E0155",
+        EvalError::Exit{..} => "E0156",
+
+        // The remaining variants either wrap another error, and so never
+        // reach this function, or are reported for bugs in the interpreter.
+        EvalError::Dev{..}
+        | EvalError::AtLoc{..}
+        | EvalError::AtSpan{..}
+        | EvalError::CastFailed{..}
+        | EvalError::BindFailed{..}
+        | EvalError::BindObjectCollectFailed{..}
+        | EvalError::BindObjectSingleFailed{..}
+        | EvalError::BindObjectPairFailed{..}
+        | EvalError::BindListItemFailed{..}
+        | EvalError::BindNextFailed{..}
+        | EvalError::EvalStmtsWithScopeStackFailed{..}
+        | EvalError::EvalStmtsFailed{..}
+        | EvalError::EvalDeclarationRhsFailed{..}
+        | EvalError::DeclarationBindFailed{..}
+        | EvalError::EvalAssignmentRhsFailed{..}
+        | EvalError::AssignmentBindFailed{..}
+        | EvalError::OpAssignmentBindFailed{..}
+        | EvalError::EvalIfConditionFailed{..}
+        | EvalError::EvalIfStatementsFailed{..}
+        | EvalError::EvalElseStatementsFailed{..}
+        | EvalError::EvalWhileConditionFailed{..}
+        | EvalError::EvalWhileStatementsFailed{..}
+        | EvalError::EvalForIterFailed{..}
+        | EvalError::ConvertForIterToPairsFailed{..}
+        | EvalError::EvalForStatementsFailed{..}
+        | EvalError::ValidateArgsFailed{..}
+        | EvalError::DeclareFunctionFailed{..}
+        | EvalError::EvalReturnExprFailed{..}
+        | EvalError::EvalBlockFailed{..}
+        | EvalError::EvalStmtFailed{..}
+        | EvalError::EvalBinOpLhsFailed{..}
+        | EvalError::EvalBinOpRhsFailed{..}
+        | EvalError::ApplyBinOpFailed{..}
+        | EvalError::BinOpAssignListIndexFailed{..}
+        | EvalError::BinOpAssignObjectIndexFailed{..}
+        | EvalError::BinOpAssignPropFailed{..}
+        | EvalError::EvalListItemsFailed{..}
+        | EvalError::EvalListItemFailed{..}
+        | EvalError::EvalSourceExprFailed{..}
+        | EvalError::EvalIndexToI64Failed{..}
+        | EvalError::EvalStringIndexFailed{..}
+        | EvalError::EvalListIndexFailed{..}
+        | EvalError::EvalObjectIndexFailed{..}
+        | EvalError::EvalStartIndexFailed{..}
+        | EvalError::EvalEndIndexFailed{..}
+        | EvalError::EvalStringRangeIndexFailed{..}
+        | EvalError::EvalListRangeIndexFailed{..}
+        | EvalError::EvalRangeStartFailed{..}
+        | EvalError::EvalRangeEndFailed{..}
+        | EvalError::EvalPropNameFailed{..}
+        | EvalError::EvalPropValueFailed{..}
+        | EvalError::EvalCallFailed{..}
+        | EvalError::EvalCallArgsFailed{..}
+        | EvalError::EvalCallFuncFailed{..}
+        | EvalError::EvalBuiltinFuncCallFailed{..}
+        | EvalError::EvalFuncCallFailed{..}
+        | EvalError::EvalExprFailed{..}
+        | EvalError::EvalPropFailed{..}
+        | EvalError::InterpolateStringFailed{..}
+        | EvalError::InterpolateStringEvalExprFailed{..}
+        | EvalError::AssertArgsFailed{..}
+        | EvalError::AssertThisFailed{..}
+        | EvalError::AssertNoThisFailed{..}
+        | EvalError::AssertStrFailed{..} => INTERNAL,
+    }
+}
+
+// `warning_code` returns the code for warnings of the given `kind`. Warning
+// codes are prefixed with `W` rather than `E`, so that they can be
+// distinguished from error codes.
+pub fn warning_code(kind: WarningKind) -> &'static str {
+    match kind {
+        WarningKind::UnusedVariables => "W0001",
+        WarningKind::Shadowing => "W0002",
+        WarningKind::UnreachableCode => "W0003",
+    }
+}
+
+// `explanation` returns a longer explanation of the error with the given
+// `code`, including an example of code that causes the error, or `None` if
+// `code` isn't a known error code.
+pub fn explanation(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, explanation)| *explanation)
+}
+
+const EXPLANATIONS: &[(&str, &str)] = &[
+    ("E0001", include_str!("../docs/errors/E0001.md")),
+    ("E0010", include_str!("../docs/errors/E0010.md")),
+    ("E0011", include_str!("../docs/errors/E0011.md")),
+    ("E0012", include_str!("../docs/errors/E0012.md")),
+    ("E0013", include_str!("../docs/errors/E0013.md")),
+    ("E0014", include_str!("../docs/errors/E0014.md")),
+    ("E0015", include_str!("../docs/errors/E0015.md")),
+    ("E0016", include_str!("../docs/errors/E0016.md")),
+    ("E0020", include_str!("../docs/errors/E0020.md")),
+    ("E0021", include_str!("../docs/errors/E0021.md")),
+    ("E0022", include_str!("../docs/errors/E0022.md")),
+    ("E0023", include_str!("../docs/errors/E0023.md")),
+    ("E0100", include_str!("../docs/errors/E0100.md")),
+    ("E0101", include_str!("../docs/errors/E0101.md")),
+    ("E0102", include_str!("../docs/errors/E0102.md")),
+    ("E0103", include_str!("../docs/errors/E0103.md")),
+    ("E0104", include_str!("../docs/errors/E0104.md")),
+    ("E0105", include_str!("../docs/errors/E0105.md")),
+    ("E0106", include_str!("../docs/errors/E0106.md")),
+    ("E0107", include_str!("../docs/errors/E0107.md")),
+    ("E0108", include_str!("../docs/errors/E0108.md")),
+    ("E0109", include_str!("../docs/errors/E0109.md")),
+    ("E0110", include_str!("../docs/errors/E0110.md")),
+    ("E0111", include_str!("../docs/errors/E0111.md")),
+    ("E0112", include_str!("../docs/errors/E0112.md")),
+    ("E0113", include_str!("../docs/errors/E0113.md")),
+    ("E0114", include_str!("../docs/errors/E0114.md")),
+    ("E0115", include_str!("../docs/errors/E0115.md")),
+    ("E0116", include_str!("../docs/errors/E0116.md")),
+    ("E0117", include_str!("../docs/errors/E0117.md")),
+    ("E0118", include_str!("../docs/errors/E0118.md")),
+    ("E0119", include_str!("../docs/errors/E0119.md")),
+    ("E0120", include_str!("../docs/errors/E0120.md")),
+    ("E0121", include_str!("../docs/errors/E0121.md")),
+    ("E0122", include_str!("../docs/errors/E0122.md")),
+    ("E0123", include_str!("../docs/errors/E0123.md")),
+    ("E0124", include_str!("../docs/errors/E0124.md")),
+    ("E0125", include_str!("../docs/errors/E0125.md")),
+    ("E0126", include_str!("../docs/errors/E0126.md")),
+    ("E0127", include_str!("../docs/errors/E0127.md")),
+    ("E0128", include_str!("../docs/errors/E0128.md")),
+    ("E0129", include_str!("../docs/errors/E0129.md")),
+    ("E0130", include_str!("../docs/errors/E0130.md")),
+    ("E0131", include_str!("../docs/errors/E0131.md")),
+    ("E0132", include_str!("../docs/errors/E0132.md")),
+    ("E0133", include_str!("../docs/errors/E0133.md")),
+    ("E0134", include_str!("../docs/errors/E0134.md")),
+    ("E0135", include_str!("../docs/errors/E0135.md")),
+    ("E0136", include_str!("../docs/errors/E0136.md")),
+    ("E0137", include_str!("../docs/errors/E0137.md")),
+    ("E0138", include_str!("../docs/errors/E0138.md")),
+    ("E0152", include_str!("../docs/errors/E0152.md")),
+    ("E0153", include_str!("../docs/errors/E0153.md")),
+    ("E0154", include_str!("../docs/errors/E0154.md")),
+    ("E0155", include_str!("../docs/errors/E0155.md")),
+    ("E0156", include_str!("../docs/errors/E0156.md")),
+    ("E0157", include_str!("../docs/errors/E0157.md")),
+    ("E0158", include_str!("../docs/errors/E0158.md")),
+    ("E0159", include_str!("../docs/errors/E0159.md")),
+    ("E0160", include_str!("../docs/errors/E0160.md")),
+    ("E0161", include_str!("../docs/errors/E0161.md")),
+    ("E0162", include_str!("../docs/errors/E0162.md")),
+    ("E0163", include_str!("../docs/errors/E0163.md")),
+    ("E0164", include_str!("../docs/errors/E0164.md")),
+    ("E0165", include_str!("../docs/errors/E0165.md")),
+    ("E0166", include_str!("../docs/errors/E0166.md")),
+    ("E0167", include_str!("../docs/errors/E0167.md")),
+    ("E0168", include_str!("../docs/errors/E0168.md")),
+    ("E0169", include_str!("../docs/errors/E0169.md")),
+    ("E0999", include_str!("../docs/errors/E0999.md")),
+    ("W0001", include_str!("../docs/errors/W0001.md")),
+    ("W0002", include_str!("../docs/errors/W0002.md")),
+    ("W0003", include_str!("../docs/errors/W0003.md")),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // `test_codes_have_explanations` checks that every code that's assigned
+    // in this file has an explanation.
+    #[test]
+    fn test_codes_have_explanations() {
+        let src = include_str!("error_codes.rs");
+        let end = src.find("const EXPLANATIONS")
+            .expect("couldn't find explanations");
+        let src = &src[..end];
+
+        let code_starts =
+            src.match_indices("\"E").chain(src.match_indices("\"W"));
+        for (i, _) in code_starts {
+            let code = &src[i+1..i+6];
+            if !code[1..].chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+
+            assert!(
+                explanation(code).is_some(),
+                "no explanation for '{code}'",
+            );
+        }
+
+        assert_eq!(explanation("E9998"), None);
+    }
+
+    #[test]
+    fn test_codes_are_unique() {
+        for (i, (code, _)) in EXPLANATIONS.iter().enumerate() {
+            assert!(
+                !EXPLANATIONS[i+1..].iter().any(|(c, _)| c == code),
+                "'{code}' is defined more than once",
+            );
+        }
+    }
+}
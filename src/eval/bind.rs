@@ -3,6 +3,7 @@
 // licence that can be found in the LICENCE file.
 
 use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::collections::HashSet;
 
 use snafu::ResultExt;
@@ -16,6 +17,7 @@ use super::error::*;
 use super::error::Error;
 use super::scope;
 use super::scope::ScopeStack;
+use super::suggest;
 use crate::lock_deref;
 use crate::value;
 use value::ListRef;
@@ -48,7 +50,7 @@ pub fn bind(
 )
     -> Result<()>
 {
-    bind_next(context, scopes, &mut HashSet::new(), lhs, rhs, None, bind_type)
+    bind_next(context, scopes, &mut HashMap::new(), lhs, rhs, None, bind_type)
 }
 
 #[derive(Clone, Copy)]
@@ -58,12 +60,13 @@ pub enum BindType {
 }
 
 // `bind_next` performs a bind, but returns an error if a name that's in
-// `names_in_binding` gets reused.
+// `names_in_binding` gets reused. `names_in_binding` maps each name that has
+// been bound to the location that it was bound at.
 #[allow(clippy::too_many_lines)]
 pub fn bind_next(
     context: &EvaluationContext,
     scopes: &mut ScopeStack,
-    names_in_binding: &mut HashSet<String>,
+    names_in_binding: &mut HashMap<String, Location>,
     lhs: &Expr,
     rhs: SourcedValue,
     op: Option<(BinaryOp, Location)>,
@@ -71,12 +74,9 @@ pub fn bind_next(
 )
     -> Result<()>
 {
-    let (raw_lhs, loc) = lhs;
-    let new_loc_err = |source| {
-        let (line, col) = loc;
-
-        Err(Error::AtLoc{source: Box::new(source), line: *line, col: *col})
-    };
+    let (raw_lhs, span) = lhs;
+    let loc = &span.loc();
+    let new_loc_err = |source| Err(eval::new_span_err(source, span));
     let new_invalid_bind_error = |s: &str| {
         new_loc_err(Error::InvalidBindTarget{descr: s.to_string()})
     };
@@ -322,14 +322,14 @@ pub fn bind_name(
 )
     -> Result<()>
 {
-    let names = &mut HashSet::new();
+    let names = &mut HashMap::new();
 
     bind_next_name(scopes, names, name, name_loc, rhs, None, bind_type)
 }
 
 fn bind_next_name(
     scopes: &mut ScopeStack,
-    names_in_binding: &mut HashSet<String>,
+    names_in_binding: &mut HashMap<String, Location>,
     name: &str,
     name_loc: &(usize, usize),
     rhs: SourcedValue,
@@ -347,10 +347,14 @@ fn bind_next_name(
         Err(Error::AtLoc{source: Box::new(source), line: *line, col: *col})
     };
 
-    if names_in_binding.contains(name) {
-        return new_loc_error(Error::AlreadyInBinding{name: name.to_string()});
+    if let Some((prev_line, prev_col)) = names_in_binding.get(name) {
+        return new_loc_error(Error::AlreadyInBinding{
+            name: name.to_string(),
+            prev_line: *prev_line,
+            prev_col: *prev_col,
+        });
     }
-    names_in_binding.insert(name.to_string());
+    names_in_binding.insert(name.to_string(), *name_loc);
 
     match bind_type {
         BindType::Declaration => {
@@ -377,6 +381,10 @@ fn bind_next_name(
                     } else {
                         return new_loc_error(Error::Undefined{
                             name: name.to_string(),
+                            suggestion: suggest::closest(
+                                name,
+                                &scopes.names(),
+                            ),
                         });
                     };
 
@@ -395,6 +403,7 @@ fn bind_next_name(
             if !scopes.assign(name, rhs_val) {
                 return new_loc_error(Error::Undefined{
                     name: name.to_string(),
+                    suggestion: suggest::closest(name, &scopes.names()),
                 });
             }
         },
@@ -466,7 +475,7 @@ fn bind_range_index(
 fn bind_object(
     context: &EvaluationContext,
     scopes: &mut ScopeStack,
-    names_in_binding: &mut HashSet<String>,
+    names_in_binding: &mut HashMap<String, Location>,
     lhs: &Vec<PropItem>,
     rhs: &ObjectRef,
     bind_type: BindType,
@@ -483,19 +492,18 @@ fn bind_object(
             .cloned()
             .collect::<HashSet<String>>();
 
+    // `prop_locs` maps each property that has been destructured to the
+    // location that it was destructured at.
+    let mut prop_locs = HashMap::<String, Location>::new();
+
     let mut i = 0;
     for prop_item in lhs {
         match prop_item {
             PropItem::Single{expr, is_spread, collect} => {
-                let (raw_expr, prop_name_loc) = expr;
+                let (raw_expr, prop_name_span) = expr;
+                let prop_name_loc = &prop_name_span.loc();
                 let new_loc_err = |source| {
-                    let (line, col) = prop_name_loc;
-
-                    Err(Error::AtLoc{
-                        source: Box::new(source),
-                        line: *line,
-                        col: *col,
-                    })
+                    Err(eval::new_span_err(source, prop_name_span))
                 };
 
                 if *is_spread {
@@ -537,6 +545,10 @@ fn bind_object(
                     continue;
                 }
 
+                // We bind the property before checking whether it has
+                // already been destructured so that reusing a shorthand
+                // property, as in `{a, a}`, is reported as a name that's
+                // bound multiple times.
                 bind_object_prop(
                     context,
                     scopes,
@@ -548,16 +560,29 @@ fn bind_object(
                 )
                     .context(BindObjectSingleFailed)?;
 
+                check_prop_not_destructured(
+                    &mut prop_locs,
+                    &prop_name,
+                    prop_name_loc,
+                )?;
+
                 remaining_keys.remove(&prop_name);
             },
 
             PropItem::Pair{name, value: new_lhs} => {
-                let (_, prop_name_loc) = name;
+                let (_, prop_name_span) = name;
+                let prop_name_loc = &prop_name_span.loc();
 
                 let prop_name =
                     eval::eval_expr_to_str(context, scopes, "property", name)
                         .context(EvalObjectIndexFailed)?;
 
+                check_prop_not_destructured(
+                    &mut prop_locs,
+                    &prop_name,
+                    prop_name_loc,
+                )?;
+
                 bind_object_prop(
                     context,
                     scopes,
@@ -579,10 +604,38 @@ fn bind_object(
     Ok(())
 }
 
+// `check_prop_not_destructured` returns an error if `prop_name` is in
+// `prop_locs`, and otherwise records that `prop_name` was destructured at
+// `prop_name_loc`.
+fn check_prop_not_destructured(
+    prop_locs: &mut HashMap<String, Location>,
+    prop_name: &str,
+    prop_name_loc: &Location,
+)
+    -> Result<()>
+{
+    if let Some((prev_line, prev_col)) = prop_locs.get(prop_name) {
+        let (line, col) = prop_name_loc;
+
+        return Err(Error::AtLoc{
+            source: Box::new(Error::PropAlreadyInDestructure{
+                name: prop_name.to_string(),
+                prev_line: *prev_line,
+                prev_col: *prev_col,
+            }),
+            line: *line,
+            col: *col,
+        });
+    }
+    prop_locs.insert(prop_name.to_string(), *prop_name_loc);
+
+    Ok(())
+}
+
 fn bind_object_prop(
     context: &EvaluationContext,
     scopes: &mut ScopeStack,
-    names_in_binding: &mut HashSet<String>,
+    names_in_binding: &mut HashMap<String, Location>,
     lhs: &Expr,
     rhs: &ObjectRef,
     prop_name: (&str, &(usize, usize)),
@@ -600,11 +653,16 @@ fn bind_object_prop(
         Err(Error::AtLoc{source: Box::new(source), line: *line, col: *col})
     };
 
+    let maybe_rhs = lock_deref!(rhs).get(prop_name.0).cloned();
     let new_rhs =
-        match lock_deref!(rhs).get(prop_name.0) {
-            Some(v) => v.clone(),
+        match maybe_rhs {
+            Some(v) => v,
             None => return new_loc_err(Error::PropNotFound{
                 name: prop_name.0.to_string(),
+                suggestion: suggest::closest(
+                    prop_name.0,
+                    lock_deref!(rhs).keys(),
+                ),
             }),
         };
 
@@ -617,7 +675,7 @@ fn bind_object_prop(
 fn bind_list(
     context: &EvaluationContext,
     scopes: &mut ScopeStack,
-    names_in_binding: &mut HashSet<String>,
+    names_in_binding: &mut HashMap<String, Location>,
     raw_lhs: (&[ListItem], &bool),
     lhs_loc: &(usize, usize),
     rhs: &ListRef,
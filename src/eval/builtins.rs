@@ -18,4 +18,5 @@ pub struct TypeFunctions {
     pub lists: ObjectRef,
     pub objects: ObjectRef,
     pub funcs: ObjectRef,
+    pub iters: ObjectRef,
 }
@@ -0,0 +1,256 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use crate::ast::Block;
+use crate::ast::Expr;
+use crate::ast::ListItem;
+use crate::ast::Location;
+use crate::ast::Prog;
+use crate::ast::PropItem;
+use crate::ast::RawExpr;
+use crate::ast::RawStmt;
+use crate::ast::Stmt;
+
+use super::error::Error;
+
+// `check_prog` returns the errors in `prog` that can be found without
+// evaluating it, in the order that they appear in `prog`. These are errors
+// that would otherwise only be reported when the statement that contains them
+// is evaluated, such as invalid parameter lists and `break` statements outside
+// of loops.
+pub fn check_prog(Prog::Body{stmts}: &Prog) -> Vec<Error> {
+    let mut checker = Checker{in_func: false, in_loop: false, errs: vec![]};
+    checker.check_stmts(stmts);
+
+    checker.errs
+}
+
+struct Checker {
+    in_func: bool,
+    // `in_loop` is `true` if the current statement is in a loop in the
+    // current function, or in a loop at the top level of the program if
+    // `in_func` is `false`.
+    in_loop: bool,
+    errs: Vec<Error>,
+}
+
+impl Checker {
+    fn check_stmts(&mut self, stmts: &Block) {
+        for stmt in stmts {
+            self.check_stmt(stmt);
+        }
+    }
+
+    fn check_stmt(&mut self, (raw_stmt, _): &Stmt) {
+        match raw_stmt {
+            RawStmt::Block{block} => {
+                self.check_stmts(block);
+            },
+            RawStmt::Expr{expr} => {
+                self.check_expr(expr);
+            },
+            RawStmt::Declare{lhs, rhs}
+            | RawStmt::Assign{lhs, rhs}
+            | RawStmt::OpAssign{lhs, rhs, ..} => {
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+            },
+            RawStmt::If{branches, else_stmts} => {
+                for branch in branches {
+                    self.check_expr(&branch.cond);
+                    self.check_stmts(&branch.stmts);
+                }
+                if let Some(stmts) = else_stmts {
+                    self.check_stmts(stmts);
+                }
+            },
+            RawStmt::While{cond, stmts} => {
+                self.check_expr(cond);
+                self.check_loop(stmts);
+            },
+            RawStmt::For{lhs, iter, stmts} => {
+                self.check_expr(lhs);
+                self.check_expr(iter);
+                self.check_loop(stmts);
+            },
+            RawStmt::Break{loc} => {
+                if !self.in_loop {
+                    self.push_err(*loc, Error::BreakOutsideLoop);
+                }
+            },
+            RawStmt::Continue{loc} => {
+                if !self.in_loop {
+                    self.push_err(*loc, Error::ContinueOutsideLoop);
+                }
+            },
+            RawStmt::Func{args, stmts, ..} => {
+                self.check_func(args, stmts);
+            },
+            RawStmt::Return{loc, expr} => {
+                if !self.in_func {
+                    self.push_err(*loc, Error::ReturnOutsideFunction);
+                }
+                self.check_expr(expr);
+            },
+            RawStmt::Error => {
+            },
+        }
+    }
+
+    fn check_loop(&mut self, stmts: &Block) {
+        let in_loop = self.in_loop;
+        self.in_loop = true;
+        self.check_stmts(stmts);
+        self.in_loop = in_loop;
+    }
+
+    // `check_func` checks the parameters and body of a function. `break` and
+    // `continue` can't be used to exit a loop that contains the function, so
+    // the body of the function is checked as if it isn't in a loop.
+    fn check_func(&mut self, args: &[Expr], stmts: &Block) {
+        if let Err(e) = super::validate_args(args) {
+            self.errs.push(e);
+        }
+
+        let (in_func, in_loop) = (self.in_func, self.in_loop);
+        self.in_func = true;
+        self.in_loop = false;
+        self.check_stmts(stmts);
+        (self.in_func, self.in_loop) = (in_func, in_loop);
+    }
+
+    fn check_expr(&mut self, (raw_expr, _): &Expr) {
+        match raw_expr {
+            RawExpr::Null
+            | RawExpr::Bool{..}
+            | RawExpr::Int{..}
+            | RawExpr::Var{..} => {
+            },
+            RawExpr::Str{interpolation_slots, ..} => {
+                for slot in interpolation_slots.iter().flatten() {
+                    self.check_expr(&slot.expr);
+                }
+            },
+            RawExpr::BinaryOp{lhs, rhs, ..} => {
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+            },
+            RawExpr::Range{start, end} => {
+                self.check_expr(start);
+                self.check_expr(end);
+            },
+            RawExpr::List{items, ..} => {
+                for ListItem{expr, ..} in items {
+                    self.check_expr(expr);
+                }
+            },
+            RawExpr::Index{expr, location} => {
+                self.check_expr(expr);
+                self.check_expr(location);
+            },
+            RawExpr::RangeIndex{expr, start, end} => {
+                self.check_expr(expr);
+                for e in start.iter().chain(end.iter()) {
+                    self.check_expr(e);
+                }
+            },
+            RawExpr::Object{props} => {
+                for prop in props {
+                    match prop {
+                        PropItem::Pair{name, value} => {
+                            self.check_expr(name);
+                            self.check_expr(value);
+                        },
+                        PropItem::Single{expr, ..} => {
+                            self.check_expr(expr);
+                        },
+                    }
+                }
+            },
+            RawExpr::Prop{expr, ..} => {
+                self.check_expr(expr);
+            },
+            RawExpr::Func{args, stmts, ..} => {
+                self.check_func(args, stmts);
+            },
+            RawExpr::Call{func, args} => {
+                self.check_expr(func);
+                for ListItem{expr, ..} in args {
+                    self.check_expr(expr);
+                }
+            },
+        }
+    }
+
+    fn push_err(&mut self, (line, col): Location, source: Error) {
+        self.errs.push(Error::AtLoc{source: Box::new(source), line, col});
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::lexer::Lexer;
+    use crate::parser::ProgParser;
+    use crate::syntax_error;
+
+    #[test]
+    fn test_check_prog() {
+        let tests: &[(&str, &[(Location, &str)])] = &[
+            (
+                "fn f(a, b) { return a; }\nwhile true {\nbreak\n}\n",
+                &[],
+            ),
+            (
+                "fn f(a, a) {}\n",
+                &[((1, 9), "'a' is already declared at [1:6]")],
+            ),
+            (
+                "f := fn ([a..]) {};\n",
+                &[((1, 10), "can't use spread operator in parameter list")],
+            ),
+            (
+                "if true {\nbreak\n}\nfor x in [] {\ncontinue\n}\n",
+                &[((2, 1), "'break' can't be used outside of a loop")],
+            ),
+            (
+                "while true {\nfn f() {\ncontinue\n}\n}\n",
+                &[((3, 1), "'continue' can't be used outside of a loop")],
+            ),
+            (
+                "return 1;\nfn f() {\nif true { return 2; }\n}\n",
+                &[((1, 1), "'return' can't be used outside of a function")],
+            ),
+        ];
+
+        for (src, exp) in tests {
+            let tokens = syntax_error::tokens(Lexer::new(src));
+            let mut errors = vec![];
+            let prog = ProgParser::new().parse(&mut errors, tokens)
+                .expect("couldn't parse source");
+            assert!(errors.is_empty(), "unexpected syntax errors: {errors:?}");
+
+            let act: Vec<(Location, String)> =
+                check_prog(&prog)
+                    .into_iter()
+                    .map(|e| match e {
+                        Error::AtLoc{source, line, col} |
+                        Error::AtSpan{source, line, col, ..} => {
+                            ((line, col), source.to_string())
+                        },
+                        e => {
+                            panic!("expected located error, got {e:?}");
+                        },
+                    })
+                    .collect();
+            let exp: Vec<(Location, String)> =
+                exp.iter()
+                    .map(|(loc, msg)| (*loc, msg.to_string()))
+                    .collect();
+
+            assert_eq!(act, exp, "incorrect errors: {src:?}");
+        }
+    }
+}
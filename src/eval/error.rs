@@ -2,12 +2,14 @@
 // Use of this source code is governed by an MIT
 // licence that can be found in the LICENCE file.
 
+use std::error::Error as StdError;
 use std::num::TryFromIntError;
 use std::string::FromUtf8Error;
 
 use snafu::Snafu;
 
 use crate::ast::BinaryOp;
+use crate::ast::Span;
 use crate::eval::Value;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -25,15 +27,34 @@ pub enum Error {
     // highlight what the interpreter attempted to evaluate.
     #[snafu(display("can't call '{}' as a function", render_type(v)))]
     CannotCallNonFunc{v: Value},
-    #[snafu(display("'{}' is not defined", name))]
-    Undefined{name: String},
+    #[snafu(display(
+        "'{}' is not defined{}",
+        name,
+        render_suggestion(suggestion.as_deref()),
+    ))]
+    Undefined{name: String, suggestion: Option<String>},
     #[snafu(display("object property name isn't a variable"))]
     ObjectPropShorthandNotVar,
     #[snafu(display("cannot bind to {}", descr))]
     InvalidBindTarget{descr: String},
-    // TODO Add the location of the previous definition of this name.
-    #[snafu(display("'{}' is bound multiple times in this binding", name))]
-    AlreadyInBinding{name: String},
+    #[snafu(display(
+        "'{}' is already bound in this binding at [{}:{}]",
+        name,
+        prev_line,
+        prev_col,
+    ))]
+    AlreadyInBinding{name: String, prev_line: usize, prev_col: usize},
+    #[snafu(display(
+        "property '{}' is already destructured at [{}:{}]",
+        name,
+        prev_line,
+        prev_col,
+    ))]
+    PropAlreadyInDestructure{
+        name: String,
+        prev_line: usize,
+        prev_col: usize,
+    },
     #[snafu(display(
         "'{}' is already defined in the current scope at [{}:{}]",
         name,
@@ -56,14 +77,14 @@ pub enum Error {
     TooFewArgs{minimum: usize, got: usize},
     #[snafu(display(
         "can't apply '{}' to '{}' and '{}'",
-        op_symbol(op),
+        op.symbol(),
         render_type(lhs),
         render_type(rhs),
     ))]
     InvalidOpTypes{op: BinaryOp, lhs: Value, rhs: Value},
     #[snafu(display(
         "can't apply '{}' to '{}' and '{}'{}",
-        op_symbol(op),
+        op.symbol(),
         lhs_type,
         rhs_type,
         msg,
@@ -178,14 +199,23 @@ pub enum Error {
         range_len,
     ))]
     RangeIndexItemMismatch{range_len: usize, rhs_len: usize},
-    #[snafu(display("object doesn't contain property '{}'", name))]
-    PropNotFound{name: String},
     #[snafu(display(
-        "there is no type function '{}' for '{}'",
+        "object doesn't contain property '{}'{}",
+        name,
+        render_suggestion(suggestion.as_deref()),
+    ))]
+    PropNotFound{name: String, suggestion: Option<String>},
+    #[snafu(display(
+        "there is no type function '{}' for '{}'{}",
         name,
         render_type(value),
+        render_suggestion(suggestion.as_deref()),
     ))]
-    TypeFunctionNotFound{value: Value, name: String},
+    TypeFunctionNotFound{
+        value: Value,
+        name: String,
+        suggestion: Option<String>,
+    },
     #[snafu(display("cannot access type function on 'null'"))]
     TypeFunctionOnNull,
     #[snafu(display(
@@ -194,12 +224,11 @@ pub enum Error {
     ))]
     PropAccessOnNonObject{value: Value},
     #[snafu(display(
-        "interpolated values can only be strings, got '{}'",
+        "{} can't be applied to '{}'",
+        descr,
         render_type(value),
     ))]
-    InterpolatedValueNotString{value: Value},
-    #[snafu(display("couldn't parse interpolation slot: {}", source_str))]
-    InterpolateStringParseFailed{source_str: String},
+    FormatSpecNotApplicable{descr: String, value: Value},
     #[snafu(display("'{}' is not defined", name))]
     OpOnUndefinedIndex{name: String},
     #[snafu(display("'{}' is not defined", name))]
@@ -213,7 +242,7 @@ pub enum Error {
     #[snafu(display(
         "'{} {} {}' caused an integer overflow",
         lhs,
-        op_symbol(op),
+        op.symbol(),
         rhs,
     ))]
     IntOverflow{op: BinaryOp, lhs: i64, rhs: i64},
@@ -226,6 +255,16 @@ pub enum Error {
 
     #[snafu(display("{}", msg))]
     BuiltinFuncErr{msg: String},
+    #[snafu(display("assertion failed{}", render_assert_msg(msg.as_deref())))]
+    AssertionFailed{msg: Option<String>},
+    #[snafu(display("values aren't equal{}", render_path(path)))]
+    AssertEqFailed{actual: String, expected: String, path: String},
+    #[snafu(display("expected function to fail, but it returned {}", value))]
+    AssertThrowsFailed{value: String},
+    // `Exit` is returned by `exit` so that evaluation stops, and the
+    // interpreter exits with `code`.
+    #[snafu(display("`exit` was called with code {}", code))]
+    Exit{code: i32},
 
     #[snafu(display("dev error: {}", msg))]
     Dev{msg: String},
@@ -241,6 +280,18 @@ pub enum Error {
         line: usize,
         col: usize,
     },
+    // `AtSpan` is like `AtLoc`, but also records the span of the expression
+    // that failed, so that the whole expression can be highlighted. `line` and
+    // `col` are the location that's reported for the error, which isn't the
+    // start of `span` for binary operations.
+    #[snafu(display("{}:{}: {}", line, col, source))]
+    AtSpan{
+        #[snafu(source(from(Error, Box::new)))]
+        source: Box<Error>,
+        line: usize,
+        col: usize,
+        span: Span,
+    },
 
     CastFailed{source: TryFromIntError},
 
@@ -268,14 +319,6 @@ pub enum Error {
         #[snafu(source(from(Error, Box::new)))]
         source: Box<Error>,
     },
-    EvalProgFailed{
-        #[snafu(source(from(Error, Box::new)))]
-        source: Box<Error>,
-    },
-    EvalStmtsInNewScopeFailed{
-        #[snafu(source(from(Error, Box::new)))]
-        source: Box<Error>,
-    },
     EvalStmtsWithScopeStackFailed{
         #[snafu(source(from(Error, Box::new)))]
         source: Box<Error>,
@@ -408,10 +451,6 @@ pub enum Error {
         #[snafu(source(from(Error, Box::new)))]
         source: Box<Error>,
     },
-    EvalObjectPropFailed{
-        #[snafu(source(from(Error, Box::new)))]
-        source: Box<Error>,
-    },
     EvalStartIndexFailed{
         #[snafu(source(from(Error, Box::new)))]
         source: Box<Error>,
@@ -503,6 +542,26 @@ pub enum Error {
     },
 }
 
+// `requested_exit_code` returns the code that was passed to `exit`, if `e` was
+// caused by a call to `exit`.
+pub fn requested_exit_code(e: &(dyn StdError + 'static)) -> Option<i32> {
+    let mut cause = e;
+    loop {
+        // Wrapped errors are boxed, so we check for both forms.
+        let maybe_error =
+            cause.downcast_ref::<Error>()
+                .or_else(|| {
+                    cause.downcast_ref::<Box<Error>>()
+                        .map(AsRef::as_ref)
+                });
+        if let Some(Error::Exit{code}) = maybe_error {
+            return Some(*code);
+        }
+
+        cause = cause.source()?;
+    }
+}
+
 pub fn render_type(v: &Value) -> String {
     let s =
         match v {
@@ -515,34 +574,33 @@ pub fn render_type(v: &Value) -> String {
             Value::List(_) => "list",
             Value::Object(_) => "object",
 
-            Value::BuiltinFunc{..} | Value::Func{..} => "func",
+            Value::BuiltinFunc{..}
+            | Value::EvalBuiltinFunc{..}
+            | Value::Func{..} => "func",
+            Value::BuiltinIter{..} => "iter",
         };
 
     s.to_string()
 }
 
-fn op_symbol(op: &BinaryOp) -> String {
-    let s =
-        match op {
-            BinaryOp::Sum => "+",
-            BinaryOp::Sub => "-",
-            BinaryOp::Mul => "*",
-            BinaryOp::Div => "/",
-            BinaryOp::Mod => "%",
-
-            BinaryOp::And => "&&",
-            BinaryOp::Or => "||",
-
-            BinaryOp::Eq => "==",
-            BinaryOp::Ne => "!=",
-            BinaryOp::Gt => ">",
-            BinaryOp::Gte => ">=",
-            BinaryOp::Lt => "<",
-            BinaryOp::Lte => "<=",
+fn render_suggestion(suggestion: Option<&str>) -> String {
+    match suggestion {
+        Some(name) => format!("; did you mean '{name}'?"),
+        None => String::new(),
+    }
+}
 
-            BinaryOp::RefEq => "===",
-            BinaryOp::RefNe => "!==",
-        };
+fn render_assert_msg(msg: Option<&str>) -> String {
+    match msg {
+        Some(msg) => format!(": {msg}"),
+        None => String::new(),
+    }
+}
 
-    s.to_string()
+fn render_path(path: &str) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!(" (at {path})")
+    }
 }
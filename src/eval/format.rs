@@ -0,0 +1,151 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use crate::ast::Align;
+use crate::ast::FormatKind;
+use crate::ast::FormatSpec;
+use crate::builtins::fns;
+use crate::eval::error::Error;
+use crate::eval::error::Result;
+use crate::eval::value::SourcedValue;
+use crate::eval::value::Value;
+
+// `format_value` renders `v` according to `spec`. Values are rendered using
+// the same renderer as `print`, unless `spec` requests a different rendering.
+pub fn format_value(v: &SourcedValue, spec: &FormatSpec) -> Result<String> {
+    let is_int = matches!(v.v, Value::Int(_));
+
+    if !is_int {
+        if spec.sign || spec.zero || spec.alternate {
+            return Err(Error::FormatSpecNotApplicable{
+                descr: "sign, '#' and '0' flags".to_string(),
+                value: v.v.clone(),
+            });
+        }
+
+        let numeric_kind = !matches!(
+            spec.kind,
+            FormatKind::Display | FormatKind::Repr,
+        );
+        if numeric_kind {
+            return Err(Error::FormatSpecNotApplicable{
+                descr: "numeric format types".to_string(),
+                value: v.v.clone(),
+            });
+        }
+    } else if spec.precision.is_some() {
+        return Err(Error::FormatSpecNotApplicable{
+            descr: "precision".to_string(),
+            value: v.v.clone(),
+        });
+    }
+
+    let mut body =
+        match (&v.v, spec.kind) {
+            (_, FormatKind::Repr) => fns::render_repr(&v.v),
+            (Value::Int(n), kind) => format_int(*n, kind, spec),
+            _ => fns::render(v)?,
+        };
+
+    if let Some(precision) = spec.precision {
+        body = body.chars().take(precision).collect();
+    }
+
+    Ok(pad(body, spec, is_int))
+}
+
+fn format_int(n: i64, kind: FormatKind, spec: &FormatSpec) -> String {
+    let magnitude = n.unsigned_abs();
+    let (digits, prefix) =
+        match kind {
+            FormatKind::LowerHex => (format!("{magnitude:x}"), "0x"),
+            FormatKind::UpperHex => (format!("{magnitude:X}"), "0x"),
+            FormatKind::Octal => (format!("{magnitude:o}"), "0o"),
+            FormatKind::Binary => (format!("{magnitude:b}"), "0b"),
+            FormatKind::Display | FormatKind::Repr =>
+                (magnitude.to_string(), ""),
+        };
+
+    let sign =
+        if n < 0 {
+            "-"
+        } else if spec.sign {
+            "+"
+        } else {
+            ""
+        };
+
+    let prefix = if spec.alternate { prefix } else { "" };
+
+    if spec.zero {
+        let lead_len = sign.len() + prefix.len();
+        let width = spec.width.unwrap_or(0).saturating_sub(lead_len);
+
+        format!("{sign}{prefix}{digits:0>width$}")
+    } else {
+        format!("{sign}{prefix}{digits}")
+    }
+}
+
+fn pad(body: String, spec: &FormatSpec, is_int: bool) -> String {
+    let width =
+        match spec.width {
+            Some(w) if !(is_int && spec.zero) => w,
+            _ => return body,
+        };
+
+    let len = body.chars().count();
+    if len >= width {
+        return body;
+    }
+
+    let fill = spec.fill.unwrap_or(' ').to_string();
+    let padding = width - len;
+    let default_align = if is_int { Align::Right } else { Align::Left };
+
+    let (left, right) =
+        match spec.align.unwrap_or(default_align) {
+            Align::Left => (0, padding),
+            Align::Centre => (padding / 2, padding - padding / 2),
+            Align::Right => (padding, 0),
+        };
+
+    format!("{}{}{}", fill.repeat(left), body, fill.repeat(right))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::eval::value;
+    use crate::interpolation;
+
+    #[test]
+    fn test_formats_values() {
+        let tests = &[
+            (value::new_int(42), ">5", "   42"),
+            (value::new_int(42), "<5", "42   "),
+            (value::new_int(42), "*^6", "**42**"),
+            (value::new_int(255), "x", "ff"),
+            (value::new_int(255), "#X", "0xFF"),
+            (value::new_int(-5), "05", "-0005"),
+            (value::new_int(5), "+#06b", "+0b101"),
+            (value::new_int(8), "o", "10"),
+            (value::new_str_from_string("abc".to_string()), ">5", "  abc"),
+            (value::new_str_from_string("abc".to_string()), ".2", "ab"),
+            (value::new_str_from_string("abc".to_string()), "?", "\"abc\""),
+            (value::new_bool(true), "^6", " true "),
+            (value::new_null(), "", "<null>"),
+        ];
+
+        for (v, raw_spec, exp) in tests {
+            let spec = interpolation::parse_spec(raw_spec)
+                .expect("couldn't parse format spec");
+            let act = format_value(v, &spec)
+                .expect("couldn't format value");
+
+            assert_eq!(&act, exp, "incorrect formatting for '{raw_spec}'");
+        }
+    }
+}
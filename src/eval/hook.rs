@@ -0,0 +1,76 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crate::ast::Location;
+use crate::ast::Stmt;
+use super::scope::ScopeStack;
+use super::value::SourcedValue;
+
+// `Hook` is notified as a program is evaluated, so that tools such as
+// debuggers can observe the evaluation. The default implementation of each
+// method does nothing.
+pub trait Hook {
+    // `before_stmt` is called before `stmt` is evaluated in `scopes`.
+    fn before_stmt(&mut self, _scopes: &ScopeStack, _stmt: &Stmt) {}
+
+    // `branch_taken` is called when the `if` statement at `if_loc` chooses
+    // the branch at index `branch`. The index of the `else` branch is the
+    // number of conditions in the statement, whether or not the `else` branch
+    // is written.
+    fn branch_taken(&mut self, _if_loc: Location, _branch: usize) {}
+
+    // `enter_call` is called before the body of the function `func_name` is
+    // evaluated with `args`, where `call_loc` is the location of the call. It
+    // isn't called for builtin functions.
+    fn enter_call(
+        &mut self,
+        _func_name: Option<&str>,
+        _call_loc: Location,
+        _args: &[SourcedValue],
+    ) {
+    }
+
+    // `exit_call` is called after the body of the function that was most
+    // recently entered has been evaluated, with the value returned by the
+    // function, or `None` if the evaluation failed.
+    fn exit_call(&mut self, _result: Option<&SourcedValue>) {}
+}
+
+// `Hooks` is a `Hook` that notifies each of its hooks in turn, so that more
+// than one tool can observe the same evaluation.
+pub struct Hooks(pub Vec<Arc<Mutex<dyn Hook + Send>>>);
+
+impl Hook for Hooks {
+    fn before_stmt(&mut self, scopes: &ScopeStack, stmt: &Stmt) {
+        for hook in &self.0 {
+            hook.try_lock().unwrap().before_stmt(scopes, stmt);
+        }
+    }
+
+    fn branch_taken(&mut self, if_loc: Location, branch: usize) {
+        for hook in &self.0 {
+            hook.try_lock().unwrap().branch_taken(if_loc, branch);
+        }
+    }
+
+    fn enter_call(
+        &mut self,
+        func_name: Option<&str>,
+        call_loc: Location,
+        args: &[SourcedValue],
+    ) {
+        for hook in &self.0 {
+            hook.try_lock().unwrap().enter_call(func_name, call_loc, args);
+        }
+    }
+
+    fn exit_call(&mut self, result: Option<&SourcedValue>) {
+        for hook in &self.0 {
+            hook.try_lock().unwrap().exit_call(result);
+        }
+    }
+}
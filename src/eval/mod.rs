@@ -4,7 +4,6 @@
 
 use std::collections::BTreeMap;
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::convert::TryInto;
 use std::path::PathBuf;
@@ -14,8 +13,12 @@ use std::sync::Mutex;
 
 pub mod bind;
 pub mod builtins;
+pub mod check;
 pub mod error;
+pub mod format;
+pub mod hook;
 pub mod scope;
+pub mod suggest;
 #[macro_use]
 pub mod value;
 
@@ -30,17 +33,17 @@ use self::builtins::Builtins;
 #[allow(clippy::wildcard_imports)]
 use self::error::*;
 use self::error::Error;
+use self::hook::Hook;
 use self::scope::ScopeStack;
 use self::value::BuiltinFunc;
+use self::value::BuiltinIterNext;
+use self::value::EvalBuiltinFunc;
 use self::value::Func;
 use self::value::ListRef;
 use self::value::SourcedValue;
 use self::value::Str;
 use self::value::Value;
 
-use crate::lexer::Lexer;
-use crate::parser::ExprParser;
-
 macro_rules! match_eval_expr {
     (
         ( $context:ident, $scopes:ident, $expr:expr )
@@ -59,6 +62,9 @@ pub struct EvaluationContext<'a> {
     // TODO `cur_script_dir` will later be exposed by reflection imports.
     #[allow(dead_code)]
     pub cur_script_dir: PathBuf,
+    // `hook`, if set, is notified as statements and function calls are
+    // evaluated.
+    pub hook: Option<Arc<Mutex<dyn Hook>>>,
 }
 
 pub fn eval_prog(
@@ -72,7 +78,7 @@ pub fn eval_prog(
     let bindings =
         global_bindings
             .into_iter()
-            .map(|(raw_expr, v)| ((raw_expr, (0, 0)), v))
+            .map(|(raw_expr, v)| ((raw_expr, Span::default()), v))
             .collect();
 
     let v = eval_stmts(context, scopes, bindings, stmts)
@@ -168,18 +174,24 @@ fn eval_stmt(
 )
     -> Result<Escape>
 {
-    match stmt {
-        Stmt::Block{block} => {
+    if let Some(hook) = &context.hook {
+        hook.try_lock().unwrap().before_stmt(scopes, stmt);
+    }
+
+    let (raw_stmt, span) = stmt;
+
+    match raw_stmt {
+        RawStmt::Block{block} => {
             eval_stmts_in_new_scope(context, scopes, block)
                 .context(EvalBlockFailed)?;
         },
 
-        Stmt::Expr{expr} => {
+        RawStmt::Expr{expr} => {
             eval_expr(context, scopes, expr)
                 .context(EvalExprFailed)?;
         },
 
-        Stmt::Declare{lhs, rhs} => {
+        RawStmt::Declare{lhs, rhs} => {
             let v = eval_expr(context, scopes, rhs)
                 .context(EvalDeclarationRhsFailed)?;
 
@@ -187,7 +199,7 @@ fn eval_stmt(
                 .context(DeclarationBindFailed)?;
         },
 
-        Stmt::Assign{lhs, rhs} => {
+        RawStmt::Assign{lhs, rhs} => {
             let v = eval_expr(context, scopes, rhs)
                 .context(EvalAssignmentRhsFailed)?;
 
@@ -195,14 +207,14 @@ fn eval_stmt(
                 .context(AssignmentBindFailed)?;
         },
 
-        Stmt::OpAssign{lhs, op, op_loc, rhs} => {
+        RawStmt::OpAssign{lhs, op, op_loc, rhs} => {
             let rhs_val = eval_expr(context, scopes, rhs)
                 .context(EvalBinOpRhsFailed)?;
 
             bind::bind_next(
                 context,
                 scopes,
-                &mut HashSet::new(),
+                &mut HashMap::new(),
                 lhs,
                 rhs_val,
                 Some((op.clone(), *op_loc)),
@@ -211,12 +223,14 @@ fn eval_stmt(
                 .context(OpAssignmentBindFailed)?;
         },
 
-        Stmt::If{branches, else_stmts} => {
-            for Branch{cond, stmts} in branches {
+        RawStmt::If{branches, else_stmts} => {
+            for (i, Branch{cond, stmts}) in branches.iter().enumerate() {
                 let b = eval_expr_to_bool(context, scopes, "condition", cond)
                     .context(EvalIfConditionFailed)?;
 
                 if b {
+                    notify_branch_taken(context, span, i);
+
                     let v = eval_stmts_in_new_scope(context, scopes, stmts)
                         .context(EvalIfStatementsFailed)?;
 
@@ -224,6 +238,8 @@ fn eval_stmt(
                 }
             }
 
+            notify_branch_taken(context, span, branches.len());
+
             if let Some(stmts) = else_stmts {
                 let v = eval_stmts_in_new_scope(context, scopes, stmts)
                     .context(EvalElseStatementsFailed)?;
@@ -232,7 +248,7 @@ fn eval_stmt(
             }
         },
 
-        Stmt::While{cond, stmts} => {
+        RawStmt::While{cond, stmts} => {
             loop {
                 let b = eval_expr_to_bool(context, scopes, "condition", cond)
                     .context(EvalWhileConditionFailed)?;
@@ -253,14 +269,25 @@ fn eval_stmt(
             }
         },
 
-        Stmt::For{lhs, iter, stmts} => {
+        RawStmt::For{lhs, iter, stmts} => {
             let iter_val = eval_expr(context, scopes, iter)
                     .context(EvalForIterFailed)?;
 
-            let pairs = value_to_pairs(&iter_val.v)
-                    .context(ConvertForIterToPairsFailed)?;
+            let mut for_iter =
+                if let Value::BuiltinIter{name, next} = iter_val.v {
+                    ForIter::BuiltinIter{name, next, next_index: 0}
+                } else {
+                    let pairs = value_to_pairs(&iter_val.v)
+                            .context(ConvertForIterToPairsFailed)?;
 
-            for (key, value) in pairs {
+                    ForIter::Pairs(pairs.into_iter())
+                };
+
+            let (_, span) = iter;
+            let (line, col) = span.loc();
+            while let Some((key, value)) =
+                    next_for_pair(&mut for_iter, (line, col))?
+            {
                 let pair = value::new_list(vec![key, value]);
 
                 let new_bindings = vec![(lhs.clone(), pair)];
@@ -277,15 +304,15 @@ fn eval_stmt(
             }
         },
 
-        Stmt::Break{loc} => {
+        RawStmt::Break{loc} => {
             return Ok(Escape::Break{loc: *loc});
         },
 
-        Stmt::Continue{loc} => {
+        RawStmt::Continue{loc} => {
             return Ok(Escape::Continue{loc: *loc});
         },
 
-        Stmt::Func{name: (name, loc), args, collect_args, stmts} => {
+        RawStmt::Func{name: (name, loc), args, collect_args, stmts} => {
             validate_args(args)
                 .context(ValidateArgsFailed)?;
 
@@ -302,28 +329,43 @@ fn eval_stmt(
                 .context(DeclareFunctionFailed)?;
         },
 
-        Stmt::Return{loc, expr} => {
+        RawStmt::Return{loc, expr} => {
             let v = eval_expr(context, scopes, expr)
                 .context(EvalReturnExprFailed)?;
 
             return Ok(Escape::Return{value: v, loc: *loc});
         },
+
+        RawStmt::Error => {
+            return Err(Error::Dev{
+                msg: "attempted to evaluate an invalid statement".to_string(),
+            });
+        },
     }
 
     Ok(Escape::None)
 }
 
+// `notify_branch_taken` notifies the hook of `context`, if there is one, that
+// the `if` statement at `span` chose the branch at index `branch`.
+fn notify_branch_taken(
+    context: &EvaluationContext,
+    span: &Span,
+    branch: usize,
+) {
+    if let Some(hook) = &context.hook {
+        hook.try_lock().unwrap().branch_taken(span.loc(), branch);
+    }
+}
+
 fn validate_args(args: &[Expr]) -> Result<()> {
     let mut queue = VecDeque::from(args.to_owned());
     let mut name_locs = HashMap::<String, Location>::new();
 
     while let Some(arg) = queue.pop_front() {
-        let (raw_arg, loc) = arg;
-        let new_loc_err = |source| {
-            let (line, col) = loc;
-
-            Err(Error::AtLoc{source: Box::new(source), line, col})
-        };
+        let (raw_arg, span) = arg;
+        let loc = span.loc();
+        let new_loc_err = |source| Err(new_span_err(source, &span));
         let new_invalid_bind_error = |s: &str| {
             new_loc_err(Error::InvalidBindTarget{descr: s.to_string()})
         };
@@ -400,6 +442,46 @@ fn validate_args(args: &[Expr]) -> Result<()> {
     Ok(())
 }
 
+// `ForIter` is the source of the "index, value" pairs that a `for` loop
+// iterates over.
+enum ForIter {
+    Pairs(std::vec::IntoIter<(SourcedValue, SourcedValue)>),
+    // `BuiltinIter` gets each value by calling `next`, so that values are
+    // only produced as they're needed.
+    BuiltinIter{name: String, next: BuiltinIterNext, next_index: i64},
+}
+
+// `next_for_pair` returns the next "index, value" pair from `for_iter`, or
+// `None` if there are no more pairs. `loc` is the location of the iterator
+// expression.
+fn next_for_pair(for_iter: &mut ForIter, loc: Location)
+    -> Result<Option<(SourcedValue, SourcedValue)>>
+{
+    match for_iter {
+        ForIter::Pairs(pairs) => {
+            Ok(pairs.next())
+        },
+        ForIter::BuiltinIter{name, next, next_index} => {
+            let maybe_value = next()
+                .context(EvalBuiltinFuncCallFailed{
+                    func_name: Some(name.clone()),
+                    call_loc: loc,
+                })?;
+
+            let value =
+                match maybe_value {
+                    Some(value) => value,
+                    None => return Ok(None),
+                };
+
+            let key = value::new_int(*next_index);
+            *next_index += 1;
+
+            Ok(Some((key, value)))
+        },
+    }
+}
+
 // `value_to_pairs` returns the "index, value" pairs in `v`, if `v` represents
 // an "iterable" type.
 fn value_to_pairs(v: &Value) -> Result<Vec<(SourcedValue, SourcedValue)>> {
@@ -469,10 +551,8 @@ fn eval_expr(
     scopes: &mut ScopeStack,
     expr: &Expr,
 ) -> Result<SourcedValue> {
-    let (raw_expr, (line, col)) = expr;
-    let new_loc_err = |source| {
-        Err(Error::AtLoc{source: Box::new(source), line: *line, col: *col})
-    };
+    let (raw_expr, span) = expr;
+    let new_loc_err = |source| Err(new_span_err(source, span));
 
     match raw_expr {
         RawExpr::Null => Ok(value::new_null()),
@@ -483,14 +563,7 @@ fn eval_expr(
 
         RawExpr::Str{s, interpolation_slots} => {
             if let Some(slots) = interpolation_slots {
-                let v =
-                    interpolate_string(
-                        context,
-                        scopes,
-                        s,
-                        slots,
-                        (line, col),
-                    )
+                let v = interpolate_string(context, scopes, s, slots)
                     .context(InterpolateStringFailed)?;
                 Ok(value::new_str_from_string(v))
             } else {
@@ -502,9 +575,10 @@ fn eval_expr(
             let v =
                 match scopes.get(name) {
                     Some(v) => v,
-                    None => return new_loc_err(
-                        Error::Undefined{name: name.clone()},
-                    ),
+                    None => return new_loc_err(Error::Undefined{
+                        name: name.clone(),
+                        suggestion: suggest::closest(name, &scopes.names()),
+                    }),
                 };
 
             Ok(v)
@@ -518,6 +592,7 @@ fn eval_expr(
                 .context(EvalBinOpRhsFailed)?;
 
             let v = apply_binary_operation(op, op_loc, &lhs_val.v, &rhs_val.v)
+                .map_err(|e| with_span(e, span))
                 .context(ApplyBinOpFailed)?;
 
             Ok(value::new_val_ref_with_no_source(v))
@@ -578,13 +653,23 @@ fn eval_expr(
                         eval_expr_to_str(context, scopes, "property", locat)
                             .context(EvalObjectIndexFailed)?;
 
+                    let maybe_v =
+                        lock_deref!(props).get(&name).map(|v| v.v.clone());
                     let v =
-                        match lock_deref!(props).get(&name) {
-                            Some(value) => {
-                                value.v.clone()
+                        match maybe_v {
+                            Some(v) => {
+                                v
                             },
                             None => {
-                                return new_loc_err(Error::PropNotFound{name});
+                                let suggestion = suggest::closest(
+                                    &name,
+                                    lock_deref!(props).keys(),
+                                );
+
+                                return new_loc_err(Error::PropNotFound{
+                                    name,
+                                    suggestion,
+                                });
                             },
                         };
 
@@ -717,44 +802,39 @@ fn eval_expr(
                                 },
 
                                 value => {
-                                    let (_, (line, col)) = expr;
+                                    let (_, span) = expr;
 
-                                    return Err(Error::AtLoc{
-                                        source: Box::new(
-                                            Error::SpreadNonObjectInObject{
-                                                value,
-                                            },
-                                        ),
-                                        line: *line,
-                                        col: *col,
-                                    });
+                                    return Err(new_span_err(
+                                        Error::SpreadNonObjectInObject{value},
+                                        span,
+                                    ));
                                 },
                             });
                         } else {
-                            let (raw_expr, (line, col)) = expr;
+                            let (raw_expr, span) = expr;
 
                             if let RawExpr::Var{name} = raw_expr {
                                 let v =
                                     match scopes.get(name) {
                                         Some(v) => v.clone(),
-                                        None => return Err(Error::AtLoc{
-                                            source: Box::new(Error::Undefined{
-                                                name: name.clone()
-                                            }),
-                                            line: *line,
-                                            col: *col,
-                                        }),
+                                        None => return Err(new_span_err(
+                                            Error::Undefined{
+                                                name: name.clone(),
+                                                suggestion: suggest::closest(
+                                                    name,
+                                                    &scopes.names(),
+                                                ),
+                                            },
+                                            span,
+                                        )),
                                     };
 
                                 vals.insert(name.to_string(), v);
                             } else {
-                                return Err(Error::AtLoc{
-                                    source: Box::new(
-                                        Error::ObjectPropShorthandNotVar,
-                                    ),
-                                    line: *line,
-                                    col: *col,
-                                });
+                                return Err(new_span_err(
+                                    Error::ObjectPropShorthandNotVar,
+                                    span,
+                                ));
                             }
                         }
                     },
@@ -781,8 +861,12 @@ fn eval_expr(
                             &context.builtins.type_functions.lists,
                         Value::Object(_) =>
                             &context.builtins.type_functions.objects,
-                        Value::BuiltinFunc{..} | Value::Func{..}  =>
+                        Value::BuiltinFunc{..}
+                        | Value::EvalBuiltinFunc{..}
+                        | Value::Func{..} =>
                             &context.builtins.type_functions.funcs,
+                        Value::BuiltinIter{..} =>
+                            &context.builtins.type_functions.iters,
 
                         Value::Null => {
                             return new_loc_err(Error::TypeFunctionOnNull)
@@ -800,21 +884,27 @@ fn eval_expr(
                     }
                 };
 
+            let maybe_v =
+                lock_deref!(namespace).get(name).map(|v| v.v.clone());
             let v =
-                if let Some(value) = lock_deref!(namespace).get(name) {
-                    Ok(value::new_val_ref_with_source(
-                        value.v.clone(),
-                        source.v.clone(),
-                    ))
-                } else if *type_prop {
-                    new_loc_err(Error::TypeFunctionNotFound{
-                        value: source.v.clone(),
-                        name: name.clone(),
-                    })
+                if let Some(v) = maybe_v {
+                    Ok(value::new_val_ref_with_source(v, source.v.clone()))
                 } else {
-                    new_loc_err(Error::PropNotFound{
-                        name: name.clone(),
-                    })
+                    let suggestion =
+                        suggest::closest(name, lock_deref!(namespace).keys());
+
+                    if *type_prop {
+                        new_loc_err(Error::TypeFunctionNotFound{
+                            value: source.v.clone(),
+                            name: name.clone(),
+                            suggestion,
+                        })
+                    } else {
+                        new_loc_err(Error::PropNotFound{
+                            name: name.clone(),
+                            suggestion,
+                        })
+                    }
                 };
 
             v
@@ -833,7 +923,10 @@ fn eval_expr(
         },
 
         RawExpr::Call{func, args} => {
-            let v = eval_call(context, scopes, func, args, (line, col))
+            let (line, col) = span.loc();
+
+            let v = eval_call(context, scopes, func, args, (&line, &col))
+                .map_err(|e| with_span(e, span))
                 .context(EvalCallFailed)?;
 
             Ok(v)
@@ -841,13 +934,41 @@ fn eval_expr(
     }
 }
 
+// `new_span_err` returns `source`, located at the start of `span`, so that the
+// expression at `span` is highlighted when the error is reported.
+fn new_span_err(source: Error, span: &Span) -> Error {
+    let (line, col) = span.loc();
+
+    Error::AtSpan{source: Box::new(source), line, col, span: *span}
+}
+
+// `with_span` adds `span` to `e`, if `e` was located by the evaluation of the
+// expression at `span`, so that the whole expression is highlighted when `e`
+// is reported. Errors from builtin functions are located at the call, so
+// they're also given the span of the call.
+fn with_span(e: Error, span: &Span) -> Error {
+    match e {
+        Error::AtLoc{source, line, col} => {
+            Error::AtSpan{source, line, col, span: *span}
+        },
+        Error::EvalBuiltinFuncCallFailed{call_loc: (line, col), ..} => {
+            Error::AtSpan{source: Box::new(e), line, col, span: *span}
+        },
+        e => {
+            e
+        },
+    }
+}
+
 enum CallBinding {
     BuiltinFunc{
         f: BuiltinFunc,
         this: Option<SourcedValue>,
         args: Vec<SourcedValue>,
     },
+    EvalBuiltinFunc{f: EvalBuiltinFunc, args: Vec<SourcedValue>},
     Func{
+        args: Vec<SourcedValue>,
         bindings: Vec<(Expr, SourcedValue)>,
         closure: ScopeStack,
         stmts: Block,
@@ -1162,10 +1283,8 @@ fn eval_expr_to_str(
 )
     -> Result<String>
 {
-    let (_, (line, col)) = expr;
-    let new_loc_err = |source| {
-        Err(Error::AtLoc{source: Box::new(source), line: *line, col: *col})
-    };
+    let (_, span) = expr;
+    let new_loc_err = |source| Err(new_span_err(source, span));
 
     let raw_str =
         match_eval_expr!((context, scopes, expr) {
@@ -1197,10 +1316,8 @@ fn eval_expr_to_bool(
 )
     -> Result<bool>
 {
-    let (_, (line, col)) = expr;
-    let new_loc_err = |source| {
-        Err(Error::AtLoc{source: Box::new(source), line: *line, col: *col})
-    };
+    let (_, span) = expr;
+    let new_loc_err = |source| Err(new_span_err(source, span));
 
     match_eval_expr!((context, scopes, expr) {
         Value::Bool(b) =>
@@ -1223,10 +1340,8 @@ fn eval_expr_to_i64(
 )
     -> Result<i64>
 {
-    let (_, (line, col)) = expr;
-    let new_loc_err = |source| {
-        Err(Error::AtLoc{source: Box::new(source), line: *line, col: *col})
-    };
+    let (_, span) = expr;
+    let new_loc_err = |source| Err(new_span_err(source, span));
 
     match_eval_expr!((context, scopes, expr) {
         Value::Int(n) =>
@@ -1248,10 +1363,8 @@ fn eval_expr_to_index(
 )
     -> Result<usize>
 {
-    let (_, (line, col)) = expr;
-    let new_loc_err = |source| {
-        Err(Error::AtLoc{source: Box::new(source), line: *line, col: *col})
-    };
+    let (_, span) = expr;
+    let new_loc_err = |source| Err(new_span_err(source, span));
 
     let index = eval_expr_to_i64(context, scopes, "index", expr)
         .context(EvalIndexToI64Failed)?;
@@ -1327,13 +1440,12 @@ fn eval_list_items(
             },
 
             value => {
-                let (_, (line, col)) = item.expr;
+                let (_, span) = item.expr;
 
-                return Err(Error::AtLoc{
-                    source: Box::new(Error::SpreadNonListInList{value}),
-                    line,
-                    col,
-                })
+                return Err(new_span_err(
+                    Error::SpreadNonListInList{value},
+                    &span,
+                ));
             },
         };
     }
@@ -1341,7 +1453,6 @@ fn eval_list_items(
     Ok(vals)
 }
 
-#[allow(clippy::too_many_lines)]
 fn eval_call(
     context: &EvaluationContext,
     scopes: &mut ScopeStack,
@@ -1351,17 +1462,31 @@ fn eval_call(
 )
     -> Result<SourcedValue>
 {
-    let (line, col) = loc;
-    let new_loc_err = |source| {
-        Err(Error::AtLoc{source: Box::new(source), line: *line, col: *col})
-    };
-
     let arg_vals = eval_list_items(context, scopes, args)
         .context(EvalCallArgsFailed)?;
 
     let func_val = eval_expr(context, scopes, func)
         .context(EvalCallFuncFailed)?;
 
+    call_value(context, func_val, arg_vals, loc)
+}
+
+// `call_value` calls `func_val` with `arg_vals`, where `loc` is the location
+// of the call.
+#[allow(clippy::too_many_lines)]
+pub fn call_value(
+    context: &EvaluationContext,
+    func_val: SourcedValue,
+    arg_vals: Vec<SourcedValue>,
+    loc: (&usize, &usize),
+)
+    -> Result<SourcedValue>
+{
+    let (line, col) = loc;
+    let new_loc_err = |source| {
+        Err(Error::AtLoc{source: Box::new(source), line: *line, col: *col})
+    };
+
     let (func_name, v) =
         {
             let SourcedValue{v, source} = func_val;
@@ -1376,6 +1501,13 @@ fn eval_call(
                     )
                 },
 
+                Value::EvalBuiltinFunc{name, f} => {
+                    (
+                        Some(name),
+                        CallBinding::EvalBuiltinFunc{f, args: arg_vals},
+                    )
+                },
+
                 Value::Func(f) => {
                     let Func{
                         name,
@@ -1421,7 +1553,7 @@ fn eval_call(
                         bindings.push((
                             (
                                 RawExpr::Var{name: "this".to_string()},
-                                (0, 0),
+                                Span::default(),
                             ),
                             value::new_val_ref_with_no_source(this),
                         ));
@@ -1430,6 +1562,7 @@ fn eval_call(
                     (
                         name.clone(),
                         CallBinding::Func{
+                            args: arg_vals,
                             bindings,
                             closure: closure.clone(),
                             stmts: stmts.clone(),
@@ -1453,28 +1586,45 @@ fn eval_call(
                     })?
             },
 
-            CallBinding::Func{bindings, mut closure, stmts} => {
-                let v = eval_stmts(
-                    context,
-                    &mut closure,
-                    bindings,
-                    &stmts,
-                )
-                    .context(EvalFuncCallFailed{
+            CallBinding::Func{args, bindings, mut closure, stmts} => {
+                if let Some(hook) = &context.hook {
+                    let call_loc = (*line, *col);
+
+                    hook.try_lock()
+                        .unwrap()
+                        .enter_call(func_name.as_deref(), call_loc, &args);
+                }
+
+                let result =
+                    eval_stmts(context, &mut closure, bindings, &stmts)
+                        .context(EvalFuncCallFailed{
+                            func_name,
+                            call_loc: (*line, *col),
+                        })
+                        .and_then(|v| match v {
+                            Escape::None =>
+                                Ok(value::new_null()),
+                            Escape::Break{..} =>
+                                Err(Error::BreakOutsideLoop),
+                            Escape::Continue{..} =>
+                                Err(Error::ContinueOutsideLoop),
+                            Escape::Return{value, ..} =>
+                                Ok(value),
+                        });
+
+                if let Some(hook) = &context.hook {
+                    hook.try_lock().unwrap().exit_call(result.as_ref().ok());
+                }
+
+                result?
+            },
+
+            CallBinding::EvalBuiltinFunc{f, args} => {
+                f(context, args, (*line, *col))
+                    .context(EvalBuiltinFuncCallFailed{
                         func_name,
                         call_loc: (*line, *col),
-                    })?;
-
-                match v {
-                    Escape::None =>
-                        value::new_null(),
-                    Escape::Break{..} =>
-                        return Err(Error::BreakOutsideLoop),
-                    Escape::Continue{..} =>
-                        return Err(Error::ContinueOutsideLoop),
-                    Escape::Return{value, ..} =>
-                        value,
-                }
+                    })?
             },
         };
 
@@ -1485,80 +1635,32 @@ fn interpolate_string(
     context: &EvaluationContext,
     scopes: &mut ScopeStack,
     s: &str,
-    interpolation_slots: &Vec<(usize, usize)>,
-    loc: (&usize, &usize),
+    interpolation_slots: &Vec<InterpolationSlot>,
 )
     -> Result<String>
 {
-    let (line, col) = loc;
-    let new_loc_err = |source, col| {
-        Err(Error::AtLoc{source: Box::new(source), line: *line, col})
-    };
-
-    let parser = ExprParser::new();
-
     let mut result: Vec<String> = vec![];
 
     let mut last_slot_end = 0;
 
-    for cur_slot in interpolation_slots {
-        let (cur_slot_start, cur_slot_end) = cur_slot;
-        result.push(s[last_slot_end .. *cur_slot_start].to_string());
-
-        // We shorten the slot to skip the delimiters (`${` at the start and
-        // `}` at the end).
-        let directive = &s[(cur_slot_start+2) .. (cur_slot_end-1)];
-
-        let slot_col = col + cur_slot_start + 4;
-
-        let mut lexer = Lexer::new(directive);
+    for InterpolationSlot{start, end, expr, spec} in interpolation_slots {
+        result.push(s[last_slot_end .. *start].to_string());
 
-        let ast =
-            match parser.parse(&mut lexer) {
-                Ok(v) => v,
-                Err(e) => return new_loc_err(
-                    Error::InterpolateStringParseFailed{
-                        source_str: format!("{e:?}"),
-                    },
-                    slot_col,
-                ),
-            };
-
-        // We catch the evaluation error manually so that we can modify the
-        // location of the error to account for the string location.
-        let v =
-            match eval_expr(context, scopes, &ast) {
-                Ok(v) => v,
-                Err(e) => return new_loc_err(
-                    Error::InterpolateStringEvalExprFailed{
-                        source: Box::new(e),
-                    },
-                    slot_col,
-                ),
-            };
+        let v = eval_expr(context, scopes, expr)
+            .context(InterpolateStringEvalExprFailed)?;
 
-        match v.v {
-            Value::Str(s) => {
-                match String::from_utf8(s.clone()) {
-                    Ok(s) => result.push(s),
-                    Err(source) => return new_loc_err(
-                        Error::StringConstructionFailed{
-                            source,
-                            descr: "interpolated slot".to_string(),
-                        },
-                        slot_col,
-                    ),
-                }
+        match format::format_value(&v, spec) {
+            Ok(s) => {
+                result.push(s);
             },
-            value => {
-                return new_loc_err(
-                    Error::InterpolatedValueNotString{value},
-                    slot_col,
-                );
+            Err(source) => {
+                let (_, span) = expr;
+
+                return Err(new_span_err(source, span));
             },
         }
 
-        last_slot_end = *cur_slot_end;
+        last_slot_end = *end;
     }
 
     result.push(s[last_slot_end ..].to_string());
@@ -2,6 +2,7 @@
 // Use of this source code is governed by an MIT
 // licence that can be found in the LICENCE file.
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -75,6 +76,31 @@ impl ScopeStack {
 
         false
     }
+
+    // `names` returns the names that are defined in any scope of this
+    // `ScopeStack`.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![];
+        for scope in &self.0 {
+            names.extend(scope.try_lock().unwrap().keys().cloned());
+        }
+
+        names
+    }
+
+    // `vars` returns the variables that are visible in this `ScopeStack`,
+    // sorted by name. Variables in inner scopes hide variables with the same
+    // name in outer scopes.
+    pub fn vars(&self) -> Vec<(String, SourcedValue)> {
+        let mut vars = BTreeMap::new();
+        for scope in &self.0 {
+            for (name, (v, _)) in scope.try_lock().unwrap().iter() {
+                vars.insert(name.clone(), v.clone());
+            }
+        }
+
+        vars.into_iter().collect()
+    }
 }
 
 pub fn set(slot: &mut SourcedValue, v: SourcedValue) {
@@ -0,0 +1,122 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+// `closest` returns the candidate that is most similar to `name`, if the edit
+// distance between them is small enough for the candidate to plausibly be
+// what was intended. Candidates that would require every character of `name`
+// to be changed are never suggested. Ties are broken alphabetically so that
+// suggestions are deterministic.
+pub fn closest<'a, I>(name: &str, candidates: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let name_len = name.chars().count();
+    let max_dist = (name_len / 3).max(1).min(name_len.saturating_sub(1));
+
+    let mut best: Option<(usize, &String)> = None;
+    for candidate in candidates {
+        if candidate == name {
+            continue;
+        }
+
+        let dist = edit_distance(name, candidate);
+        if dist > max_dist {
+            continue;
+        }
+
+        let is_better =
+            match best {
+                Some((best_dist, best_candidate)) => {
+                    (dist, candidate) < (best_dist, best_candidate)
+                },
+                None => {
+                    true
+                },
+            };
+        if is_better {
+            best = Some((dist, candidate));
+        }
+    }
+
+    best.map(|(_, candidate)| candidate.clone())
+}
+
+// `edit_distance` returns the number of single-character insertions,
+// deletions, substitutions and adjacent transpositions that are needed to turn
+// `a` into `b`. Transpositions are counted so that common typos such as
+// `pritn` are considered close to the intended name.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // `dists[i][j]` is the distance between the first `i` characters of `a`
+    // and the first `j` characters of `b`.
+    let mut dists = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in dists.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, dist) in dists[0].iter_mut().enumerate() {
+        *dist = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i-1] != b[j-1]);
+            let mut dist = (dists[i-1][j-1] + cost)
+                .min(dists[i-1][j] + 1)
+                .min(dists[i][j-1] + 1);
+            if i > 1 && j > 1 && a[i-1] == b[j-2] && a[i-2] == b[j-1] {
+                dist = dist.min(dists[i-2][j-2] + 1);
+            }
+            dists[i][j] = dist;
+        }
+    }
+
+    dists[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        let tests = &[
+            ("", "", 0),
+            ("abc", "", 3),
+            ("length", "length", 0),
+            ("lenght", "length", 1),
+            ("pritn", "print", 1),
+            ("lengt", "length", 1),
+            ("kitten", "sitting", 3),
+        ];
+
+        for (a, b, exp) in tests {
+            assert_eq!(edit_distance(a, b), *exp, "'{a}' -> '{b}'");
+        }
+    }
+
+    #[test]
+    fn test_closest() {
+        let candidates: Vec<String> = ["len", "length", "keys", "print"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let tests = &[
+            ("lenght", Some("length")),
+            ("pritn", Some("print")),
+            ("key", Some("keys")),
+            ("lem", Some("len")),
+            ("l", None),
+            ("print", None),
+            ("xyz", None),
+        ];
+
+        for (name, exp) in tests {
+            let act = closest(name, &candidates);
+
+            assert_eq!(act.as_deref(), *exp, "incorrect suggestion: {name}");
+        }
+    }
+}
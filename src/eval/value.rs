@@ -7,8 +7,10 @@ use std::sync::Arc;
 use std::sync::Mutex;
 
 use crate::ast::Block;
+use crate::eval::EvaluationContext;
 use crate::eval::Expr;
 use crate::eval::Result;
+use crate::lexer::Location;
 use super::scope::ScopeStack;
 
 // `lock_deref` must be defined as a macro, because a reference to the
@@ -63,7 +65,14 @@ pub enum Value {
     Object(ObjectRef),
 
     BuiltinFunc{name: String, f: BuiltinFunc},
+    // `EvalBuiltinFunc` is a builtin function that needs access to the
+    // evaluation context, such as to call functions that are defined in Seed.
+    EvalBuiltinFunc{name: String, f: EvalBuiltinFunc},
     Func(Arc<Mutex<Func>>),
+
+    // `BuiltinIter` is an iterator that `for` loops get values from by
+    // calling `next`, until it returns `None`.
+    BuiltinIter{name: String, next: BuiltinIterNext},
 }
 
 pub type Str = Vec<u8>;
@@ -82,6 +91,14 @@ pub type Object = BTreeMap<String, SourcedValue>;
 pub type BuiltinFunc =
     fn(Option<SourcedValue>, Vec<SourcedValue>) -> Result<SourcedValue>;
 
+// `EvalBuiltinFunc`s are passed the location of the call, so that they can
+// make calls of their own from it.
+pub type EvalBuiltinFunc =
+    fn(&EvaluationContext, Vec<SourcedValue>, Location)
+        -> Result<SourcedValue>;
+
+pub type BuiltinIterNext = fn() -> Result<Option<SourcedValue>>;
+
 #[derive(Clone, Debug)]
 pub struct Func {
     pub name: Option<String>,
@@ -143,6 +160,18 @@ pub fn new_built_in_func(name: String, f: BuiltinFunc) -> SourcedValue {
     new_val_ref_with_no_source(Value::BuiltinFunc{name, f})
 }
 
+pub fn new_eval_built_in_func(name: String, f: EvalBuiltinFunc)
+    -> SourcedValue
+{
+    new_val_ref_with_no_source(Value::EvalBuiltinFunc{name, f})
+}
+
+pub fn new_built_in_iter(name: String, next: BuiltinIterNext)
+    -> SourcedValue
+{
+    new_val_ref_with_no_source(Value::BuiltinIter{name, next})
+}
+
 pub fn ref_eq<T>(a: &Arc<Mutex<T>>, b: &Arc<Mutex<T>>) -> bool {
     Arc::ptr_eq(a, b)
 }
@@ -0,0 +1,813 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::ast::BinaryOp;
+use crate::ast::Block;
+use crate::ast::Expr;
+use crate::ast::ListItem;
+use crate::ast::Prog;
+use crate::ast::PropItem;
+use crate::ast::RawExpr;
+use crate::ast::RawStmt;
+use crate::ast::Span;
+use crate::ast::Stmt;
+use crate::lexer::Comment;
+use crate::lexer::Lexer;
+use crate::lexer::Position;
+use crate::lexer::Token;
+
+// `MAX_WIDTH` is the width that lists, objects, argument lists and parameter
+// lists are wrapped at.
+const MAX_WIDTH: usize = 79;
+
+const INDENT: &str = "    ";
+
+// `format_prog` returns the canonical formatting of `prog`, which must have
+// been parsed from `src` without errors.
+//
+// Statements are written one per line, and blocks are indented by four
+// spaces. Lists, objects, argument lists and parameter lists that don't fit
+// on one line are written with one item per line. At most one blank line is
+// kept between statements. Comments are kept on the line that they were
+// written on, except for comments inside expressions that are wrapped or
+// joined, which are moved after the statement that contains them. Literals
+// are written as they appear in `src`.
+pub fn format_prog(src: &str, Prog::Body{stmts}: &Prog) -> String {
+    let mut lexer = Lexer::new_keeping_comments(src);
+
+    let mut block_ends = BTreeMap::new();
+    let mut open_braces = vec![];
+    // `src` has already been parsed, so we don't expect any lexing errors.
+    for (start, t, _) in lexer.by_ref().flatten() {
+        match t {
+            Token::BraceOpen => {
+                open_braces.push(start);
+            },
+            Token::BraceClose => {
+                if let Some(open) = open_braces.pop() {
+                    block_ends.insert(open.offset, (open, start));
+                }
+            },
+            _ => {
+            },
+        }
+    }
+
+    let comments = lexer.take_comments();
+    let mut f = Formatter{
+        src,
+        written: vec![false; comments.len()],
+        comments,
+        block_ends,
+        no_wrap: false,
+    };
+
+    f.stmts(stmts, 0, 0, src.len())
+}
+
+struct Formatter<'a> {
+    src: &'a str,
+    comments: Vec<Comment>,
+    // `written` records which of `comments` have been written.
+    written: Vec<bool>,
+    // `block_ends` maps the offset of each `{` in the source to the
+    // positions of the `{` and its matching `}`.
+    block_ends: BTreeMap<usize, (Position, Position)>,
+    // `no_wrap` is `true` while checking whether a sequence fits on one
+    // line, so that nested sequences aren't wrapped.
+    no_wrap: bool,
+}
+
+impl Formatter<'_> {
+    // `stmts` returns `stmts` formatted at `indent`, with each statement on
+    // its own line, along with the comments that appear between `start` and
+    // `end`.
+    fn stmts(
+        &mut self,
+        stmts: &Block,
+        indent: usize,
+        start: usize,
+        end: usize,
+    )
+        -> String
+    {
+        let mut out = String::new();
+        let mut last_line = None;
+
+        for (i, stmt) in stmts.iter().enumerate() {
+            let (_, span) = stmt;
+
+            out += &self.comments_before(
+                start,
+                span.start.offset,
+                indent,
+                &mut last_line,
+            );
+            if last_line.is_some_and(|l| span.start.line > l + 1) {
+                out.push('\n');
+            }
+
+            out += &INDENT.repeat(indent);
+            out += &self.stmt(stmt, indent);
+            // A comment that follows several statements on the same line is
+            // kept with the last of them.
+            let next = stmts.get(i + 1);
+            let is_last_on_line =
+                next.is_none_or(|(_, next)| next.start.line > span.end.line);
+            if is_last_on_line {
+                let before = next.map_or(end, |(_, next)| next.start.offset);
+                out += &self.trailing_comment(span.end, before);
+            }
+            out.push('\n');
+
+            // Any comments inside the statement that haven't been written
+            // yet were inside an expression, so we write them after the
+            // statement.
+            let mut comment_line = None;
+            out += &self.comments_before(
+                start,
+                span.end.offset,
+                indent,
+                &mut comment_line,
+            );
+            last_line = Some(span.end.line);
+        }
+
+        out += &self.comments_before(start, end, indent, &mut last_line);
+
+        out
+    }
+
+    // `comments_before` returns the unwritten comments that appear between
+    // `start` and `offset`, with each comment on its own line at `indent`.
+    // `last_line` is the source line of the last statement or comment that was
+    // written, and is used to preserve blank lines.
+    fn comments_before(
+        &mut self,
+        start: usize,
+        offset: usize,
+        indent: usize,
+        last_line: &mut Option<usize>,
+    )
+        -> String
+    {
+        let mut out = String::new();
+        for (i, comment) in self.comments.iter().enumerate() {
+            if comment.pos.offset >= offset {
+                break;
+            }
+            if self.written[i] || comment.pos.offset < start {
+                continue;
+            }
+            self.written[i] = true;
+
+            if last_line.is_some_and(|l| comment.pos.line > l + 1) {
+                out.push('\n');
+            }
+            out += &INDENT.repeat(indent);
+            out += comment.text.trim_end();
+            out.push('\n');
+            *last_line = Some(comment.pos.line);
+        }
+
+        out
+    }
+
+    // `trailing_comment` returns the unwritten comment that follows `pos` on
+    // the same line and appears before `before`, prefixed with a space, or an
+    // empty string if there is no such comment.
+    fn trailing_comment(&mut self, pos: Position, before: usize) -> String {
+        for (i, comment) in self.comments.iter().enumerate() {
+            if self.written[i]
+                || comment.pos.line != pos.line
+                || comment.pos.offset < pos.offset
+                || comment.pos.offset >= before
+            {
+                continue;
+            }
+            self.written[i] = true;
+
+            return format!(" {}", comment.text.trim_end());
+        }
+
+        String::new()
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn stmt(&mut self, (raw_stmt, span): &Stmt, indent: usize) -> String {
+        let col = indent * INDENT.len();
+
+        match raw_stmt {
+            RawStmt::Block{block} => {
+                let (s, _) = self.block(block, span.start.offset, indent);
+
+                s
+            },
+            RawStmt::Expr{expr} => {
+                self.expr(expr, indent, col)
+            },
+            RawStmt::Declare{lhs, rhs} => {
+                self.binding(lhs, ":=", rhs, indent)
+            },
+            RawStmt::Assign{lhs, rhs} => {
+                self.binding(lhs, "=", rhs, indent)
+            },
+            RawStmt::OpAssign{lhs, op, rhs, ..} => {
+                let op = format!("{}=", op.symbol());
+
+                self.binding(lhs, &op, rhs, indent)
+            },
+            RawStmt::If{branches, else_stmts} => {
+                let mut out = String::new();
+                let mut next_offset = span.start.offset;
+                for (i, branch) in branches.iter().enumerate() {
+                    if i > 0 {
+                        out += " else ";
+                    }
+                    out += "if ";
+                    out += &self.expr(
+                        &branch.cond,
+                        indent,
+                        end_col(col, &out),
+                    );
+
+                    let (_, cond_span) = &branch.cond;
+                    let (block, end) = self.block(
+                        &branch.stmts,
+                        cond_span.end.offset,
+                        indent,
+                    );
+                    out += " ";
+                    out += &block;
+                    next_offset = end;
+                }
+                if let Some(stmts) = else_stmts {
+                    let (block, _) = self.block(stmts, next_offset, indent);
+                    out += " else ";
+                    out += &block;
+                }
+
+                out
+            },
+            RawStmt::While{cond, stmts} => {
+                let mut out = "while ".to_string();
+                out += &self.expr(cond, indent, end_col(col, &out));
+
+                let (block, _) = self.block(stmts, cond.1.end.offset, indent);
+                out += " ";
+                out += &block;
+
+                out
+            },
+            RawStmt::For{lhs, iter, stmts} => {
+                let mut out = "for ".to_string();
+                out += &self.expr(lhs, indent, end_col(col, &out));
+                out += " in ";
+                out += &self.expr(iter, indent, end_col(col, &out));
+
+                let (block, _) = self.block(stmts, iter.1.end.offset, indent);
+                out += " ";
+                out += &block;
+
+                out
+            },
+            RawStmt::Break{..} => {
+                "break".to_string()
+            },
+            RawStmt::Continue{..} => {
+                "continue".to_string()
+            },
+            RawStmt::Func{name: (name, _), args, collect_args, stmts} => {
+                self.func(
+                    &format!("fn {name}"),
+                    args,
+                    *collect_args,
+                    stmts,
+                    span.start.offset,
+                    indent,
+                    col,
+                )
+            },
+            RawStmt::Return{expr, ..} => {
+                let mut out = "return ".to_string();
+                out += &self.expr(expr, indent, end_col(col, &out));
+
+                out
+            },
+            RawStmt::Error => {
+                panic!("attempted to format an invalid statement");
+            },
+        }
+    }
+
+    fn binding(&mut self, lhs: &Expr, op: &str, rhs: &Expr, indent: usize)
+        -> String
+    {
+        let col = indent * INDENT.len();
+
+        let mut out = self.expr(lhs, indent, col);
+        let _ = write!(out, " {op} ");
+        out += &self.expr(rhs, indent, end_col(col, &out));
+
+        out
+    }
+
+    // `block` returns `stmts` formatted as a block at `indent`, where the
+    // opening brace of the block is the first `{` in the source at or after
+    // `offset`. It also returns the offset of the closing brace of the block.
+    fn block(&mut self, stmts: &Block, offset: usize, indent: usize)
+        -> (String, usize)
+    {
+        let (open, close) =
+            match self.block_ends.range(offset..).next() {
+                Some((_, v)) => *v,
+                None => panic!("couldn't find block at offset {offset}"),
+            };
+
+        // Statements in the block can be wrapped independently of any
+        // sequence that contains the block.
+        let no_wrap = self.no_wrap;
+        self.no_wrap = false;
+        // Only a comment that appears before the body can follow the opening
+        // brace, so that a comment after a one-line block stays after it.
+        let body_start =
+            stmts.first().map_or(close.offset, |(_, span)| span.start.offset);
+        let trailing = self.trailing_comment(open, body_start);
+        let body =
+            self.stmts(stmts, indent + 1, open.offset, close.offset);
+        self.no_wrap = no_wrap;
+        if trailing.is_empty() && body.is_empty() {
+            return ("{}".to_string(), close.offset);
+        }
+
+        let s = format!("{{{trailing}\n{body}{}}}", INDENT.repeat(indent));
+
+        (s, close.offset)
+    }
+
+    // `func` returns a function with the given parameters and body, prefixed
+    // by `prefix`, where the function starts at `offset` in the source.
+    #[allow(clippy::too_many_arguments)]
+    fn func(
+        &mut self,
+        prefix: &str,
+        args: &[Expr],
+        collect_args: bool,
+        stmts: &Block,
+        offset: usize,
+        indent: usize,
+        col: usize,
+    )
+        -> String
+    {
+        let params: Vec<Item> =
+            args.iter()
+                .enumerate()
+                .map(|(i, arg)| {
+                    let collect = collect_args && i == args.len() - 1;
+
+                    Item::Expr{
+                        expr: arg,
+                        prefix: if collect { ".." } else { "" },
+                        suffix: "",
+                    }
+                })
+                .collect();
+
+        let mut out = prefix.to_string();
+        out += &self.seq(
+            "(",
+            ")",
+            &params,
+            !collect_args,
+            indent,
+            end_col(col, &out),
+        );
+
+        let body_offset =
+            args.last().map_or(offset, |(_, span)| span.end.offset);
+        let (block, _) = self.block(stmts, body_offset, indent);
+        out += " ";
+        out += &block;
+
+        out
+    }
+
+    // `expr` returns `expr` formatted at `indent`, where `col` is the column
+    // that the expression starts at.
+    #[allow(clippy::too_many_lines)]
+    fn expr(&mut self, (raw_expr, span): &Expr, indent: usize, col: usize)
+        -> String
+    {
+        match raw_expr {
+            RawExpr::Null => {
+                "null".to_string()
+            },
+            RawExpr::Bool{b} => {
+                b.to_string()
+            },
+            RawExpr::Int{..} | RawExpr::Str{..} => {
+                self.literal(span)
+            },
+            RawExpr::Var{name} => {
+                name.clone()
+            },
+            RawExpr::BinaryOp{op, lhs, rhs, ..} => {
+                let prec = op_precedence(op);
+
+                let mut out = self.operand(lhs, prec, indent, col);
+                let _ = write!(out, " {} ", op.symbol());
+                let rhs_col = end_col(col, &out);
+                out += &self.operand(rhs, prec + 1, indent, rhs_col);
+
+                out
+            },
+            RawExpr::Range{start, end} => {
+                let mut out = self.operand(start, RANGE_PREC, indent, col);
+                out += "..";
+                let end_col = end_col(col, &out);
+                out += &self.operand(end, RANGE_PREC + 1, indent, end_col);
+
+                out
+            },
+            RawExpr::List{items, collect} => {
+                let items: Vec<Item> =
+                    items.iter()
+                        .enumerate()
+                        .map(|(i, ListItem{expr, is_spread})| {
+                            let is_collect = *collect && i == items.len() - 1;
+
+                            Item::Expr{
+                                expr,
+                                prefix: if is_collect { ".." } else { "" },
+                                suffix: if *is_spread { ".." } else { "" },
+                            }
+                        })
+                        .collect();
+
+                self.seq("[", "]", &items, !collect, indent, col)
+            },
+            RawExpr::Index{expr, location} => {
+                let mut out = self.operand(expr, POSTFIX_PREC, indent, col);
+                out += "[";
+                out += &self.expr(location, indent, end_col(col, &out));
+                out += "]";
+
+                out
+            },
+            RawExpr::RangeIndex{expr, start, end} => {
+                let mut out = self.operand(expr, POSTFIX_PREC, indent, col);
+                out += "[";
+                if let Some(start) = start {
+                    out += &self.expr(start, indent, end_col(col, &out));
+                }
+                out += ":";
+                if let Some(end) = end {
+                    out += &self.expr(end, indent, end_col(col, &out));
+                }
+                out += "]";
+
+                out
+            },
+            RawExpr::Object{props} => {
+                let items: Vec<Item> =
+                    props.iter()
+                        .map(|prop| match prop {
+                            PropItem::Pair{name, value} => {
+                                Item::Pair{name, value}
+                            },
+                            PropItem::Single{expr, is_spread, collect} => {
+                                Item::Expr{
+                                    expr,
+                                    prefix: if *collect { ".." } else { "" },
+                                    suffix: if *is_spread { ".." } else { "" },
+                                }
+                            },
+                        })
+                        .collect();
+
+                self.seq("{", "}", &items, true, indent, col)
+            },
+            RawExpr::Prop{expr, name, type_prop} => {
+                let mut out = self.operand(expr, POSTFIX_PREC, indent, col);
+                out += if *type_prop { "->" } else { "." };
+                out += name;
+
+                out
+            },
+            RawExpr::Func{args, collect_args, stmts} => {
+                self.func(
+                    "fn",
+                    args,
+                    *collect_args,
+                    stmts,
+                    span.start.offset,
+                    indent,
+                    col,
+                )
+            },
+            RawExpr::Call{func, args} => {
+                let args: Vec<Item> =
+                    args.iter()
+                        .map(|ListItem{expr, is_spread}| Item::Expr{
+                            expr,
+                            prefix: "",
+                            suffix: if *is_spread { ".." } else { "" },
+                        })
+                        .collect();
+
+                let mut out = self.operand(func, POSTFIX_PREC, indent, col);
+                let args_col = end_col(col, &out);
+                out += &self.seq("(", ")", &args, true, indent, args_col);
+
+                out
+            },
+        }
+    }
+
+    // `operand` returns `expr` formatted as an operand that requires an
+    // expression with a precedence of at least `min_prec`, and wraps `expr`
+    // in parentheses if its precedence is lower.
+    fn operand(&mut self, expr: &Expr, min_prec: u8, indent: usize, col: usize)
+        -> String
+    {
+        if precedence(expr) < min_prec {
+            format!("({})", self.expr(expr, indent, col + 1))
+        } else {
+            self.expr(expr, indent, col)
+        }
+    }
+
+    // `literal` returns the source of the literal at `span`. The span of a
+    // parenthesised literal includes its parentheses, so we trim them.
+    fn literal(&self, span: &Span) -> String {
+        let raw = &self.src[span.start.offset..span.end.offset];
+
+        raw.trim_matches(|c: char| c == '(' || c == ')' || c.is_whitespace())
+            .to_string()
+    }
+
+    // `seq` returns `items` separated by commas and delimited by `open` and
+    // `close`. The items are written on one line if they fit within
+    // `MAX_WIDTH`; otherwise, if `can_wrap` is `true`, each item is written on
+    // its own line, followed by a comma.
+    fn seq(
+        &mut self,
+        open: &str,
+        close: &str,
+        items: &[Item],
+        can_wrap: bool,
+        indent: usize,
+        col: usize,
+    )
+        -> String
+    {
+        let written = self.written.clone();
+        let no_wrap = self.no_wrap;
+
+        self.no_wrap = true;
+        let mut out = open.to_string();
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                out += ", ";
+            }
+            out += &self.item(item, indent, end_col(col, &out));
+        }
+        out += close;
+        self.no_wrap = no_wrap;
+
+        if no_wrap || !can_wrap || items.is_empty() || fits(col, &out) {
+            return out;
+        }
+
+        self.written = written;
+
+        let item_indent = INDENT.repeat(indent + 1);
+        let item_col = item_indent.len();
+
+        let mut out = format!("{open}\n");
+        for item in items {
+            out += &item_indent;
+            out += &self.item(item, indent + 1, item_col);
+            out += ",\n";
+        }
+        out += &INDENT.repeat(indent);
+        out += close;
+
+        out
+    }
+
+    fn item(&mut self, item: &Item, indent: usize, col: usize) -> String {
+        match item {
+            Item::Expr{expr, prefix, suffix} => {
+                // A range that's followed by `..` must be parenthesised so
+                // that the `..` isn't parsed as part of the range.
+                let min_prec =
+                    if suffix.is_empty() {
+                        RANGE_PREC
+                    } else {
+                        RANGE_PREC + 1
+                    };
+
+                let mut out = prefix.to_string();
+                let expr_col = end_col(col, &out);
+                out += &self.operand(expr, min_prec, indent, expr_col);
+                out += suffix;
+
+                out
+            },
+            Item::Pair{name, value} => {
+                let mut out = self.expr(name, indent, col);
+                out += ": ";
+                out += &self.expr(value, indent, end_col(col, &out));
+
+                out
+            },
+        }
+    }
+}
+
+// `Item` is an item in a comma-separated sequence, such as a list or an
+// argument list.
+enum Item<'a> {
+    Expr{expr: &'a Expr, prefix: &'static str, suffix: &'static str},
+    Pair{name: &'a Expr, value: &'a Expr},
+}
+
+const RANGE_PREC: u8 = 1;
+const POSTFIX_PREC: u8 = 5;
+
+// `precedence` returns the precedence of `expr`, where expressions with a
+// higher precedence bind more tightly. The precedences correspond to the
+// `ExprPrecedenceN` productions of the grammar.
+fn precedence((raw_expr, _): &Expr) -> u8 {
+    match raw_expr {
+        RawExpr::Range{..} => {
+            RANGE_PREC
+        },
+        RawExpr::BinaryOp{op, ..} => {
+            op_precedence(op)
+        },
+        RawExpr::Index{..}
+        | RawExpr::RangeIndex{..}
+        | RawExpr::Prop{..}
+        | RawExpr::Call{..} => {
+            POSTFIX_PREC
+        },
+        _ => {
+            POSTFIX_PREC + 1
+        },
+    }
+}
+
+fn op_precedence(op: &BinaryOp) -> u8 {
+    match op {
+        BinaryOp::And | BinaryOp::Or => {
+            2
+        },
+        BinaryOp::Sum | BinaryOp::Sub => {
+            3
+        },
+        BinaryOp::Mul
+        | BinaryOp::Div
+        | BinaryOp::Mod
+        | BinaryOp::Eq
+        | BinaryOp::Ne
+        | BinaryOp::Gt
+        | BinaryOp::Gte
+        | BinaryOp::Lt
+        | BinaryOp::Lte
+        | BinaryOp::RefEq
+        | BinaryOp::RefNe => {
+            4
+        },
+    }
+}
+
+// `end_col` returns the column after `s`, where `s` starts at `col`.
+fn end_col(col: usize, s: &str) -> usize {
+    match s.rfind('\n') {
+        Some(i) => s[i+1..].chars().count(),
+        None => col + s.chars().count(),
+    }
+}
+
+// `fits` returns `true` if every line of `s`, which starts at `col`, fits
+// within `MAX_WIDTH`.
+fn fits(col: usize, s: &str) -> bool {
+    s.split('\n')
+        .enumerate()
+        .all(|(i, line)| {
+            let start = if i == 0 { col } else { 0 };
+
+            start + line.chars().count() <= MAX_WIDTH
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::parser::ProgParser;
+    use crate::syntax_error;
+
+    fn format_src(src: &str) -> String {
+        let tokens = syntax_error::tokens(Lexer::new(src));
+        let mut errors = vec![];
+        let prog = ProgParser::new().parse(&mut errors, tokens)
+            .expect("couldn't parse source");
+        assert!(errors.is_empty(), "unexpected syntax errors: {errors:?}");
+
+        format_prog(src, &prog)
+    }
+
+    #[test]
+    fn test_format_prog() {
+        let tests = &[
+            (
+                "x:=1;y := [1,2 ,3]\n",
+                "x := 1\ny := [1, 2, 3]\n",
+            ),
+            (
+                "if x==1 {print( 1 );}else if x {\nprint(2)\n} else {\n}\n",
+                "if x == 1 {\n    print(1)\n} else if x {\n    print(2)\n} \
+                 else {}\n",
+            ),
+            (
+                "fn f(a, ..b) {return (a+b)*(a-b) ;}\n",
+                "fn f(a, ..b) {\n    return (a + b) * (a - b)\n}\n",
+            ),
+            (
+                "print(a - (b - c), (a - b) - c, (a..b)->len(), [(a..b)..])\n",
+                "print(a - (b - c), a - b - c, (a..b)->len(), [(a..b)..])\n",
+            ),
+            (
+                "x := {a: 1, b: [2, 3], c..};\ny := $\"${x:>5}\" + \"\\n\"\n",
+                "x := {a: 1, b: [2, 3], c..}\ny := $\"${x:>5}\" + \"\\n\"\n",
+            ),
+            (
+                "xs := [\"aaaaaaaaaaaaaaaaaaaa\", \"bbbbbbbbbbbbbbbbbbbb\", \
+                 \"cccccccccccccccccccc\", [1, 2]]\n",
+                "xs := [\n    \"aaaaaaaaaaaaaaaaaaaa\",\n    \
+                 \"bbbbbbbbbbbbbbbbbbbb\",\n    \
+                 \"cccccccccccccccccccc\",\n    [1, 2],\n]\n",
+            ),
+            (
+                "f(fn(x) {\nreturn x\n})\n",
+                "f(fn(x) {\n    return x\n})\n",
+            ),
+        ];
+
+        for (src, exp) in tests {
+            assert_eq!(format_src(src), *exp, "incorrect formatting: {src:?}");
+            assert_eq!(format_src(exp), *exp, "not idempotent: {exp:?}");
+        }
+    }
+
+    #[test]
+    fn test_format_prog_keeps_comments() {
+        let tests = &[
+            (
+                "# a\n\n\n# b\nx := 1 # c\nprint(x)\n# d\n",
+                "# a\n\n# b\nx := 1 # c\nprint(x)\n# d\n",
+            ),
+            (
+                "x := 1; y := 2 # c\n",
+                "x := 1\ny := 2 # c\n",
+            ),
+            (
+                "while true { # a\n# b\nbreak\n# c\n}\n",
+                "while true { # a\n    # b\n    break\n    # c\n}\n",
+            ),
+            (
+                "if true {\n# a\n} else {\n# b\n}\n",
+                "if true {\n    # a\n} else {\n    # b\n}\n",
+            ),
+            (
+                "x := [\n1, # a\n2,\n]\nprint(x)\n",
+                "x := [1, 2]\n# a\nprint(x)\n",
+            ),
+            (
+                "if true { print(1); } # done\n",
+                "if true {\n    print(1)\n} # done\n",
+            ),
+            (
+                "fn f(a,\nb) { return a; } # c\n",
+                "fn f(a, b) {\n    return a\n} # c\n",
+            ),
+            (
+                "fn f(\na, # a\nb,\n) {\nreturn a;\n}\n",
+                "fn f(a, b) {\n    return a\n}\n# a\n",
+            ),
+        ];
+
+        for (src, exp) in tests {
+            assert_eq!(format_src(src), *exp, "incorrect formatting: {src:?}");
+            assert_eq!(format_src(exp), *exp, "not idempotent: {exp:?}");
+        }
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::fmt::Write;
+
+// `CONTEXT` is the number of unchanged lines that are shown around each
+// change.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+enum OpKind {
+    Equal,
+    Delete,
+    Insert,
+}
+
+// `Op` is a single step of an edit script. `old` and `new` are the indices of
+// the lines in the old and new texts at the point where `Op` is applied.
+#[derive(Clone, Copy)]
+struct Op {
+    kind: OpKind,
+    old: usize,
+    new: usize,
+}
+
+// `unified` returns the differences between `old` and `new` as a unified
+// diff, or an empty string if `old` and `new` are equal.
+pub fn unified(old_name: &str, old: &str, new_name: &str, new: &str)
+    -> String
+{
+    if old == new {
+        return String::new();
+    }
+
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = edit_script(&old_lines, &new_lines);
+
+    let mut s = format!("--- {old_name}\n+++ {new_name}\n");
+    for (start, end) in hunks(&ops) {
+        let hunk = &ops[start..end];
+        let count_except = |kind| {
+            hunk.iter()
+                .filter(|op| op.kind != kind)
+                .count()
+        };
+        let old_len = count_except(OpKind::Insert);
+        let new_len = count_except(OpKind::Delete);
+        let _ = writeln!(
+            s,
+            "@@ -{} +{} @@",
+            render_range(hunk[0].old, old_len),
+            render_range(hunk[0].new, new_len),
+        );
+
+        for op in hunk {
+            let (prefix, line) =
+                match op.kind {
+                    OpKind::Equal => (' ', old_lines[op.old]),
+                    OpKind::Delete => ('-', old_lines[op.old]),
+                    OpKind::Insert => ('+', new_lines[op.new]),
+                };
+            s.push(prefix);
+            s += line;
+            if !line.ends_with('\n') {
+                s += "\n\\ No newline at end of file\n";
+            }
+        }
+    }
+
+    s
+}
+
+// `edit_script` returns the shortest list of operations that turns `old` into
+// `new`, where deletions come before insertions in each change.
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Op> {
+    // `lcs[i][j]` is the length of the longest common subsequence of
+    // `old[i..]` and `new[j..]`.
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] =
+                if old[i] == new[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+        }
+    }
+
+    let mut ops = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        let kind =
+            if i < old.len() && j < new.len() && old[i] == new[j] {
+                OpKind::Equal
+            } else if j == new.len()
+                    || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+                OpKind::Delete
+            } else {
+                OpKind::Insert
+            };
+        ops.push(Op{kind, old: i, new: j});
+
+        if kind != OpKind::Insert {
+            i += 1;
+        }
+        if kind != OpKind::Delete {
+            j += 1;
+        }
+    }
+
+    ops
+}
+
+// `hunks` returns the ranges of `ops` that should be rendered as hunks. Each
+// hunk contains one or more changes, surrounded by up to `CONTEXT` unchanged
+// lines; changes that are close together are shown in the same hunk.
+fn hunks(ops: &[Op]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = vec![];
+    for (i, op) in ops.iter().enumerate() {
+        if op.kind == OpKind::Equal {
+            continue;
+        }
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + 1 + CONTEXT).min(ops.len());
+
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => {
+                *last_end = end;
+            },
+            _ => {
+                hunks.push((start, end));
+            },
+        }
+    }
+
+    hunks
+}
+
+// `render_range` renders a range of lines in a hunk header. An empty range is
+// rendered using the index of the line before it, following GNU `diff`.
+fn render_range(start: usize, len: usize) -> String {
+    if len == 0 {
+        format!("{start},0")
+    } else {
+        format!("{},{len}", start + 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unified() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl";
+
+        assert_eq!(unified("old", old, "new", old), "");
+        assert_eq!(
+            unified("old", old, "new", new),
+            "\
+--- old
++++ new
+@@ -1,5 +1,5 @@
+ a
+-b
++B
+ c
+ d
+ e
+@@ -9,3 +9,4 @@
+ i
+ j
+ k
++l
+\\ No newline at end of file
+",
+        );
+        assert_eq!(
+            unified("old", "", "new", "x\n"),
+            "--- old\n+++ new\n@@ -0,0 +1,1 @@\n+x\n",
+        );
+    }
+}
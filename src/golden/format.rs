@@ -0,0 +1,654 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+// This module is also included by `build.rs`, so it must only depend on the
+// standard library and `snafu`.
+
+use std::fmt::Write;
+use std::num::ParseIntError;
+use std::path::Component;
+use std::path::Path;
+
+use snafu::ResultExt;
+use snafu::Snafu;
+
+pub const TEST_MARKER_START: &str =
+    "==================================================";
+
+pub const TEST_MARKER_SECTION: &str =
+    "--------------------------------------------------";
+
+const EXIT_CODE_KEY: &str = "exit_code";
+const ARG_KEY: &str = "arg";
+const ENV_KEY: &str = "env";
+
+const STDIN_SECTION: &str = "stdin";
+const FILE_SECTION_PREFIX: &str = "file ";
+
+// `WILDCARD` matches any sequence of characters within a line of expected
+// output, and `WILDCARD_LINES` is a line of expected output that matches any
+// number of lines.
+pub const WILDCARD: &str = "[..]";
+pub const WILDCARD_LINES: &str = "[...]";
+
+// `Test` is a single test case from a golden test file. Only tests in the
+// extended (`.xtest`) format have headers and a `stderr` section, so
+// `exit_code` is `0`, and `args`, `env` and `stderr` are empty, for tests in
+// the basic (`.test`) format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Test {
+    pub name: String,
+    pub src: String,
+    pub exit_code: i32,
+    // `args` are the command-line arguments that are passed after the path
+    // of the script.
+    pub args: Vec<String>,
+    // `env` contains the environment variables that are set for the script,
+    // in addition to the environment of the runner.
+    pub env: Vec<(String, String)>,
+    pub stdout: String,
+    pub stderr: String,
+    pub stdin: Option<String>,
+    // `files` contains the paths and contents of files that are written to
+    // the directory of the script before it's run. Each path is relative to
+    // the directory of the script.
+    pub files: Vec<(String, String)>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("line {}: lines found before the first marker", line))]
+    LinesBeforeFirstTest{line: usize},
+    #[snafu(display("line {}: expected a space before the test name", line))]
+    MissingSpaceBeforeName{line: usize},
+    #[snafu(display("line {}: expected '<key>: <value>'", line))]
+    InvalidHeader{line: usize},
+    #[snafu(display("line {}: unknown header '{}'", line, key))]
+    UnknownHeader{line: usize, key: String},
+    #[snafu(display("line {}: couldn't parse exit code: {}", line, source))]
+    InvalidExitCode{line: usize, source: ParseIntError},
+    #[snafu(display("line {}: expected '{}: <name>=<value>'", line, ENV_KEY))]
+    InvalidEnvVar{line: usize},
+    #[snafu(display("line {}: unknown section '{}'", line, name))]
+    UnknownSection{line: usize, name: String},
+    #[snafu(display("line {}: section '{}' is defined twice", line, name))]
+    DuplicateSection{line: usize, name: String},
+    #[snafu(display(
+        "line {}: file path '{}' must be inside the script's directory",
+        line,
+        path,
+    ))]
+    InvalidFilePath{line: usize, path: String},
+    #[snafu(display(
+        "line {}: test name '{}' must be a single path component",
+        line,
+        name,
+    ))]
+    InvalidTestName{line: usize, name: String},
+    #[snafu(display("line {}: too many sections in test '{}'", line, name))]
+    TooManySections{line: usize, name: String},
+    #[snafu(display("expected output isn't defined for test '{}'", name))]
+    MissingExpectedOutput{name: String},
+    #[snafu(display("line {}: lines found after the closing marker", line))]
+    LinesAfterEnd{line: usize},
+    #[snafu(display("no tests are defined"))]
+    NoTests,
+    #[snafu(display("file doesn't end with a closing test marker"))]
+    MissingEndMarker,
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    Headers,
+    Src,
+    Stdout,
+    Stderr,
+    Stdin,
+    // `File` is the section for the file at the given index of `Test::files`.
+    File(usize),
+    // `Extra` is an unnamed section after the required sections, which must
+    // be empty.
+    Extra,
+}
+
+// `parse` returns the tests defined in `src`. `extended` is `true` if `src` is
+// in the extended (`.xtest`) format, where each test starts with a section of
+// headers and ends with a `stderr` section.
+//
+// The required sections of a test can be followed by named sections, which
+// start with a section marker that is followed by the name of the section.
+pub fn parse(src: &str, extended: bool) -> Result<Vec<Test>, Error> {
+    let sections: &[Section] =
+        if extended {
+            &[Section::Headers, Section::Src, Section::Stdout, Section::Stderr]
+        } else {
+            &[Section::Src, Section::Stdout]
+        };
+
+    let mut tests = vec![];
+    let mut end_matched = false;
+    let mut cur_test: Option<Test> = None;
+    // `section_num` is the number of section markers that have been found in
+    // the current test.
+    let mut section_num = 0;
+    let mut section = sections[0];
+    for (i, line) in src.lines().enumerate() {
+        let line_num = i + 1;
+
+        if end_matched {
+            return Err(Error::LinesAfterEnd{line: line_num});
+        }
+
+        if let Some(suffix) = line.strip_prefix(TEST_MARKER_START) {
+            if let Some(test) = cur_test.take() {
+                if section_num < sections.len() - 1 {
+                    return Err(Error::MissingExpectedOutput{name: test.name});
+                }
+                tests.push(test);
+            }
+
+            if suffix.is_empty() {
+                if tests.is_empty() {
+                    return Err(Error::NoTests);
+                }
+                end_matched = true;
+            } else {
+                cur_test = Some(new_test(line_num, suffix)?);
+                section_num = 0;
+                section = sections[0];
+            }
+
+            continue;
+        }
+
+        let test =
+            match &mut cur_test {
+                Some(test) => test,
+                None => {
+                    return Err(Error::LinesBeforeFirstTest{line: line_num});
+                },
+            };
+
+        let suffix =
+            match line.strip_prefix(TEST_MARKER_SECTION) {
+                Some(suffix) => suffix,
+                None => {
+                    add_line(test, section, line_num, line)?;
+                    continue;
+                },
+            };
+
+        section_num += 1;
+        if suffix.is_empty() {
+            section =
+                sections.get(section_num)
+                    .copied()
+                    .unwrap_or(Section::Extra);
+        } else if section_num < sections.len() {
+            return Err(Error::MissingExpectedOutput{
+                name: test.name.clone(),
+            });
+        } else {
+            section = start_named_section(test, line_num, suffix)?;
+        }
+    }
+
+    if !end_matched {
+        return Err(Error::MissingEndMarker);
+    }
+
+    Ok(tests)
+}
+
+// `new_test` returns an empty test for the start marker that is followed by
+// `suffix`.
+fn new_test(line_num: usize, suffix: &str) -> Result<Test, Error> {
+    let name =
+        match suffix.strip_prefix(' ') {
+            Some(name) => name,
+            None => {
+                return Err(Error::MissingSpaceBeforeName{line: line_num});
+            },
+        };
+
+    // The test name is used as a directory name and a file name when the test
+    // is run, so it mustn't be able to refer to any other path.
+    let mut components = Path::new(name).components();
+    let is_valid =
+        matches!(components.next(), Some(Component::Normal(_)))
+            && components.next().is_none();
+    if !is_valid {
+        return Err(Error::InvalidTestName{
+            line: line_num,
+            name: name.to_string(),
+        });
+    }
+
+    Ok(Test{
+        name: name.to_string(),
+        src: String::new(),
+        exit_code: 0,
+        args: vec![],
+        env: vec![],
+        stdout: String::new(),
+        stderr: String::new(),
+        stdin: None,
+        files: vec![],
+    })
+}
+
+// `start_named_section` adds the named section that is started by a section
+// marker followed by `suffix` to `test`, and returns the section.
+fn start_named_section(test: &mut Test, line_num: usize, suffix: &str)
+    -> Result<Section, Error>
+{
+    let name = suffix.strip_prefix(' ').unwrap_or(suffix);
+
+    if name == STDIN_SECTION {
+        if test.stdin.is_some() {
+            return Err(Error::DuplicateSection{
+                line: line_num,
+                name: name.to_string(),
+            });
+        }
+        test.stdin = Some(String::new());
+
+        return Ok(Section::Stdin);
+    }
+
+    let path =
+        match name.strip_prefix(FILE_SECTION_PREFIX) {
+            Some(path) => path,
+            None => {
+                return Err(Error::UnknownSection{
+                    line: line_num,
+                    name: name.to_string(),
+                });
+            },
+        };
+
+    let is_valid =
+        !path.is_empty()
+            && Path::new(path).components()
+                .all(|c| matches!(c, Component::Normal(_)));
+    if !is_valid {
+        return Err(Error::InvalidFilePath{
+            line: line_num,
+            path: path.to_string(),
+        });
+    }
+    if test.files.iter().any(|(p, _)| p == path) {
+        return Err(Error::DuplicateSection{
+            line: line_num,
+            name: name.to_string(),
+        });
+    }
+    test.files.push((path.to_string(), String::new()));
+
+    Ok(Section::File(test.files.len() - 1))
+}
+
+// `add_line` adds `line`, which is in the given `section` of `test`, to
+// `test`.
+fn add_line(test: &mut Test, section: Section, line_num: usize, line: &str)
+    -> Result<(), Error>
+{
+    let content =
+        match section {
+            Section::Headers => {
+                return add_header(test, line_num, line);
+            },
+            Section::Src => {
+                &mut test.src
+            },
+            Section::Stdout => {
+                &mut test.stdout
+            },
+            Section::Stderr => {
+                &mut test.stderr
+            },
+            Section::Stdin => {
+                test.stdin.get_or_insert_with(String::new)
+            },
+            Section::File(i) => {
+                &mut test.files[i].1
+            },
+            Section::Extra => {
+                return Err(Error::TooManySections{
+                    line: line_num,
+                    name: test.name.clone(),
+                });
+            },
+        };
+    *content += line;
+    *content += "\n";
+
+    Ok(())
+}
+
+fn add_header(test: &mut Test, line_num: usize, line: &str)
+    -> Result<(), Error>
+{
+    let (key, value) =
+        match line.split_once(": ") {
+            Some(header) => header,
+            None => return Err(Error::InvalidHeader{line: line_num}),
+        };
+
+    match key {
+        EXIT_CODE_KEY => {
+            test.exit_code = value.parse()
+                .context(InvalidExitCode{line: line_num})?;
+        },
+        ARG_KEY => {
+            test.args.push(value.to_string());
+        },
+        ENV_KEY => {
+            let (name, value) =
+                match value.split_once('=') {
+                    Some(var) => var,
+                    None => return Err(Error::InvalidEnvVar{line: line_num}),
+                };
+            test.env.push((name.to_string(), value.to_string()));
+        },
+        _ => {
+            return Err(Error::UnknownHeader{
+                line: line_num,
+                key: key.to_string(),
+            });
+        },
+    }
+
+    Ok(())
+}
+
+// `render` returns `tests` in the format that is read by `parse`. Every
+// non-empty section of each test must end with a newline.
+pub fn render(tests: &[Test], extended: bool) -> String {
+    let mut s = String::new();
+    for test in tests {
+        let _ = writeln!(s, "{TEST_MARKER_START} {}", test.name);
+        if extended {
+            let _ = writeln!(s, "{EXIT_CODE_KEY}: {}", test.exit_code);
+            for arg in &test.args {
+                let _ = writeln!(s, "{ARG_KEY}: {arg}");
+            }
+            for (name, value) in &test.env {
+                let _ = writeln!(s, "{ENV_KEY}: {name}={value}");
+            }
+            let _ = writeln!(s, "{TEST_MARKER_SECTION}");
+        }
+        s += &test.src;
+        let _ = writeln!(s, "{TEST_MARKER_SECTION}");
+        s += &test.stdout;
+        if extended {
+            let _ = writeln!(s, "{TEST_MARKER_SECTION}");
+            s += &test.stderr;
+        }
+        if let Some(stdin) = &test.stdin {
+            let _ = writeln!(s, "{TEST_MARKER_SECTION} {STDIN_SECTION}");
+            s += stdin;
+        }
+        for (path, content) in &test.files {
+            let _ = writeln!(
+                s,
+                "{TEST_MARKER_SECTION} {FILE_SECTION_PREFIX}{path}",
+            );
+            s += content;
+        }
+    }
+    let _ = writeln!(s, "{TEST_MARKER_START}");
+
+    s
+}
+
+// `matches` returns `true` if `actual` matches the `expected` output, which
+// may contain `WILDCARD` and `WILDCARD_LINES`.
+pub fn matches(expected: &str, actual: &str) -> bool {
+    if expected == actual {
+        return true;
+    }
+    if !has_wildcards(expected) {
+        return false;
+    }
+    if expected.ends_with('\n') != actual.ends_with('\n') {
+        return false;
+    }
+
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    matches_lines(&expected, &actual)
+}
+
+// `has_wildcards` returns `true` if the expected output `expected` contains a
+// `WILDCARD` or a `WILDCARD_LINES`.
+pub fn has_wildcards(expected: &str) -> bool {
+    expected.contains(WILDCARD) || expected.contains(WILDCARD_LINES)
+}
+
+fn matches_lines(expected: &[&str], actual: &[&str]) -> bool {
+    match (expected.split_first(), actual.split_first()) {
+        (None, _) => {
+            actual.is_empty()
+        },
+        (Some((&WILDCARD_LINES, rest)), _) => {
+            (0..=actual.len()).any(|i| matches_lines(rest, &actual[i..]))
+        },
+        (Some((pattern, rest)), Some((line, actual_rest))) => {
+            matches_line(pattern, line) && matches_lines(rest, actual_rest)
+        },
+        (Some(_), None) => {
+            false
+        },
+    }
+}
+
+// `matches_line` returns `true` if `line` matches `pattern`, where each
+// `WILDCARD` in `pattern` matches any sequence of characters.
+fn matches_line(pattern: &str, line: &str) -> bool {
+    let parts: Vec<&str> = pattern.split(WILDCARD).collect();
+    if parts.len() == 1 {
+        return pattern == line;
+    }
+
+    let mut rest =
+        match line.strip_prefix(parts[0]) {
+            Some(rest) => rest,
+            None => return false,
+        };
+    let last = parts[parts.len() - 1];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_render() {
+        let src = "\
+================================================== first
+exit_code: 103
+arg: a b
+arg: c
+env: NAME=x=y
+--------------------------------------------------
+print(1);
+x
+--------------------------------------------------
+1
+--------------------------------------------------
+error
+-------------------------------------------------- stdin
+input
+-------------------------------------------------- file data/a.txt
+a
+================================================== second
+exit_code: 0
+--------------------------------------------------
+--------------------------------------------------
+--------------------------------------------------
+==================================================
+";
+
+        let tests = parse(src, true)
+            .expect("couldn't parse tests");
+
+        let file = ("data/a.txt".to_string(), "a\n".to_string());
+        assert_eq!(
+            tests,
+            vec![
+                Test{
+                    name: "first".to_string(),
+                    src: "print(1);\nx\n".to_string(),
+                    exit_code: 103,
+                    args: vec!["a b".to_string(), "c".to_string()],
+                    env: vec![("NAME".to_string(), "x=y".to_string())],
+                    stdout: "1\n".to_string(),
+                    stderr: "error\n".to_string(),
+                    stdin: Some("input\n".to_string()),
+                    files: vec![file],
+                },
+                Test{
+                    name: "second".to_string(),
+                    src: String::new(),
+                    exit_code: 0,
+                    args: vec![],
+                    env: vec![],
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    stdin: None,
+                    files: vec![],
+                },
+            ],
+        );
+        assert_eq!(render(&tests, true), src);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let marker = TEST_MARKER_START;
+        let section = TEST_MARKER_SECTION;
+        let cases = [
+            (
+                "x\n".to_string(),
+                false,
+                "line 1: lines found before the first marker",
+            ),
+            (
+                format!("{marker}t\n"),
+                false,
+                "line 1: expected a space before the test name",
+            ),
+            (
+                format!("{marker} t\n{section}\n{section}\nx\n"),
+                false,
+                "line 4: too many sections in test 't'",
+            ),
+            (
+                format!("{marker} t\n{marker}\n"),
+                false,
+                "expected output isn't defined for test 't'",
+            ),
+            (
+                format!("{marker} ../t\n"),
+                false,
+                "line 1: test name '../t' must be a single path component",
+            ),
+            (
+                format!("{marker} a/t\n"),
+                false,
+                "line 1: test name 'a/t' must be a single path component",
+            ),
+            (
+                format!("{marker} t\n{section}\n{marker}\nx\n"),
+                false,
+                "line 4: lines found after the closing marker",
+            ),
+            (format!("{marker}\n"), false, "no tests are defined"),
+            (
+                format!("{marker} t\n{section}\n"),
+                false,
+                "file doesn't end with a closing test marker",
+            ),
+            (
+                format!("{marker} t\n{section} stdin\n"),
+                false,
+                "expected output isn't defined for test 't'",
+            ),
+            (
+                format!("{marker} t\n{section}\n{section} input\n"),
+                false,
+                "line 3: unknown section 'input'",
+            ),
+            (
+                format!("{marker} t\n{section}\n{section} file ../a\n"),
+                false,
+                "line 3: file path '../a' must be inside the script's \
+                 directory",
+            ),
+            (
+                format!("{marker} t\n{section}\n{section} stdin\n")
+                    + &format!("{section} stdin\n"),
+                false,
+                "line 4: section 'stdin' is defined twice",
+            ),
+            (
+                format!("{marker} t\nexit_code 1\n"),
+                true,
+                "line 2: expected '<key>: <value>'",
+            ),
+            (
+                format!("{marker} t\ncode: 1\n"),
+                true,
+                "line 2: unknown header 'code'",
+            ),
+            (
+                format!("{marker} t\nenv: NAME\n"),
+                true,
+                "line 2: expected 'env: <name>=<value>'",
+            ),
+        ];
+
+        for (src, extended, expected) in cases {
+            match parse(&src, extended) {
+                Ok(_) => panic!("expected error for:\n{src}"),
+                Err(e) => assert_eq!(e.to_string(), expected),
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let cases = [
+            ("a\n", "a\n", true),
+            ("a\n", "b\n", false),
+            ("[..]\n", "a", false),
+            ("took [..]ms\n", "took 12ms\n", true),
+            ("took [..]ms\n", "took 12s\n", false),
+            ("[..]:[..]:[..]\n", "a:b:c\n", true),
+            ("ab[..]ba\n", "aba\n", false),
+            ("a\n[...]\nz\n", "a\nz\n", true),
+            ("a\n[...]\nz\n", "a\nb\nc\nz\n", true),
+            ("a\n[...]\nz\n", "a\nb\nc\n", false),
+            ("[...]\n", "x\ny\n", true),
+        ];
+
+        for (expected, actual, want) in cases {
+            assert_eq!(
+                matches(expected, actual),
+                want,
+                "matches({expected:?}, {actual:?})",
+            );
+        }
+    }
+}
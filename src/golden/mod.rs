@@ -0,0 +1,480 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+pub mod diff;
+pub mod format;
+
+use std::env;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::io::Error as IoError;
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+use snafu::ResultExt;
+use snafu::Snafu;
+
+use self::format::Test;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("couldn't read '{}': {}", path.display(), source))]
+    ReadFailed{path: PathBuf, source: IoError},
+    #[snafu(display("couldn't parse '{}': {}", path.display(), source))]
+    ParseFailed{path: PathBuf, source: format::Error},
+    #[snafu(display("couldn't create '{}': {}", path.display(), source))]
+    CreateFailed{path: PathBuf, source: IoError},
+    #[snafu(display("couldn't run '{}': {}", path.display(), source))]
+    RunFailed{path: PathBuf, source: IoError},
+    #[snafu(display("couldn't write '{}': {}", path.display(), source))]
+    WriteFailed{path: PathBuf, source: IoError},
+    #[snafu(display("couldn't remove '{}': {}", path.display(), source))]
+    RemoveFailed{path: PathBuf, source: IoError},
+    #[snafu(display("couldn't write report: {}", source))]
+    WriteReportFailed{source: IoError},
+}
+
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    pub blessed: usize,
+}
+
+// `TestFile` is a golden test file that has been parsed.
+struct TestFile {
+    path: PathBuf,
+    // `stem` is the name of the file without its extension, which is used as
+    // the directory of the scripts of the tests in the file.
+    stem: String,
+    extended: bool,
+    tests: Vec<Test>,
+}
+
+// `Outcome` is the result of running the script of a test.
+pub struct Outcome {
+    // `exit_code` is `None` if the script was terminated by a signal.
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+// `run_golden_tests` runs the tests in the golden test files (`.test` and
+// `.xtest` files) in `dir` using the Seed interpreter at `exe`, and writes a
+// report of the results to `w`. If `bless` is `true` then the expected output
+// of each failing test is replaced by its actual output.
+pub fn run_golden_tests<W: Write>(
+    exe: &Path,
+    dir: &Path,
+    bless: bool,
+    w: &mut W,
+)
+    -> Result<Summary, Error>
+{
+    let mut files = read_test_files(dir)?;
+
+    let tmp_dir = env::temp_dir().join(format!(
+        "seed_golden_{}",
+        std::process::id(),
+    ));
+    fs::create_dir_all(&tmp_dir)
+        .context(CreateFailed{path: &tmp_dir})?;
+
+    let cases: Vec<(&TestFile, &Test)> =
+        files.iter()
+            .flat_map(|file| file.tests.iter().map(move |test| (file, test)))
+            .collect();
+    let results = run_cases(exe, &tmp_dir, &cases);
+
+    fs::remove_dir_all(&tmp_dir)
+        .context(RemoveFailed{path: tmp_dir})?;
+
+    let mut summary = Summary{passed: 0, failed: 0, blessed: 0};
+    let mut failures = vec![];
+    let mut results = results.into_iter();
+    for file in &mut files {
+        let mut file_blessed = false;
+        for test in &mut file.tests {
+            let label = format!("{}::{}", file.path.display(), test.name);
+            let outcome =
+                match results.next() {
+                    Some(result) => result?,
+                    None => break,
+                };
+
+            let report = render_failure(test, &outcome);
+            if report.is_empty() {
+                writeln!(w, "test {label} ... ok")
+                    .context(WriteReportFailed)?;
+                summary.passed += 1;
+
+                continue;
+            }
+
+            if bless {
+                match bless_test(test, file.extended, outcome) {
+                    Ok(()) => {
+                        writeln!(w, "test {label} ... blessed")
+                            .context(WriteReportFailed)?;
+                        summary.blessed += 1;
+                        file_blessed = true;
+
+                        continue;
+                    },
+                    Err(msg) => {
+                        failures.push((label.clone(), report + msg + "\n"));
+                    },
+                }
+            } else {
+                failures.push((label.clone(), report));
+            }
+
+            writeln!(w, "test {label} ... FAILED")
+                .context(WriteReportFailed)?;
+            summary.failed += 1;
+        }
+
+        if file_blessed {
+            let src = format::render(&file.tests, file.extended);
+            fs::write(&file.path, src)
+                .context(WriteFailed{path: &file.path})?;
+        }
+    }
+
+    if !failures.is_empty() {
+        writeln!(w, "\nfailures:")
+            .context(WriteReportFailed)?;
+        for (label, report) in failures {
+            write!(w, "\n---- {label} ----\n{report}")
+                .context(WriteReportFailed)?;
+        }
+    }
+
+    let result = if summary.failed == 0 { "ok" } else { "FAILED" };
+    write!(
+        w,
+        "\ntest result: {result}. {} passed; {} failed",
+        summary.passed,
+        summary.failed,
+    )
+        .context(WriteReportFailed)?;
+    if bless {
+        write!(w, "; {} blessed", summary.blessed)
+            .context(WriteReportFailed)?;
+    }
+    writeln!(w)
+        .context(WriteReportFailed)?;
+
+    Ok(summary)
+}
+
+// `read_test_files` returns the golden test files in `dir`, in order.
+fn read_test_files(dir: &Path) -> Result<Vec<TestFile>, Error> {
+    let mut paths = vec![];
+    let entries = fs::read_dir(dir)
+        .context(ReadFailed{path: dir})?;
+    for maybe_entry in entries {
+        let entry = maybe_entry
+            .context(ReadFailed{path: dir})?;
+        let path = entry.path();
+        let is_test_file =
+            path.extension()
+                .is_some_and(|ext| ext == "test" || ext == "xtest");
+        if path.is_file() && is_test_file {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut files = vec![];
+    for path in paths {
+        let src = fs::read_to_string(&path)
+            .context(ReadFailed{path: &path})?;
+        let extended = path.extension().is_some_and(|ext| ext == "xtest");
+        let tests = format::parse(&src, extended)
+            .context(ParseFailed{path: &path})?;
+        let stem =
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+        files.push(TestFile{path, stem, extended, tests});
+    }
+
+    Ok(files)
+}
+
+// `run_cases` runs the scripts of `cases` in parallel, in subdirectories of
+// `tmp_dir`, and returns the outcomes in the same order as `cases`.
+fn run_cases(exe: &Path, tmp_dir: &Path, cases: &[(&TestFile, &Test)])
+    -> Vec<Result<Outcome, Error>>
+{
+    let num_workers =
+        thread::available_parallelism()
+            .map_or(1, NonZeroUsize::get)
+            .min(cases.len().max(1));
+    let next_case = AtomicUsize::new(0);
+
+    let mut results: Vec<(usize, Result<Outcome, Error>)> =
+        thread::scope(|scope| {
+            let workers: Vec<_> =
+                (0..num_workers)
+                    .map(|_| scope.spawn(|| {
+                        let mut results = vec![];
+                        let mut i = next_case.fetch_add(1, Ordering::Relaxed);
+                        while let Some((file, test)) = cases.get(i) {
+                            let result =
+                                run_case(exe, tmp_dir, &file.stem, test);
+                            results.push((i, result));
+                            i = next_case.fetch_add(1, Ordering::Relaxed);
+                        }
+
+                        results
+                    }))
+                    .collect();
+
+            workers.into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        });
+    results.sort_by_key(|(i, _)| *i);
+
+    results.into_iter()
+        .map(|(_, result)| result)
+        .collect()
+}
+
+// `run_case` runs the script of `test`, which is from the golden test file
+// with the given `stem`, in its own directory under `root_dir`. The script is
+// written to `<stem>/<name>.sd`, relative to that directory, along with the
+// files of `test`, and is run from that directory, so that the script is
+// reported relative to it.
+pub fn run_case(exe: &Path, root_dir: &Path, stem: &str, test: &Test)
+    -> Result<Outcome, Error>
+{
+    let case_dir = root_dir.join(stem).join(&test.name);
+    let script_dir = case_dir.join(stem);
+    fs::create_dir_all(&script_dir)
+        .context(CreateFailed{path: &script_dir})?;
+
+    for (rel_path, content) in &test.files {
+        let path = script_dir.join(rel_path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .context(CreateFailed{path: dir})?;
+        }
+        fs::write(&path, content)
+            .context(WriteFailed{path: &path})?;
+    }
+
+    let rel_path = Path::new(stem).join(format!("{}.sd", test.name));
+    let path = case_dir.join(&rel_path);
+    fs::write(&path, &test.src)
+        .context(WriteFailed{path: &path})?;
+
+    let stdin =
+        if test.stdin.is_some() { Stdio::piped() } else { Stdio::null() };
+    let mut child =
+        Command::new(exe)
+            .current_dir(&case_dir)
+            .arg(&rel_path)
+            .args(&test.args)
+            .envs(test.env.iter().map(|(name, value)| (name, value)))
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context(RunFailed{path: &path})?;
+
+    if let (Some(input), Some(mut child_stdin)) =
+            (test.stdin.clone(), child.stdin.take()) {
+        // We write the input from a separate thread so that we don't block if
+        // the script fills its output pipes before reading all of its input.
+        // The script may also exit without reading all of its input, so we
+        // ignore errors.
+        thread::spawn(move || {
+            let _ = child_stdin.write_all(input.as_bytes());
+        });
+    }
+
+    let output = child.wait_with_output()
+        .context(RunFailed{path})?;
+
+    Ok(Outcome{
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
+
+// `render_failure` returns a description of the differences between the
+// expected and actual outcome of `test`, or an empty string if the actual
+// outcome matches the expected outcome.
+pub fn render_failure(test: &Test, outcome: &Outcome) -> String {
+    let mut report = String::new();
+    if outcome.exit_code != Some(test.exit_code) {
+        let actual =
+            match outcome.exit_code {
+                Some(code) => code.to_string(),
+                None => "no exit code".to_string(),
+            };
+        let _ = writeln!(
+            report,
+            "exit code: expected {}, got {actual}",
+            test.exit_code,
+        );
+    }
+
+    let streams = [
+        ("stdout", &test.stdout, &outcome.stdout),
+        ("stderr", &test.stderr, &outcome.stderr),
+    ];
+    for (name, expected, actual) in streams {
+        if !format::matches(expected, actual) {
+            let _ = writeln!(report, "{name}:");
+            report += &diff::unified("expected", expected, "actual", actual);
+        }
+    }
+
+    report
+}
+
+// `bless_test` replaces the expected outcome of `test` with `outcome`, or
+// returns the reason that `outcome` can't be represented in the format of
+// the test. Expected output that already matches `outcome` is kept, so that
+// its wildcards aren't lost, and output that doesn't match can't be blessed if
+// it contains wildcards.
+fn bless_test(test: &mut Test, extended: bool, outcome: Outcome)
+    -> Result<(), &'static str>
+{
+    let exit_code =
+        match outcome.exit_code {
+            Some(code) => code,
+            None => return Err("can't bless a test without an exit code"),
+        };
+
+    if !extended && (exit_code != 0 || !outcome.stderr.is_empty()) {
+        return Err(
+            "can't bless a test with a non-zero exit code or `stderr` output \
+             in a `.test` file",
+        );
+    }
+
+    let ends_with_newline = |s: &str| s.is_empty() || s.ends_with('\n');
+    if !ends_with_newline(&outcome.stdout)
+            || !ends_with_newline(&outcome.stderr) {
+        return Err("can't bless output that doesn't end with a newline");
+    }
+
+    let streams = [
+        (&test.stdout, &outcome.stdout),
+        (&test.stderr, &outcome.stderr),
+    ];
+    for (expected, actual) in streams {
+        if !format::matches(expected, actual)
+                && format::has_wildcards(expected) {
+            return Err("can't bless output that contains wildcards");
+        }
+    }
+
+    test.exit_code = exit_code;
+    if !format::matches(&test.stdout, &outcome.stdout) {
+        test.stdout = outcome.stdout;
+    }
+    if !format::matches(&test.stderr, &outcome.stderr) {
+        test.stderr = outcome.stderr;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bless_test() {
+        let mut test = Test{
+            name: "t".to_string(),
+            src: String::new(),
+            exit_code: 0,
+            args: vec![],
+            env: vec![],
+            stdout: String::new(),
+            stderr: String::new(),
+            stdin: None,
+            files: vec![],
+        };
+        let outcome = |exit_code, stdout: &str, stderr: &str| Outcome{
+            exit_code,
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+        };
+
+        let invalid = [
+            (false, outcome(Some(1), "", "")),
+            (true, outcome(None, "", "")),
+            (true, outcome(Some(0), "x", "")),
+        ];
+        for (extended, outcome) in invalid {
+            assert!(bless_test(&mut test, extended, outcome).is_err());
+        }
+        assert_eq!(test.exit_code, 0);
+
+        let result = bless_test(&mut test, true, outcome(Some(1), "", "e\n"));
+
+        assert!(result.is_ok());
+        assert_eq!((test.exit_code, test.stderr.as_str()), (1, "e\n"));
+
+        test.stdout = "took [..]ms\n".to_string();
+        let result =
+            bless_test(&mut test, true, outcome(Some(0), "took 3ms\n", ""));
+
+        assert!(result.is_ok());
+        assert_eq!(test.stdout, "took [..]ms\n");
+
+        let result =
+            bless_test(&mut test, true, outcome(Some(0), "took 3s\n", ""));
+
+        assert!(result.is_err());
+        assert_eq!(test.stdout, "took [..]ms\n");
+    }
+
+    // `test_run_case_writes_files` runs `cat` instead of the interpreter,
+    // because scripts can't read files, to check that the files of a test
+    // are written next to its script.
+    #[test]
+    fn test_run_case_writes_files() {
+        let root_dir = env::temp_dir().join(format!(
+            "seed_golden_run_case_{}",
+            std::process::id(),
+        ));
+        let test = Test{
+            name: "t".to_string(),
+            src: "src\n".to_string(),
+            exit_code: 0,
+            args: vec!["cases/data/a.txt".to_string(), "-".to_string()],
+            env: vec![],
+            stdout: String::new(),
+            stderr: String::new(),
+            stdin: Some("input\n".to_string()),
+            files: vec![("data/a.txt".to_string(), "a\n".to_string())],
+        };
+
+        let result = run_case(Path::new("cat"), &root_dir, "cases", &test);
+
+        fs::remove_dir_all(&root_dir)
+            .expect("couldn't remove test directory");
+        let outcome = result
+            .expect("couldn't run case");
+        assert_eq!(outcome.stdout, "src\na\ninput\n");
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use lalrpop_util::ParseError;
+
+use crate::ast::Align;
+use crate::ast::Expr;
+use crate::ast::FormatKind;
+use crate::ast::FormatSpec;
+use crate::ast::InterpolationSlot;
+use crate::lexer::InterpSlot;
+use crate::lexer::Lexer;
+use crate::lexer::Position;
+use crate::lexer::Token;
+use crate::parser::ExprParser;
+use crate::syntax_error;
+use crate::syntax_error::SyntaxError;
+
+// `split_slot` splits the source of an interpolation slot into the expression
+// source and the format specifier source, if the slot contains a format
+// specifier. The specifier is separated from the expression by the last `:`
+// that isn't nested in brackets, braces, parentheses or a string.
+pub fn split_slot(slot: &str) -> (&str, Option<&str>) {
+    let mut depth: usize = 0;
+    let mut in_str = false;
+    let mut escaped = false;
+    let mut split_at = None;
+
+    for (i, c) in slot.char_indices() {
+        if in_str {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_str = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_str = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 => split_at = Some(i),
+            _ => {},
+        }
+    }
+
+    match split_at {
+        Some(i) => (&slot[..i], Some(&slot[i+1..])),
+        None => (slot, None),
+    }
+}
+
+// `parse_spec` returns an error message if `spec` isn't a valid format
+// specifier.
+pub fn parse_spec(spec: &str) -> Result<FormatSpec, String> {
+    let chars: Vec<char> = spec.chars().collect();
+    let mut i = 0;
+    let mut fs = FormatSpec::default();
+
+    if chars.len() >= 2 && to_align(chars[1]).is_some() {
+        fs.fill = Some(chars[0]);
+        fs.align = to_align(chars[1]);
+        i = 2;
+    } else if let Some(align) = chars.first().and_then(|c| to_align(*c)) {
+        fs.align = Some(align);
+        i = 1;
+    }
+
+    if chars.get(i) == Some(&'+') {
+        fs.sign = true;
+        i += 1;
+    }
+
+    if chars.get(i) == Some(&'#') {
+        fs.alternate = true;
+        i += 1;
+    }
+
+    if chars.get(i) == Some(&'0') {
+        fs.zero = true;
+        i += 1;
+    }
+
+    let (width, next) = parse_usize(&chars, i)?;
+    fs.width = width;
+    i = next;
+
+    if chars.get(i) == Some(&'.') {
+        let (precision, next) = parse_usize(&chars, i + 1)?;
+        if precision.is_none() {
+            return Err("expected a precision after '.'".to_string());
+        }
+        fs.precision = precision;
+        i = next;
+    }
+
+    if let Some(c) = chars.get(i) {
+        fs.kind =
+            match c {
+                '?' => FormatKind::Repr,
+                'x' => FormatKind::LowerHex,
+                'X' => FormatKind::UpperHex,
+                'o' => FormatKind::Octal,
+                'b' => FormatKind::Binary,
+                c => return Err(format!("unknown format type '{c}'")),
+            };
+        i += 1;
+    }
+
+    if i != chars.len() {
+        let rest: String = chars[i..].iter().collect();
+
+        return Err(format!("unexpected '{rest}'"));
+    }
+
+    Ok(fs)
+}
+
+fn to_align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '^' => Some(Align::Centre),
+        '>' => Some(Align::Right),
+        _ => None,
+    }
+}
+
+fn parse_usize(chars: &[char], start: usize)
+    -> Result<(Option<usize>, usize), String>
+{
+    let mut end = start;
+    while end < chars.len() && chars[end].is_ascii_digit() {
+        end += 1;
+    }
+
+    if end == start {
+        return Ok((None, start));
+    }
+
+    let raw: String = chars[start..end].iter().collect();
+    match raw.parse() {
+        Ok(n) => Ok((Some(n), end)),
+        Err(_) => Err(format!("'{raw}' is too large")),
+    }
+}
+
+// `parse_slots` parses the expression and format specifier of each of the
+// interpolation `slots` in `s`, so that interpolated strings don't need to be
+// parsed when they're evaluated. Errors are reported using the location of
+// the failing slot contents in the source file.
+pub fn parse_slots(s: &str, slots: Vec<InterpSlot>)
+    -> Result<
+        Vec<InterpolationSlot>,
+        ParseError<Position, Token, SyntaxError>,
+    >
+{
+    let parser = ExprParser::new();
+
+    let mut parsed_slots = vec![];
+    for (start, end, pos) in slots {
+        // We shorten the slot to skip the delimiters (`${` at the start and
+        // `}` at the end).
+        let slot = &s[(start+2) .. (end-1)];
+
+        let (directive, maybe_raw_spec) = split_slot(slot);
+
+        let spec =
+            if let Some(raw_spec) = maybe_raw_spec {
+                match parse_spec(raw_spec) {
+                    Ok(spec) => spec,
+                    Err(reason) => return Err(ParseError::User{
+                        error: SyntaxError::InvalidFormatSpec(
+                            advance(pos, &slot[..=directive.len()]).loc(),
+                            raw_spec.to_string(),
+                            reason,
+                        ),
+                    }),
+                }
+            } else {
+                FormatSpec::default()
+            };
+
+        let lexer = Lexer::new_at(directive, pos);
+        let mut errors = vec![];
+        let expr: Expr =
+            match parser.parse(&mut errors, syntax_error::tokens(lexer)) {
+                // Slots are parsed as expressions, but statements in function
+                // literals can recover from syntax errors, so we return the
+                // first recovered error, if any.
+                Ok(_) if !errors.is_empty() => {
+                    return Err(errors.remove(0).error);
+                },
+                Ok(expr) => {
+                    expr
+                },
+                // We report an incomplete slot at the closing brace of the
+                // slot, rather than at the end of the last token in the slot.
+                Err(ParseError::UnrecognizedEof{expected, ..}) => {
+                    return Err(ParseError::UnrecognizedEof{
+                        location: advance(pos, directive),
+                        expected,
+                    });
+                },
+                Err(e) => {
+                    return Err(e);
+                },
+            };
+
+        parsed_slots.push(InterpolationSlot{start, end, expr, spec});
+    }
+
+    Ok(parsed_slots)
+}
+
+// `advance` returns the position that follows `text`, if `text` starts at
+// `pos`.
+fn advance(pos: Position, text: &str) -> Position {
+    let Position{mut line, mut col, offset} = pos;
+    for c in text.chars() {
+        if c == '\n' {
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+    }
+
+    Position{line, col, offset: offset + text.len()}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_slot() {
+        let tests = &[
+            ("n", ("n", None)),
+            ("n:>5", ("n", Some(">5"))),
+            ("xs[1:2]", ("xs[1:2]", None)),
+            ("xs[1:2]:?", ("xs[1:2]", Some("?"))),
+            ("{\"a\": 1}.a:x", ("{\"a\": 1}.a", Some("x"))),
+            ("\"a:\\\"b\"", ("\"a:\\\"b\"", None)),
+        ];
+
+        for (src, exp) in tests {
+            assert_eq!(&split_slot(src), exp, "incorrect split of '{src}'");
+        }
+    }
+
+    #[test]
+    fn test_parse_spec_errors() {
+        for raw_spec in &["5q", ".", "xx", ">>>"] {
+            assert!(
+                parse_spec(raw_spec).is_err(),
+                "'{raw_spec}' should be invalid",
+            );
+        }
+    }
+}
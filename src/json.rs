@@ -0,0 +1,561 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// `Json` is an intermediate representation of a JSON document. It's used
+// instead of `Value` so that JSON can also be produced for data that doesn't
+// originate from a Seed program, such as diagnostics.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    List(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    // `get` returns the property `name` of this value, if this value is an
+    // object.
+    pub fn get(&self, name: &str) -> Option<&Json> {
+        match self {
+            Json::Object(props) => props.get(name),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Json::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+// `new_object` returns a JSON object with the given properties.
+pub fn new_object(props: Vec<(&str, Json)>) -> Json {
+    let props: BTreeMap<String, Json> =
+        props
+            .into_iter()
+            .map(|(name, v)| (name.to_string(), v))
+            .collect();
+
+    Json::Object(props)
+}
+
+// `encode` renders `v` as a JSON string. If `indent` is `None` then `v` is
+// rendered on a single line without whitespace, otherwise each nested value is
+// rendered on its own line, indented by `indent` spaces per level.
+pub fn encode(v: &Json, indent: Option<usize>) -> String {
+    let mut s = String::new();
+    encode_next(&mut s, v, indent, 0);
+
+    s
+}
+
+fn encode_next(
+    s: &mut String,
+    v: &Json,
+    indent: Option<usize>,
+    depth: usize,
+) {
+    match v {
+        Json::Null => {
+            *s += "null";
+        },
+        Json::Bool(b) => {
+            *s += &b.to_string();
+        },
+        Json::Int(n) => {
+            *s += &n.to_string();
+        },
+        Json::Str(raw_str) => {
+            encode_str(s, raw_str);
+        },
+        Json::List(items) => {
+            if items.is_empty() {
+                *s += "[]";
+                return;
+            }
+
+            *s += "[";
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    *s += ",";
+                }
+                write_line_start(s, indent, depth + 1);
+                encode_next(s, item, indent, depth + 1);
+            }
+            write_line_start(s, indent, depth);
+            *s += "]";
+        },
+        Json::Object(props) => {
+            if props.is_empty() {
+                *s += "{}";
+                return;
+            }
+
+            *s += "{";
+            for (i, (name, prop)) in props.iter().enumerate() {
+                if i > 0 {
+                    *s += ",";
+                }
+                write_line_start(s, indent, depth + 1);
+                encode_str(s, name);
+                *s += ":";
+                if indent.is_some() {
+                    *s += " ";
+                }
+                encode_next(s, prop, indent, depth + 1);
+            }
+            write_line_start(s, indent, depth);
+            *s += "}";
+        },
+    }
+}
+
+fn write_line_start(s: &mut String, indent: Option<usize>, depth: usize) {
+    if let Some(n) = indent {
+        *s += "\n";
+        *s += &" ".repeat(n * depth);
+    }
+}
+
+fn encode_str(s: &mut String, raw_str: &str) {
+    *s += "\"";
+    for c in raw_str.chars() {
+        match c {
+            '"' => *s += "\\\"",
+            '\\' => *s += "\\\\",
+            '\n' => *s += "\\n",
+            '\r' => *s += "\\r",
+            '\t' => *s += "\\t",
+            c if (c as u32) < 0x20 => {
+                // `write!` to a `String` can't fail.
+                let _ = write!(s, "\\u{:04x}", c as u32);
+            },
+            c => s.push(c),
+        }
+    }
+    *s += "\"";
+}
+
+#[derive(Debug)]
+pub struct DecodeError {
+    pub line: usize,
+    pub col: usize,
+    pub msg: String,
+}
+
+// `decode` parses `src` as a single JSON value. Numbers with fractional parts
+// or exponents are rejected, because Seed only supports integers.
+pub fn decode(src: &str) -> Result<Json, DecodeError> {
+    let mut decoder = Decoder{chars: src.chars().peekable(), line: 1, col: 1};
+
+    decoder.skip_whitespace();
+    let v = decoder.next_value()?;
+    decoder.skip_whitespace();
+
+    if let Some(c) = decoder.chars.peek() {
+        let msg =
+            format!("unexpected '{}' after JSON value", c.escape_debug());
+
+        return Err(decoder.new_error(msg));
+    }
+
+    Ok(v)
+}
+
+struct Decoder<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    col: usize,
+}
+
+impl Decoder<'_> {
+    fn new_error(&self, msg: String) -> DecodeError {
+        DecodeError{line: self.line, col: self.col, msg}
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.chars.peek() {
+            if !matches!(c, ' ' | '\t' | '\n' | '\r') {
+                break;
+            }
+            self.next_char();
+        }
+    }
+
+    fn expect_char(&mut self, exp: char) -> Result<(), DecodeError> {
+        match self.chars.peek() {
+            Some(c) if *c == exp => {
+                self.next_char();
+
+                Ok(())
+            },
+            Some(c) => {
+                let msg =
+                    format!("expected '{exp}', got '{}'", c.escape_debug());
+
+                Err(self.new_error(msg))
+            },
+            None => {
+                Err(self.new_error(format!("expected '{exp}', got EOF")))
+            },
+        }
+    }
+
+    fn next_value(&mut self) -> Result<Json, DecodeError> {
+        let c =
+            match self.chars.peek() {
+                Some(c) => *c,
+                None => return Err(self.new_error(
+                    "expected a JSON value, got EOF".to_string(),
+                )),
+            };
+
+        match c {
+            'n' => self.next_keyword("null", Json::Null),
+            't' => self.next_keyword("true", Json::Bool(true)),
+            'f' => self.next_keyword("false", Json::Bool(false)),
+            '"' => Ok(Json::Str(self.next_str()?)),
+            '[' => self.next_list(),
+            '{' => self.next_object(),
+            '-' | '0'..='9' => self.next_int(),
+            c => Err(self.new_error(format!(
+                "expected a JSON value, got '{}'",
+                c.escape_debug(),
+            ))),
+        }
+    }
+
+    fn next_keyword(&mut self, keyword: &str, v: Json)
+        -> Result<Json, DecodeError>
+    {
+        for exp in keyword.chars() {
+            self.expect_char(exp)?;
+        }
+
+        Ok(v)
+    }
+
+    fn next_int(&mut self) -> Result<Json, DecodeError> {
+        let (line, col) = (self.line, self.col);
+
+        let mut raw_int = String::new();
+        if let Some('-') = self.chars.peek() {
+            raw_int.push('-');
+            self.next_char();
+        }
+        while let Some(c) = self.chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            raw_int.push(*c);
+            self.next_char();
+        }
+
+        if let Some('.' | 'e' | 'E') = self.chars.peek() {
+            return Err(DecodeError{
+                line,
+                col,
+                msg: "only integer numbers are supported".to_string(),
+            });
+        }
+
+        match raw_int.parse() {
+            Ok(n) => Ok(Json::Int(n)),
+            Err(_) => Err(DecodeError{
+                line,
+                col,
+                msg: format!("'{raw_int}' isn't a valid int"),
+            }),
+        }
+    }
+
+    fn next_str(&mut self) -> Result<String, DecodeError> {
+        self.expect_char('"')?;
+
+        let mut s = String::new();
+        loop {
+            let c =
+                match self.chars.peek() {
+                    Some(c) => *c,
+                    None => return Err(self.new_error(
+                        "unterminated string".to_string(),
+                    )),
+                };
+
+            if c == '"' {
+                self.next_char();
+
+                return Ok(s);
+            } else if c == '\\' {
+                self.next_char();
+                s.push(self.next_escape()?);
+            } else if (c as u32) < 0x20 {
+                return Err(self.new_error(format!(
+                    "unescaped control character '{}' in string",
+                    c.escape_debug(),
+                )));
+            } else {
+                self.next_char();
+                s.push(c);
+            }
+        }
+    }
+
+    fn next_escape(&mut self) -> Result<char, DecodeError> {
+        let c =
+            match self.chars.peek() {
+                Some(c) => *c,
+                None => return Err(self.new_error(
+                    "unterminated escape sequence".to_string(),
+                )),
+            };
+
+        let escaped =
+            match c {
+                '"' => '"',
+                '\\' => '\\',
+                '/' => '/',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    self.next_char();
+
+                    return self.next_unicode_escape();
+                },
+                c => {
+                    return Err(self.new_error(format!(
+                        "'{}' is not a valid escape character",
+                        c.escape_debug(),
+                    )));
+                },
+            };
+        self.next_char();
+
+        Ok(escaped)
+    }
+
+    // `next_unicode_escape` parses the hex digits of a `\u` escape, including
+    // a trailing low surrogate if the first code unit is a high surrogate.
+    fn next_unicode_escape(&mut self) -> Result<char, DecodeError> {
+        let (line, col) = (self.line, self.col);
+
+        let high = self.next_hex4()?;
+        let code_point =
+            if (0xd800..0xdc00).contains(&high) {
+                self.expect_char('\\')?;
+                self.expect_char('u')?;
+                let low = self.next_hex4()?;
+                if !(0xdc00..0xe000).contains(&low) {
+                    return Err(DecodeError{
+                        line,
+                        col,
+                        msg: "invalid surrogate pair".to_string(),
+                    });
+                }
+
+                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+            } else {
+                high
+            };
+
+        match char::from_u32(code_point) {
+            Some(c) => Ok(c),
+            None => Err(DecodeError{
+                line,
+                col,
+                msg: format!("'{code_point:04x}' isn't a valid code point"),
+            }),
+        }
+    }
+
+    fn next_hex4(&mut self) -> Result<u32, DecodeError> {
+        let mut n = 0;
+        for _ in 0..4 {
+            let c =
+                match self.chars.peek() {
+                    Some(c) => *c,
+                    None => return Err(self.new_error(
+                        "unterminated unicode escape".to_string(),
+                    )),
+                };
+
+            let h =
+                match c.to_digit(16) {
+                    Some(h) => h,
+                    None => return Err(self.new_error(format!(
+                        "'{}' is not a valid hex character",
+                        c.escape_debug(),
+                    ))),
+                };
+            self.next_char();
+
+            n = n * 16 + h;
+        }
+
+        Ok(n)
+    }
+
+    fn next_list(&mut self) -> Result<Json, DecodeError> {
+        self.expect_char('[')?;
+        self.skip_whitespace();
+
+        let mut items = vec![];
+        if let Some(']') = self.chars.peek() {
+            self.next_char();
+
+            return Ok(Json::List(items));
+        }
+
+        loop {
+            self.skip_whitespace();
+            items.push(self.next_value()?);
+            self.skip_whitespace();
+
+            if let Some(',') = self.chars.peek() {
+                self.next_char();
+            } else {
+                self.expect_char(']')?;
+
+                return Ok(Json::List(items));
+            }
+        }
+    }
+
+    fn next_object(&mut self) -> Result<Json, DecodeError> {
+        self.expect_char('{')?;
+        self.skip_whitespace();
+
+        let mut props = BTreeMap::new();
+        if let Some('}') = self.chars.peek() {
+            self.next_char();
+
+            return Ok(Json::Object(props));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let name = self.next_str()?;
+            self.skip_whitespace();
+            self.expect_char(':')?;
+            self.skip_whitespace();
+            let v = self.next_value()?;
+            props.insert(name, v);
+            self.skip_whitespace();
+
+            if let Some(',') = self.chars.peek() {
+                self.next_char();
+            } else {
+                self.expect_char('}')?;
+
+                return Ok(Json::Object(props));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encodes() {
+        let v = Json::Object(BTreeMap::from([
+            ("a".to_string(), Json::List(vec![Json::Int(1), Json::Null])),
+            ("b".to_string(), Json::Str("x\"y\n".to_string())),
+            ("c".to_string(), Json::List(vec![])),
+        ]));
+
+        assert_eq!(
+            encode(&v, None),
+            r#"{"a":[1,null],"b":"x\"y\n","c":[]}"#,
+        );
+        assert_eq!(
+            encode(&v, Some(2)),
+            "{\n  \"a\": [\n    1,\n    null\n  ],\n  \"b\": \"x\\\"y\\n\",\
+             \n  \"c\": []\n}",
+        );
+    }
+
+    #[test]
+    fn test_decodes() {
+        let tests = &[
+            ("null", Json::Null),
+            (" true ", Json::Bool(true)),
+            ("-12", Json::Int(-12)),
+            (
+                r#""a\u00e9\ud83d\ude00""#,
+                Json::Str("a\u{e9}\u{1f600}".to_string()),
+            ),
+            (
+                r#"[1, {"a": []}]"#,
+                Json::List(vec![
+                    Json::Int(1),
+                    Json::Object(BTreeMap::from([
+                        ("a".to_string(), Json::List(vec![])),
+                    ])),
+                ]),
+            ),
+        ];
+
+        for (src, exp) in tests {
+            let act = decode(src)
+                .expect("couldn't decode JSON");
+
+            assert_eq!(&act, exp, "unexpected decoding of '{src}'");
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let tests = &[
+            ("", (1, 1)),
+            ("[1,\n 2.5]", (2, 2)),
+            ("{\"a\" 1}", (1, 6)),
+            ("[1] 2", (1, 5)),
+        ];
+
+        for (src, (exp_line, exp_col)) in tests {
+            let err = decode(src)
+                .expect_err("decoding should have failed");
+
+            assert_eq!(
+                (err.line, err.col),
+                (*exp_line, *exp_col),
+                "unexpected error location for '{src}': {}",
+                err.msg,
+            );
+        }
+    }
+}
@@ -8,7 +8,10 @@ mod scanner;
 
 use self::scanner::Scanner;
 
-pub type InterpSlot = (usize, usize);
+// `InterpSlot` contains the start and end indices of an interpolation slot in
+// an interpolated string, and the position of the contents of the slot in the
+// source.
+pub type InterpSlot = (usize, usize, Position);
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
@@ -78,10 +81,23 @@ pub enum LexError {
     InvalidHexChar(Location, char),
 }
 
+// `Comment` is a `#` comment in the source. `text` includes the leading `#`,
+// but not the newline that ends the comment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment {
+    pub pos: Position,
+    pub text: String,
+}
+
 pub struct Lexer<'input> {
     pub scanner: Scanner<'input>,
 
     last_token: Option<Token>,
+
+    // `comments` contains the comments that have been skipped so far, if the
+    // `Lexer` was created using `new_keeping_comments`; otherwise it's
+    // `None`.
+    comments: Option<Vec<Comment>>,
 }
 
 impl<'input> Lexer<'input> {
@@ -89,18 +105,60 @@ impl<'input> Lexer<'input> {
         Lexer{
             scanner: Scanner::new(chars),
             last_token: None,
+            comments: None,
+        }
+    }
+
+    // `new_keeping_comments` returns a `Lexer` that records the comments that
+    // it skips, so that tools that rewrite the source, such as the formatter,
+    // can preserve them. The comments can be retrieved using
+    // `take_comments`. The same tokens are returned as for a `Lexer` that's
+    // created using `new`.
+    pub fn new_keeping_comments(chars: &'input str) -> Self {
+        Lexer{
+            scanner: Scanner::new(chars),
+            last_token: None,
+            comments: Some(vec![]),
         }
     }
 
+    // `new_at` returns a `Lexer` for `chars`, where the first character of
+    // `chars` is at `pos`.
+    pub fn new_at(chars: &'input str, pos: Position) -> Self {
+        Lexer{
+            scanner: Scanner::new_at(chars, pos),
+            last_token: None,
+            comments: None,
+        }
+    }
+
+    // `take_comments` returns the comments that have been skipped so far, in
+    // the order that they appear in the source. It returns an empty list if
+    // this `Lexer` wasn't created using `new_keeping_comments`.
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        self.comments
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
     fn skip_whitespace_and_comments(&mut self) {
         while let Some(c) = self.scanner.peek_char() {
             if c == '#' {
+                let pos = self.scanner.pos();
+                let start = self.scanner.index;
                 while let Some(c_) = self.scanner.peek_char() {
                     if c_ == '\n' {
                         break;
                     }
                     self.scanner.next_char();
                 }
+
+                if let Some(comments) = &mut self.comments {
+                    let end = self.scanner.index;
+                    let text = self.scanner.range(start, end).to_string();
+                    comments.push(Comment{pos, text});
+                }
             } else {
                 // We return a `Token::StmtEnd` in the case of a newline
                 // character, as a simplification of the rules for omitting
@@ -183,16 +241,20 @@ impl<'input> Lexer<'input> {
     {
         self.scanner.next_char();
 
-        let mut chars = vec![];
+        // We use a `String` rather than a `Vec<char>` so that the indices of
+        // interpolation slots are byte indices into the resulting string.
+        let mut chars = String::new();
         let mut state = StrScanState::None;
         let mut first_hex_char = None;
 
         let mut cur_interpolation_start = 0;
+        let mut cur_interpolation_pos = Position::default();
         let mut interpolation_slots = vec![];
         let mut interpolation_brace_count = 0;
 
         while let Some(c) = self.scanner.peek_char() {
-            let cur_loc = self.scanner.loc();
+            let cur_pos = self.scanner.pos();
+            let cur_loc = cur_pos.loc();
 
             self.scanner.next_char();
 
@@ -267,6 +329,10 @@ impl<'input> Lexer<'input> {
                         ));
                     }
 
+                    if cur_interpolation_start+2 == chars.len() {
+                        cur_interpolation_pos = cur_pos;
+                    }
+
                     if c == '{' {
                         interpolation_brace_count += 1;
                     } else if c == '}' {
@@ -275,7 +341,11 @@ impl<'input> Lexer<'input> {
 
                     if interpolation_brace_count == 0 {
                         // We shorten the slot to ignore the delimiters.
-                        let slot = (cur_interpolation_start, chars.len()+1);
+                        let slot = (
+                            cur_interpolation_start,
+                            chars.len()+1,
+                            cur_interpolation_pos,
+                        );
                         interpolation_slots.push(slot);
                         state = StrScanState::None;
                     }
@@ -285,12 +355,10 @@ impl<'input> Lexer<'input> {
             }
         }
 
-        let s = chars.into_iter().collect();
-
         if interpolate {
-            Ok(Token::InterpStrLiteral(s, interpolation_slots))
+            Ok(Token::InterpStrLiteral(chars, interpolation_slots))
         } else {
-            Ok(Token::StrLiteral(s))
+            Ok(Token::StrLiteral(chars))
         }
     }
 
@@ -391,10 +459,11 @@ impl<'input> Lexer<'input> {
         Some(t)
     }
 
-    fn next_token(&mut self) -> Option<Result<Span, LexError>> {
+    fn next_token(&mut self) -> Option<Result<TokenSpan, LexError>> {
         self.skip_whitespace_and_comments();
 
-        let start_loc = self.scanner.loc();
+        let start_pos = self.scanner.pos();
+        let start_loc = start_pos.loc();
 
         let c = self.scanner.peek_char()?;
 
@@ -427,15 +496,7 @@ impl<'input> Lexer<'input> {
                 return Some(Err(LexError::Unexpected(start_loc, c)));
             };
 
-        let (line, mut col) = self.scanner.loc();
-        if self.scanner.peek_char().is_some() && col > 0 {
-            // We reduce the column count by one, under the assumption that in
-            // the successful cases above the scanner has progressed one
-            // character beyond the end of the current token.
-            col -= 1;
-        }
-
-        Some(Ok((start_loc, t, (line, col))))
+        Some(Ok((start_pos, t, self.scanner.end_pos())))
     }
 }
 
@@ -446,12 +507,30 @@ enum StrScanState {
     Interpolate,
 }
 
-pub type Span = (Location, Token, Location);
+pub type TokenSpan = (Position, Token, Position);
 
 pub type Location = (usize, usize);
 
+// `Position` is a `Location` in the source, along with the byte offset of the
+// `Location` from the start of the source. The `Position` at the end of a
+// token refers to the line and column of the last character of the token, but
+// the offset refers to the byte after the token, so that the offsets of a
+// token can be used to slice the source.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub col: usize,
+    pub offset: usize,
+}
+
+impl Position {
+    pub fn loc(self) -> Location {
+        (self.line, self.col)
+    }
+}
+
 impl Iterator for Lexer<'_> {
-    type Item = Result<Span, LexError>;
+    type Item = Result<TokenSpan, LexError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
@@ -619,9 +698,10 @@ mod test {
         let exp_spans = new_expected_spans(encoded_exp_locs, exp_toks);
 
         for (n, exp_span) in exp_spans.into_iter().enumerate() {
-            let act_span = lexer.next()
+            let (act_start, act_tok, act_end) = lexer.next()
                 .expect("token stream ended before expected")
                 .expect("unexpected error in token stream");
+            let act_span = (act_start.loc(), act_tok, act_end.loc());
 
             assert_eq!(
                 exp_span,
@@ -637,8 +717,65 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_lexes_offsets() {
+        let src = "x := \"é\" +\n  f(12)";
+        let exp_texts = &["x", ":=", "\"é\"", "+", "f", "(", "12", ")"];
+
+        let act_texts: Vec<&str> =
+            Lexer::new(src)
+                .map(|item| {
+                    let (start, _, end) = item
+                        .expect("unexpected error in token stream");
+
+                    &src[start.offset..end.offset]
+                })
+                .collect();
+
+        assert_eq!(exp_texts.to_vec(), act_texts);
+    }
+
+    #[test]
+    fn test_lexes_comments() {
+        let src = "x := 1 # one\n\n# two\nprint(x)";
+
+        let mut lexer = Lexer::new_keeping_comments(src);
+        let toks: Vec<Token> =
+            lexer.by_ref()
+                .map(|item| {
+                    let (_, t, _) = item
+                        .expect("unexpected error in token stream");
+
+                    t
+                })
+                .collect();
+        let comments: Vec<(Location, String)> =
+            lexer.take_comments()
+                .into_iter()
+                .map(|Comment{pos, text}| (pos.loc(), text))
+                .collect();
+
+        let exp_toks: Vec<Token> =
+            Lexer::new(src)
+                .map(|item| {
+                    let (_, t, _) = item
+                        .expect("unexpected error in token stream");
+
+                    t
+                })
+                .collect();
+        assert_eq!(toks, exp_toks);
+        assert_eq!(
+            comments,
+            vec![
+                ((1, 8), "# one".to_string()),
+                ((3, 1), "# two".to_string()),
+            ],
+        );
+    }
+
     fn new_expected_spans(encoded_exp_locs: &str, exp_toks: Vec<Token>)
-        -> Vec<Span>
+        -> Vec<(Location, Token, Location)>
     {
         let exp_locs = parse_encoded_locs(encoded_exp_locs);
 
@@ -4,6 +4,8 @@
 
 use std::str::CharIndices;
 
+use super::Position;
+
 pub struct Scanner<'a> {
     raw_chars: &'a str,
     chars: CharIndices<'a>,
@@ -14,17 +16,26 @@ pub struct Scanner<'a> {
     // and column.
     line: usize,
     col: usize,
+    // `last_loc` is the location of the last character that was consumed by
+    // `next_char`.
+    last_loc: (usize, usize),
+    // `base_offset` is the byte offset of the start of `raw_chars` in the
+    // source.
+    base_offset: usize,
 }
 
 impl<'a> Scanner<'a> {
     pub fn new(chars: &'a str) -> Self {
+        Scanner::new_at(chars, Position{line: 1, col: 1, offset: 0})
+    }
+
+    // `new_at` returns a `Scanner` for `chars`, where the first character of
+    // `chars` is at `pos`. This allows substrings of a source file to be
+    // scanned while reporting positions relative to the source file.
+    pub fn new_at(chars: &'a str, pos: Position) -> Self {
         let mut char_indices = chars.char_indices();
 
         let cur_char = char_indices.next().map(|(_, c)| c);
-        let mut loc = (1, 1);
-        if let Some('\n') = cur_char {
-            loc = (2, 0);
-        }
 
         Scanner{
             raw_chars: chars,
@@ -32,8 +43,10 @@ impl<'a> Scanner<'a> {
             index: 0,
             cur_char,
 
-            line: loc.0,
-            col: loc.1,
+            line: pos.line,
+            col: pos.col,
+            last_loc: (pos.line, pos.col),
+            base_offset: pos.offset,
         }
     }
 
@@ -46,16 +59,22 @@ impl<'a> Scanner<'a> {
         // because the characters of a UTF-8 string can't be indexed in
         // constant time.
 
-        if let Some((i, c)) = self.chars.next() {
-            self.index = i;
-            self.cur_char = Some(c);
+        // A newline is located at the end of the line that it terminates, so
+        // we only move to the next line after moving past a newline.
+        if let Some(c) = self.cur_char {
+            self.last_loc = (self.line, self.col);
 
             if c == '\n' {
                 self.line += 1;
-                self.col = 0;
+                self.col = 1;
             } else {
                 self.col += 1;
             }
+        }
+
+        if let Some((i, c)) = self.chars.next() {
+            self.index = i;
+            self.cur_char = Some(c);
         } else {
             self.index = self.raw_chars.len();
             self.cur_char = None;
@@ -66,6 +85,23 @@ impl<'a> Scanner<'a> {
         (self.line, self.col)
     }
 
+    pub fn pos(&self) -> Position {
+        Position{
+            line: self.line,
+            col: self.col,
+            offset: self.base_offset + self.index,
+        }
+    }
+
+    // `end_pos` returns the position at the end of the last character that was
+    // consumed by `next_char`, which is the line and column of the character
+    // and the offset of the byte that follows it.
+    pub fn end_pos(&self) -> Position {
+        let (line, col) = self.last_loc;
+
+        Position{line, col, offset: self.base_offset + self.index}
+    }
+
     pub fn range(&self, start: usize, end: usize) -> &'a str {
         &self.raw_chars[start..end]
     }
@@ -91,6 +127,21 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_scans_newlines() {
+        let mut scanner = Scanner::new("ab\n\nc");
+        let mut newline_locs = vec![];
+
+        while let Some(c) = scanner.peek_char() {
+            if c == '\n' {
+                newline_locs.push(scanner.loc());
+            }
+            scanner.next_char();
+        }
+
+        assert_eq!(newline_locs, vec![(1, 3), (2, 1)]);
+    }
+
     fn assert_scan_dollar(src: &str, exp_dollar_loc: (usize, usize)) {
         let mut scanner = Scanner::new(src);
 
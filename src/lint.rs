@@ -0,0 +1,591 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::HashSet;
+
+use crate::ast::Block;
+use crate::ast::Expr;
+use crate::ast::ListItem;
+use crate::ast::Location;
+use crate::ast::PropItem;
+use crate::ast::RawExpr;
+use crate::ast::RawStmt;
+use crate::ast::Span;
+use crate::ast::Stmt;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum WarningKind {
+    UnusedVariables,
+    Shadowing,
+    UnreachableCode,
+}
+
+impl WarningKind {
+    pub const ALL: &'static [WarningKind] = &[
+        WarningKind::UnusedVariables,
+        WarningKind::Shadowing,
+        WarningKind::UnreachableCode,
+    ];
+
+    // `name` returns the name that's used to enable or disable this kind of
+    // warning on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            WarningKind::UnusedVariables => "unused-variables",
+            WarningKind::Shadowing => "shadowing",
+            WarningKind::UnreachableCode => "unreachable-code",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<WarningKind> {
+        WarningKind::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub msg: String,
+    // `span` is the start and end (inclusive) of the source that the warning
+    // refers to.
+    pub span: (Location, Location),
+    pub notes: Vec<(String, Option<Location>)>,
+}
+
+// `lint` returns warnings of the `enabled` kinds for `stmts`, which is the
+// body of a program, sorted by location. The warnings are found by walking
+// the AST, so `lint` doesn't evaluate `stmts`.
+pub fn lint(stmts: &Block, enabled: &HashSet<WarningKind>) -> Vec<Warning> {
+    let mut linter = Linter{enabled, scopes: vec![], warnings: vec![]};
+
+    linter.push_scope();
+    linter.lint_stmts(stmts);
+    linter.pop_scope();
+
+    let mut warnings = linter.warnings;
+    warnings.sort_by_key(|w| w.span);
+
+    warnings
+}
+
+struct Linter<'a> {
+    enabled: &'a HashSet<WarningKind>,
+    scopes: Vec<Scope>,
+    warnings: Vec<Warning>,
+}
+
+#[derive(Default)]
+struct Scope {
+    vars: Vec<Var>,
+    // `pending` contains the names that were referenced in this scope before
+    // they were declared in any enclosing scope. These names may refer to
+    // variables that are declared later, such as when a function refers to a
+    // variable that's declared after the function, so they're resolved when
+    // the scope is popped.
+    pending: HashSet<String>,
+}
+
+struct Var {
+    name: String,
+    span: (Location, Location),
+    used: bool,
+    // `check_unused` is `false` for variables that are commonly unused, such
+    // as function parameters, and for variables that the user has opted out
+    // of checks for, using a `_` prefix.
+    check_unused: bool,
+}
+
+impl Linter<'_> {
+    fn warn(
+        &mut self,
+        kind: WarningKind,
+        msg: String,
+        span: (Location, Location),
+        notes: Vec<(String, Option<Location>)>,
+    ) {
+        if self.enabled.contains(&kind) {
+            self.warnings.push(Warning{kind, msg, span, notes});
+        }
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    fn pop_scope(&mut self) {
+        let mut scope = self.scopes.pop()
+            .expect("`Linter` scope stack shouldn't be empty");
+
+        for name in scope.pending.drain() {
+            if let Some(var) = find_var(&mut scope.vars, &name) {
+                var.used = true;
+            } else if let Some(parent) = self.scopes.last_mut() {
+                parent.pending.insert(name);
+            }
+        }
+
+        for var in scope.vars {
+            if var.check_unused && !var.used {
+                self.warn(
+                    WarningKind::UnusedVariables,
+                    format!("'{}' is declared but never used", var.name),
+                    var.span,
+                    vec![],
+                );
+            }
+        }
+    }
+
+    fn cur_scope(&mut self) -> &mut Scope {
+        self.scopes.last_mut()
+            .expect("`Linter` scope stack shouldn't be empty")
+    }
+
+    // `declare` declares `name` in the current scope. If `check_shadowing` is
+    // `true` then a warning is generated if `name` is declared in an outer
+    // scope.
+    fn declare(
+        &mut self,
+        name: &str,
+        span: (Location, Location),
+        check_unused: bool,
+        check_shadowing: bool,
+    ) {
+        if name == "_" {
+            return;
+        }
+
+        if check_shadowing {
+            let n = self.scopes.len() - 1;
+            let maybe_outer_loc =
+                self.scopes[..n]
+                    .iter()
+                    .rev()
+                    .find_map(|scope| {
+                        scope.vars
+                            .iter()
+                            .rev()
+                            .find(|var| var.name == name)
+                            .map(|var| var.span.0)
+                    });
+
+            if let Some(outer_loc) = maybe_outer_loc {
+                self.warn(
+                    WarningKind::Shadowing,
+                    format!("'{name}' shadows a variable in an outer scope"),
+                    span,
+                    vec![
+                        (
+                            format!("'{name}' was previously defined here"),
+                            Some(outer_loc),
+                        ),
+                        (
+                            format!("use '=' to assign to the outer '{name}'"),
+                            None,
+                        ),
+                    ],
+                );
+            }
+        }
+
+        self.cur_scope().vars.push(Var{
+            name: name.to_string(),
+            span,
+            used: false,
+            check_unused: check_unused && !name.starts_with('_'),
+        });
+    }
+
+    // `reference` marks the variable that `name` refers to as used.
+    fn reference(&mut self, name: &str) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(var) = find_var(&mut scope.vars, name) {
+                var.used = true;
+
+                return;
+            }
+        }
+
+        self.cur_scope().pending.insert(name.to_string());
+    }
+
+    fn lint_block(&mut self, stmts: &Block) {
+        self.push_scope();
+        self.lint_stmts(stmts);
+        self.pop_scope();
+    }
+
+    fn lint_stmts(&mut self, stmts: &Block) {
+        // We only report the first unreachable statement in each block, but
+        // we continue to lint the unreachable statements so that variables
+        // that they use aren't reported as unused.
+        if let Some(i) = stmts.iter().position(terminates) {
+            if let (Some((_, first)), Some((_, last))) =
+                (stmts.get(i + 1), stmts.last())
+            {
+                let (_, terminator) = &stmts[i];
+
+                self.warn(
+                    WarningKind::UnreachableCode,
+                    "unreachable statement".to_string(),
+                    (first.start.loc(), last.end.loc()),
+                    vec![(
+                        "any code following this statement is unreachable"
+                            .to_string(),
+                        Some(terminator.loc()),
+                    )],
+                );
+            }
+        }
+
+        for stmt in stmts {
+            self.lint_stmt(stmt);
+        }
+    }
+
+    fn lint_stmt(&mut self, (raw_stmt, _): &Stmt) {
+        match raw_stmt {
+            RawStmt::Block{block} => {
+                self.lint_block(block);
+            },
+            RawStmt::Expr{expr} | RawStmt::Return{expr, ..} => {
+                self.lint_expr(expr);
+            },
+            RawStmt::Declare{lhs, rhs} => {
+                self.lint_expr(rhs);
+                self.lint_binding(lhs, true);
+            },
+            RawStmt::Assign{lhs, rhs} | RawStmt::OpAssign{lhs, rhs, ..} => {
+                self.lint_expr(rhs);
+                self.lint_assignment(lhs);
+            },
+            RawStmt::If{branches, else_stmts} => {
+                for branch in branches {
+                    self.lint_expr(&branch.cond);
+                    self.lint_block(&branch.stmts);
+                }
+                if let Some(stmts) = else_stmts {
+                    self.lint_block(stmts);
+                }
+            },
+            RawStmt::While{cond, stmts} => {
+                self.lint_expr(cond);
+                self.lint_block(stmts);
+            },
+            RawStmt::For{lhs, iter, stmts} => {
+                self.lint_expr(iter);
+
+                self.push_scope();
+                self.lint_binding(lhs, false);
+                self.lint_stmts(stmts);
+                self.pop_scope();
+            },
+            RawStmt::Func{name: (name, loc), args, stmts, ..} => {
+                // We declare the function before linting its body so that
+                // recursive calls refer to the function.
+                self.declare(name, (*loc, *loc), false, false);
+                self.lint_func(args, stmts);
+            },
+            RawStmt::Break{..} | RawStmt::Continue{..} | RawStmt::Error => {
+            },
+        }
+    }
+
+    fn lint_func(&mut self, args: &[Expr], stmts: &Block) {
+        self.push_scope();
+        for arg in args {
+            self.lint_param(arg);
+        }
+        self.lint_stmts(stmts);
+        self.pop_scope();
+    }
+
+    fn lint_param(&mut self, param: &Expr) {
+        let (raw_expr, span) = param;
+        match raw_expr {
+            RawExpr::Var{name} => {
+                self.declare(name, span_locs(span), false, false);
+            },
+            RawExpr::List{items, ..} => {
+                for ListItem{expr, ..} in items {
+                    self.lint_param(expr);
+                }
+            },
+            RawExpr::Object{props} => {
+                for prop in props {
+                    match prop {
+                        PropItem::Pair{value, ..} => {
+                            self.lint_param(value);
+                        },
+                        PropItem::Single{expr, ..} => {
+                            self.lint_param(expr);
+                        },
+                    }
+                }
+            },
+            _ => {
+                self.lint_expr(param);
+            },
+        }
+    }
+
+    // `lint_binding` lints the left-hand side of a declaration. `:=` can
+    // shadow outer variables by mistake, so `check_shadowing` should be
+    // `true` for declaration statements.
+    fn lint_binding(&mut self, lhs: &Expr, check_shadowing: bool) {
+        let (raw_expr, span) = lhs;
+        match raw_expr {
+            RawExpr::Var{name} => {
+                self.declare(name, span_locs(span), true, check_shadowing);
+            },
+            RawExpr::List{items, ..} => {
+                for ListItem{expr, ..} in items {
+                    self.lint_binding(expr, check_shadowing);
+                }
+            },
+            RawExpr::Object{props} => {
+                for prop in props {
+                    match prop {
+                        PropItem::Pair{name, value} => {
+                            self.lint_expr(name);
+                            self.lint_binding(value, check_shadowing);
+                        },
+                        PropItem::Single{expr, ..} => {
+                            self.lint_binding(expr, check_shadowing);
+                        },
+                    }
+                }
+            },
+            _ => {
+                self.lint_expr(lhs);
+            },
+        }
+    }
+
+    // `lint_assignment` lints the left-hand side of an assignment. Assigning
+    // to a variable doesn't count as using it, but the containers of assigned
+    // indices and properties are used.
+    fn lint_assignment(&mut self, lhs: &Expr) {
+        let (raw_expr, _) = lhs;
+        match raw_expr {
+            RawExpr::Var{..} => {
+            },
+            RawExpr::List{items, ..} => {
+                for ListItem{expr, ..} in items {
+                    self.lint_assignment(expr);
+                }
+            },
+            RawExpr::Object{props} => {
+                for prop in props {
+                    match prop {
+                        PropItem::Pair{name, value} => {
+                            self.lint_expr(name);
+                            self.lint_assignment(value);
+                        },
+                        PropItem::Single{expr, ..} => {
+                            self.lint_assignment(expr);
+                        },
+                    }
+                }
+            },
+            _ => {
+                self.lint_expr(lhs);
+            },
+        }
+    }
+
+    fn lint_expr(&mut self, (raw_expr, _): &Expr) {
+        match raw_expr {
+            RawExpr::Null | RawExpr::Bool{..} | RawExpr::Int{..} => {
+            },
+            RawExpr::Str{interpolation_slots, ..} => {
+                for slot in interpolation_slots.iter().flatten() {
+                    self.lint_expr(&slot.expr);
+                }
+            },
+            RawExpr::Var{name} => {
+                self.reference(name);
+            },
+            RawExpr::BinaryOp{lhs, rhs, ..} => {
+                self.lint_expr(lhs);
+                self.lint_expr(rhs);
+            },
+            RawExpr::Range{start, end} => {
+                self.lint_expr(start);
+                self.lint_expr(end);
+            },
+            RawExpr::List{items, ..} => {
+                for ListItem{expr, ..} in items {
+                    self.lint_expr(expr);
+                }
+            },
+            RawExpr::Index{expr, location} => {
+                self.lint_expr(expr);
+                self.lint_expr(location);
+            },
+            RawExpr::RangeIndex{expr, start, end} => {
+                self.lint_expr(expr);
+                for e in start.iter().chain(end.iter()) {
+                    self.lint_expr(e);
+                }
+            },
+            RawExpr::Object{props} => {
+                for prop in props {
+                    match prop {
+                        PropItem::Pair{name, value} => {
+                            self.lint_expr(name);
+                            self.lint_expr(value);
+                        },
+                        PropItem::Single{expr, ..} => {
+                            self.lint_expr(expr);
+                        },
+                    }
+                }
+            },
+            RawExpr::Prop{expr, ..} => {
+                self.lint_expr(expr);
+            },
+            RawExpr::Func{args, stmts, ..} => {
+                self.lint_func(args, stmts);
+            },
+            RawExpr::Call{func, args} => {
+                self.lint_expr(func);
+                for ListItem{expr, ..} in args {
+                    self.lint_expr(expr);
+                }
+            },
+        }
+    }
+}
+
+fn find_var<'a>(vars: &'a mut [Var], name: &str) -> Option<&'a mut Var> {
+    vars.iter_mut().rev().find(|var| var.name == name)
+}
+
+fn span_locs(span: &Span) -> (Location, Location) {
+    (span.start.loc(), span.end.loc())
+}
+
+// `terminates` returns `true` if `stmt` always transfers control out of the
+// block that contains it, using `return`, `break` or `continue`.
+fn terminates((raw_stmt, _): &Stmt) -> bool {
+    match raw_stmt {
+        RawStmt::Return{..} | RawStmt::Break{..} | RawStmt::Continue{..} => {
+            true
+        },
+        RawStmt::Block{block} => {
+            block.iter().any(terminates)
+        },
+        RawStmt::If{branches, else_stmts: Some(else_stmts)} => {
+            branches.iter().all(|b| b.stmts.iter().any(terminates))
+                && else_stmts.iter().any(terminates)
+        },
+        _ => {
+            false
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::ast::Prog;
+    use crate::lexer::Lexer;
+    use crate::parser::ProgParser;
+    use crate::syntax_error;
+
+    // `lint_src` returns the messages and start locations of all warnings for
+    // `src`.
+    fn lint_src(src: &str) -> Vec<(String, Location)> {
+        let tokens = syntax_error::tokens(Lexer::new(src));
+        let mut errors = vec![];
+        let Prog::Body{stmts} =
+            ProgParser::new().parse(&mut errors, tokens)
+                .expect("couldn't parse source");
+        assert!(errors.is_empty(), "unexpected syntax errors: {errors:?}");
+
+        let enabled = WarningKind::ALL.iter().copied().collect();
+
+        lint(&stmts, &enabled)
+            .into_iter()
+            .map(|w| (w.msg, w.span.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint() {
+        let tests: &[(&str, &[(&str, Location)])] = &[
+            (
+                "x := 1\n",
+                &[("'x' is declared but never used", (1, 1))],
+            ),
+            (
+                "_x := 1\n",
+                &[],
+            ),
+            (
+                "x := 1\nx = 2\n",
+                &[("'x' is declared but never used", (1, 1))],
+            ),
+            (
+                "xs := [1]\nxs[0] = 2\n",
+                &[],
+            ),
+            (
+                "fn f() { return x; }\nx := 1\nprint(f());\n",
+                &[],
+            ),
+            (
+                "fn f(a, b) { return 1; }\nprint(f(1, 2));\n",
+                &[],
+            ),
+            (
+                "for x in [1] { print(1); }\n",
+                &[("'x' is declared but never used", (1, 5))],
+            ),
+            (
+                "x := 1\nif true { x := 2\nprint(x); }\nprint(x);\n",
+                &[("'x' shadows a variable in an outer scope", (2, 11))],
+            ),
+            (
+                "fn f() { return 1;\nprint(2); }\nprint(f());\n",
+                &[("unreachable statement", (2, 1))],
+            ),
+            (
+                "while true {\nif true { break; } else { continue; }\n\
+                 print(1);\nprint(2);\n}\n",
+                &[("unreachable statement", (3, 1))],
+            ),
+        ];
+
+        for (src, exp) in tests {
+            let exp: Vec<(String, Location)> =
+                exp.iter()
+                    .map(|(msg, loc)| (msg.to_string(), *loc))
+                    .collect();
+
+            assert_eq!(lint_src(src), exp, "incorrect warnings: {src:?}");
+        }
+    }
+
+    #[test]
+    fn test_lint_enabled() {
+        let src = "x := 1\nif true { x := 2\nprint(x); }\n";
+        let tokens = syntax_error::tokens(Lexer::new(src));
+        let mut errors = vec![];
+        let Prog::Body{stmts} =
+            ProgParser::new().parse(&mut errors, tokens)
+                .expect("couldn't parse source");
+
+        let enabled = HashSet::from([WarningKind::Shadowing]);
+        let kinds: Vec<WarningKind> =
+            lint(&stmts, &enabled)
+                .into_iter()
+                .map(|w| w.kind)
+                .collect();
+
+        assert_eq!(kinds, vec![WarningKind::Shadowing]);
+    }
+}
@@ -0,0 +1,527 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use crate::ast::Block;
+use crate::ast::Expr;
+use crate::ast::ListItem;
+use crate::ast::Location;
+use crate::ast::PropItem;
+use crate::ast::RawExpr;
+use crate::ast::RawStmt;
+use crate::ast::Span;
+use crate::ast::Stmt;
+
+// `Range` is the start and end (inclusive) of a region of the source.
+pub type Range = (Location, Location);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymbolKind {
+    Variable,
+    Function,
+    Parameter,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    // `range` is the range of the name in the declaration of the symbol.
+    pub range: Range,
+    // `scope` is the range of the source that the symbol can be referenced
+    // in, or `None` if the symbol is declared at the top level of the
+    // program.
+    pub scope: Option<Range>,
+    // `detail` is a short description of the symbol, such as the signature of
+    // a function.
+    pub detail: String,
+}
+
+// `Analysis` contains the symbols that are declared in a program, and the
+// references to them.
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub symbols: Vec<Symbol>,
+    // `refs` contains the range of each reference to a symbol, along with
+    // the index of the symbol in `symbols`.
+    pub refs: Vec<(Range, usize)>,
+    // `unresolved` contains the references to names that aren't declared in
+    // the program, such as builtin functions.
+    pub unresolved: Vec<(Range, String)>,
+}
+
+impl Analysis {
+    // `symbol_at` returns the index of the symbol that is declared or
+    // referenced at `loc`. The location directly after a name is considered
+    // to be part of the name, because that's where the cursor is usually
+    // placed after typing the name.
+    pub fn symbol_at(&self, loc: Location) -> Option<usize> {
+        let decls =
+            self.symbols
+                .iter()
+                .enumerate()
+                .map(|(i, symbol)| (symbol.range, i));
+
+        self.refs
+            .iter()
+            .copied()
+            .chain(decls)
+            .find(|(range, _)| touches(*range, loc))
+            .map(|(_, i)| i)
+    }
+
+    // `unresolved_at` returns the undeclared name that is referenced at
+    // `loc`.
+    pub fn unresolved_at(&self, loc: Location) -> Option<&str> {
+        self.unresolved
+            .iter()
+            .find(|(range, _)| touches(*range, loc))
+            .map(|(_, name)| name.as_str())
+    }
+
+    // `references` returns the ranges of the references to the symbol at
+    // index `symbol`, in the order that they appear in the source.
+    pub fn references(&self, symbol: usize) -> Vec<Range> {
+        let mut ranges: Vec<Range> =
+            self.refs
+                .iter()
+                .filter(|(_, i)| *i == symbol)
+                .map(|(range, _)| *range)
+                .collect();
+        ranges.sort_unstable();
+
+        ranges
+    }
+
+    // `visible_at` returns the indices of the symbols that can be referenced
+    // at `loc`. Symbols are only visible after they're declared, and only the
+    // innermost symbol with a given name is returned.
+    pub fn visible_at(&self, loc: Location) -> Vec<usize> {
+        let mut visible: Vec<usize> = vec![];
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            let in_scope =
+                symbol.scope.is_none_or(|scope| touches(scope, loc));
+            if !in_scope || symbol.range.1 >= loc {
+                continue;
+            }
+
+            let maybe_prev =
+                visible
+                    .iter()
+                    .position(|j| self.symbols[*j].name == symbol.name);
+            match maybe_prev {
+                Some(j) => visible[j] = i,
+                None => visible.push(i),
+            }
+        }
+
+        visible
+    }
+}
+
+// `touches` returns `true` if `loc` is in `range`, or directly after it.
+fn touches((start, (end_line, end_col)): Range, loc: Location) -> bool {
+    start <= loc && loc <= (end_line, end_col + 1)
+}
+
+// `analyse` returns the symbols that are declared in `stmts`, which is the
+// body of a program that was parsed from `src`, and the references to them.
+// References are resolved using the same scoping rules as the evaluator,
+// except that names that are referenced in a function before they're declared
+// in an enclosing scope are resolved to the later declaration, because the
+// function may be called after the declaration.
+pub fn analyse(src: &str, stmts: &Block) -> Analysis {
+    let mut analyser = Analyser{
+        src,
+        analysis: Analysis::default(),
+        scopes: vec![],
+    };
+
+    analyser.push_scope(None);
+    analyser.stmts(stmts);
+    analyser.pop_scope();
+
+    let mut analysis = analyser.analysis;
+    analysis.refs.sort_unstable();
+    analysis.unresolved.sort_unstable();
+
+    analysis
+}
+
+struct Analyser<'a> {
+    src: &'a str,
+    analysis: Analysis,
+    scopes: Vec<Scope>,
+}
+
+struct Scope {
+    range: Option<Range>,
+    // `symbols` contains the indices of the symbols that are declared in this
+    // scope, in the order that they're declared.
+    symbols: Vec<usize>,
+    // `pending` contains the names that were referenced in this scope before
+    // they were declared in any enclosing scope, which are resolved when the
+    // scope is popped.
+    pending: Vec<(Range, String)>,
+}
+
+impl Analyser<'_> {
+    fn push_scope(&mut self, range: Option<Range>) {
+        self.scopes.push(Scope{range, symbols: vec![], pending: vec![]});
+    }
+
+    fn pop_scope(&mut self) {
+        let scope = self.scopes.pop()
+            .expect("`Analyser` scope stack shouldn't be empty");
+
+        for (range, name) in scope.pending {
+            if let Some(i) = self.find(&scope.symbols, &name) {
+                self.analysis.refs.push((range, i));
+            } else if let Some(parent) = self.scopes.last_mut() {
+                parent.pending.push((range, name));
+            } else {
+                self.analysis.unresolved.push((range, name));
+            }
+        }
+    }
+
+    fn find(&self, symbols: &[usize], name: &str) -> Option<usize> {
+        symbols
+            .iter()
+            .rev()
+            .find(|i| self.analysis.symbols[**i].name == name)
+            .copied()
+    }
+
+    fn declare(
+        &mut self,
+        name: &str,
+        kind: SymbolKind,
+        range: Range,
+        detail: String,
+    ) {
+        if name == "_" {
+            return;
+        }
+
+        let scope = self.scopes.last_mut()
+            .expect("`Analyser` scope stack shouldn't be empty");
+
+        scope.symbols.push(self.analysis.symbols.len());
+        self.analysis.symbols.push(Symbol{
+            name: name.to_string(),
+            kind,
+            range,
+            scope: scope.range,
+            detail,
+        });
+    }
+
+    fn reference(&mut self, name: &str, range: Range) {
+        for scope in self.scopes.iter().rev() {
+            if let Some(i) = self.find(&scope.symbols, name) {
+                self.analysis.refs.push((range, i));
+
+                return;
+            }
+        }
+
+        self.scopes.last_mut()
+            .expect("`Analyser` scope stack shouldn't be empty")
+            .pending
+            .push((range, name.to_string()));
+    }
+
+    fn stmts(&mut self, stmts: &Block) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn block(&mut self, stmts: &Block) {
+        self.push_scope(block_range(stmts));
+        self.stmts(stmts);
+        self.pop_scope();
+    }
+
+    fn stmt(&mut self, (raw_stmt, span): &Stmt) {
+        match raw_stmt {
+            RawStmt::Block{block} => {
+                self.block(block);
+            },
+            RawStmt::Expr{expr} | RawStmt::Return{expr, ..} => {
+                self.expr(expr);
+            },
+            RawStmt::Declare{lhs, rhs} => {
+                self.expr(rhs);
+                self.binding(lhs, SymbolKind::Variable);
+            },
+            RawStmt::Assign{lhs, rhs} | RawStmt::OpAssign{lhs, rhs, ..} => {
+                self.expr(rhs);
+                self.expr(lhs);
+            },
+            RawStmt::If{branches, else_stmts} => {
+                for branch in branches {
+                    self.expr(&branch.cond);
+                    self.block(&branch.stmts);
+                }
+                if let Some(stmts) = else_stmts {
+                    self.block(stmts);
+                }
+            },
+            RawStmt::While{cond, stmts} => {
+                self.expr(cond);
+                self.block(stmts);
+            },
+            RawStmt::For{lhs, iter, stmts} => {
+                self.expr(iter);
+
+                self.push_scope(Some(span_range(span)));
+                self.binding(lhs, SymbolKind::Variable);
+                self.stmts(stmts);
+                self.pop_scope();
+            },
+            RawStmt::Func{name: (name, loc), args, stmts, ..} => {
+                let (line, col) = *loc;
+                let end = (line, col + name.chars().count() - 1);
+                let detail =
+                    format!("fn {name}({})", self.params(args).join(", "));
+
+                // We declare the function before analysing its body so that
+                // recursive calls refer to the function.
+                self.declare(name, SymbolKind::Function, (*loc, end), detail);
+                self.func(args, stmts, span);
+            },
+            RawStmt::Break{..} | RawStmt::Continue{..} | RawStmt::Error => {
+            },
+        }
+    }
+
+    fn func(&mut self, args: &[Expr], stmts: &Block, span: &Span) {
+        self.push_scope(Some(span_range(span)));
+        for arg in args {
+            self.binding(arg, SymbolKind::Parameter);
+        }
+        self.stmts(stmts);
+        self.pop_scope();
+    }
+
+    // `params` returns the source of each of `args`.
+    fn params(&self, args: &[Expr]) -> Vec<String> {
+        args.iter()
+            .map(|(_, span)| {
+                self.src[span.start.offset..span.end.offset].to_string()
+            })
+            .collect()
+    }
+
+    // `binding` declares the names that are bound by `lhs`.
+    fn binding(&mut self, lhs: &Expr, kind: SymbolKind) {
+        let (raw_expr, span) = lhs;
+        match raw_expr {
+            RawExpr::Var{name} => {
+                let detail =
+                    match kind {
+                        SymbolKind::Parameter => format!("(parameter) {name}"),
+                        _ => format!("(variable) {name}"),
+                    };
+
+                self.declare(name, kind, span_range(span), detail);
+            },
+            RawExpr::List{items, ..} => {
+                for ListItem{expr, ..} in items {
+                    self.binding(expr, kind);
+                }
+            },
+            RawExpr::Object{props} => {
+                for prop in props {
+                    match prop {
+                        PropItem::Pair{name, value} => {
+                            self.expr(name);
+                            self.binding(value, kind);
+                        },
+                        PropItem::Single{expr, ..} => {
+                            self.binding(expr, kind);
+                        },
+                    }
+                }
+            },
+            _ => {
+                self.expr(lhs);
+            },
+        }
+    }
+
+    fn expr(&mut self, (raw_expr, span): &Expr) {
+        match raw_expr {
+            RawExpr::Null | RawExpr::Bool{..} | RawExpr::Int{..} => {
+            },
+            RawExpr::Str{interpolation_slots, ..} => {
+                for slot in interpolation_slots.iter().flatten() {
+                    self.expr(&slot.expr);
+                }
+            },
+            RawExpr::Var{name} => {
+                self.reference(name, span_range(span));
+            },
+            RawExpr::BinaryOp{lhs, rhs, ..} => {
+                self.expr(lhs);
+                self.expr(rhs);
+            },
+            RawExpr::Range{start, end} => {
+                self.expr(start);
+                self.expr(end);
+            },
+            RawExpr::List{items, ..} => {
+                for ListItem{expr, ..} in items {
+                    self.expr(expr);
+                }
+            },
+            RawExpr::Index{expr, location} => {
+                self.expr(expr);
+                self.expr(location);
+            },
+            RawExpr::RangeIndex{expr, start, end} => {
+                self.expr(expr);
+                for e in start.iter().chain(end.iter()) {
+                    self.expr(e);
+                }
+            },
+            RawExpr::Object{props} => {
+                for prop in props {
+                    match prop {
+                        PropItem::Pair{name, value} => {
+                            self.expr(name);
+                            self.expr(value);
+                        },
+                        PropItem::Single{expr, ..} => {
+                            self.expr(expr);
+                        },
+                    }
+                }
+            },
+            RawExpr::Prop{expr, ..} => {
+                self.expr(expr);
+            },
+            RawExpr::Func{args, stmts, ..} => {
+                self.func(args, stmts, span);
+            },
+            RawExpr::Call{func, args} => {
+                self.expr(func);
+                for ListItem{expr, ..} in args {
+                    self.expr(expr);
+                }
+            },
+        }
+    }
+}
+
+fn span_range(span: &Span) -> Range {
+    (span.start.loc(), span.end.loc())
+}
+
+// `block_range` returns the range of the statements in a block, or `None` if
+// the block is empty.
+fn block_range(stmts: &Block) -> Option<Range> {
+    match (stmts.first(), stmts.last()) {
+        (Some((_, first)), Some((_, last))) => {
+            Some((first.start.loc(), last.end.loc()))
+        },
+        _ => {
+            None
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::ast::Prog;
+    use crate::lexer::Lexer;
+    use crate::parser::ProgParser;
+    use crate::syntax_error;
+
+    const SRC: &str = "\
+x := 1;
+fn f(a) {
+    x := a;
+    return x;
+}
+print(f(x));
+";
+
+    fn analyse_src(src: &str) -> Analysis {
+        let tokens = syntax_error::tokens(Lexer::new(src));
+        let mut errors = vec![];
+        let Prog::Body{stmts} =
+            ProgParser::new().parse(&mut errors, tokens)
+                .expect("couldn't parse source");
+        assert!(errors.is_empty(), "unexpected syntax errors: {errors:?}");
+
+        analyse(src, &stmts)
+    }
+
+    fn name_at(analysis: &Analysis, loc: Location) -> Option<(Range, &str)> {
+        analysis.symbol_at(loc)
+            .map(|i| &analysis.symbols[i])
+            .map(|symbol| (symbol.range, symbol.detail.as_str()))
+    }
+
+    #[test]
+    fn test_symbol_at() {
+        let analysis = analyse_src(SRC);
+
+        let tests: &[(Location, Option<(Range, &str)>)] = &[
+            ((1, 1), Some((((1, 1), (1, 1)), "(variable) x"))),
+            ((2, 4), Some((((2, 4), (2, 4)), "fn f(a)"))),
+            ((3, 10), Some((((2, 6), (2, 6)), "(parameter) a"))),
+            ((4, 12), Some((((3, 5), (3, 5)), "(variable) x"))),
+            ((6, 7), Some((((2, 4), (2, 4)), "fn f(a)"))),
+            ((6, 9), Some((((1, 1), (1, 1)), "(variable) x"))),
+            ((6, 1), None),
+        ];
+
+        for (loc, exp) in tests {
+            assert_eq!(name_at(&analysis, *loc), *exp, "at {loc:?}");
+        }
+        assert_eq!(analysis.unresolved_at((6, 3)), Some("print"));
+    }
+
+    #[test]
+    fn test_references() {
+        let analysis = analyse_src(SRC);
+
+        let outer_x =
+            analysis.symbol_at((1, 1))
+                .expect("expected symbol at [1:1]");
+        let inner_x =
+            analysis.symbol_at((3, 5))
+                .expect("expected symbol at [3:5]");
+
+        assert_eq!(analysis.references(outer_x), vec![((6, 9), (6, 9))]);
+        assert_eq!(analysis.references(inner_x), vec![((4, 12), (4, 12))]);
+    }
+
+    #[test]
+    fn test_visible_at() {
+        let analysis = analyse_src(SRC);
+
+        let tests: &[(Location, &[&str])] = &[
+            ((1, 1), &[]),
+            ((3, 5), &["(variable) x", "fn f(a)", "(parameter) a"]),
+            ((4, 5), &["(variable) x", "fn f(a)", "(parameter) a"]),
+            ((6, 1), &["(variable) x", "fn f(a)"]),
+        ];
+
+        for (loc, exp) in tests {
+            let act: Vec<&str> =
+                analysis.visible_at(*loc)
+                    .into_iter()
+                    .map(|i| analysis.symbols[i].detail.as_str())
+                    .collect();
+
+            assert_eq!(act, *exp, "at {loc:?}");
+        }
+    }
+}
@@ -0,0 +1,834 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::io::Write;
+
+mod analysis;
+
+use self::analysis::Analysis;
+use self::analysis::Range;
+use self::analysis::SymbolKind;
+use crate::ast::Location;
+use crate::ast::Prog;
+use crate::builtin_funcs;
+use crate::eval_builtin_funcs;
+use crate::builtins::type_functions;
+use crate::diagnostic::Diagnostic;
+use crate::json;
+use crate::json::Json;
+use crate::lexer::Lexer;
+use crate::parse_error_to_diagnostic;
+use crate::parser::ProgParser;
+use crate::relocate_eof;
+use crate::rpc;
+use crate::rpc::Error;
+use crate::syntax_error;
+
+// These error codes are defined by JSON-RPC and the Language Server Protocol.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+// These kinds are defined by the Language Server Protocol.
+const COMPLETION_KIND_METHOD: i64 = 2;
+const COMPLETION_KIND_FUNCTION: i64 = 3;
+const COMPLETION_KIND_VARIABLE: i64 = 6;
+const DIAGNOSTIC_SEVERITY_ERROR: i64 = 1;
+const TEXT_DOCUMENT_SYNC_FULL: i64 = 1;
+
+// `serve` runs a language server that reads messages from `r` and writes
+// messages to `w`, until it receives an `exit` notification or `r` is closed.
+// It returns the exit code that the process should exit with, as defined by
+// the Language Server Protocol.
+pub fn serve<R: BufRead, W: Write>(r: &mut R, w: &mut W)
+    -> Result<i32, Error>
+{
+    let mut server = Server::default();
+
+    while let Some(content) = rpc::read_message(r)? {
+        let replies =
+            match json::decode(&content) {
+                Ok(msg) => {
+                    server.handle(&msg)
+                },
+                Err(e) => {
+                    let msg =
+                        format!("{}:{}: {}", e.line, e.col, e.msg);
+
+                    vec![new_error_response(Json::Null, PARSE_ERROR, &msg)]
+                },
+            };
+
+        for reply in replies {
+            rpc::write_message(w, &reply)?;
+        }
+
+        if let Some(code) = server.exit_code {
+            return Ok(code);
+        }
+    }
+
+    Ok(1)
+}
+
+#[derive(Default)]
+struct Server {
+    initialized: bool,
+    shutdown: bool,
+    // `exit_code` is set when an `exit` notification is received.
+    exit_code: Option<i32>,
+    docs: HashMap<String, Document>,
+}
+
+struct Document {
+    text: String,
+    analysis: Analysis,
+}
+
+impl Server {
+    // `handle` handles `msg`, and returns the messages that should be sent in
+    // reply.
+    fn handle(&mut self, msg: &Json) -> Vec<Json> {
+        let method = msg.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = msg.get("params").unwrap_or(&Json::Null);
+
+        // Requests have an `id`, but notifications don't, and we never send
+        // replies to notifications.
+        let id =
+            match msg.get("id") {
+                Some(id) => id.clone(),
+                None => return self.handle_notification(method, params),
+            };
+
+        if !self.initialized && method != "initialize" {
+            return vec![new_error_response(
+                id,
+                SERVER_NOT_INITIALIZED,
+                "the server hasn't been initialised",
+            )];
+        }
+
+        let result =
+            match method {
+                "initialize" => {
+                    self.initialized = true;
+
+                    Some(capabilities())
+                },
+                "shutdown" => {
+                    self.shutdown = true;
+
+                    Some(Json::Null)
+                },
+                "textDocument/definition" => {
+                    self.definition(params)
+                },
+                "textDocument/references" => {
+                    self.references(params)
+                },
+                "textDocument/completion" => {
+                    self.completion(params)
+                },
+                "textDocument/hover" => {
+                    self.hover(params)
+                },
+                _ => {
+                    let msg = format!("unsupported method '{method}'");
+
+                    return vec![
+                        new_error_response(id, METHOD_NOT_FOUND, &msg),
+                    ];
+                },
+            };
+
+        match result {
+            Some(result) => {
+                vec![json::new_object(vec![
+                    ("jsonrpc", Json::Str("2.0".to_string())),
+                    ("id", id),
+                    ("result", result),
+                ])]
+            },
+            None => {
+                vec![new_error_response(id, INVALID_PARAMS, "invalid params")]
+            },
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Json)
+        -> Vec<Json>
+    {
+        let uri =
+            params.get("textDocument")
+                .and_then(|doc| doc.get("uri"))
+                .and_then(Json::as_str)
+                .unwrap_or("")
+                .to_string();
+
+        match method {
+            "exit" => {
+                self.exit_code = Some(i32::from(!self.shutdown));
+
+                vec![]
+            },
+            "textDocument/didOpen" => {
+                let maybe_text =
+                    params.get("textDocument")
+                        .and_then(|doc| doc.get("text"))
+                        .and_then(Json::as_str);
+
+                match maybe_text {
+                    Some(text) => self.update(uri, text.to_string()),
+                    None => vec![],
+                }
+            },
+            "textDocument/didChange" => {
+                // We only support full document synchronisation, so the last
+                // change contains the whole document.
+                let maybe_text =
+                    match params.get("contentChanges") {
+                        Some(Json::List(changes)) => {
+                            changes.last()
+                                .and_then(|change| change.get("text"))
+                                .and_then(Json::as_str)
+                        },
+                        _ => {
+                            None
+                        },
+                    };
+
+                match maybe_text {
+                    Some(text) => self.update(uri, text.to_string()),
+                    None => vec![],
+                }
+            },
+            "textDocument/didClose" => {
+                self.docs.remove(&uri);
+
+                vec![new_diagnostics_notification(&uri, vec![])]
+            },
+            _ => {
+                vec![]
+            },
+        }
+    }
+
+    // `update` sets the text of the document at `uri` to `text`, and returns
+    // a notification of the syntax errors in the document.
+    fn update(&mut self, uri: String, text: String) -> Vec<Json> {
+        let tokens = syntax_error::tokens(Lexer::new(&text));
+        let mut recovered_errs = vec![];
+        let result = ProgParser::new().parse(&mut recovered_errs, tokens);
+
+        let mut errs: Vec<_> =
+            recovered_errs
+                .into_iter()
+                .map(|recovery| recovery.error)
+                .collect();
+
+        let analysis =
+            match result {
+                Ok(Prog::Body{stmts}) => {
+                    analysis::analyse(&text, &stmts)
+                },
+                // We keep the last analysis of the document if it can't be
+                // parsed, so that navigation still works while the user is
+                // partway through an edit.
+                Err(e) => {
+                    errs.push(e);
+
+                    self.docs.remove(&uri)
+                        .map_or_else(Analysis::default, |doc| doc.analysis)
+                },
+            };
+
+        let diagnostics =
+            errs.into_iter()
+                .map(|e| relocate_eof(&text, e))
+                .map(parse_error_to_diagnostic)
+                .map(|d| diagnostic_to_json(&text, &d))
+                .collect();
+
+        let notification = new_diagnostics_notification(&uri, diagnostics);
+        self.docs.insert(uri, Document{text, analysis});
+
+        vec![notification]
+    }
+
+    // `lookup` returns the document and location that are referred to by the
+    // `textDocument` and `position` of `params`.
+    fn lookup<'a>(&'a self, params: &'a Json)
+        -> Option<(&'a str, &'a Document, Location)>
+    {
+        let uri =
+            params.get("textDocument")?
+                .get("uri")?
+                .as_str()?;
+        let doc = self.docs.get(uri)?;
+
+        let pos = params.get("position")?;
+        let line = pos.get("line")?.as_int()?;
+        let character = pos.get("character")?.as_int()?;
+        let loc = to_location(&doc.text, line, character);
+
+        Some((uri, doc, loc))
+    }
+
+    fn definition(&self, params: &Json) -> Option<Json> {
+        let (uri, doc, loc) = self.lookup(params)?;
+
+        let result =
+            match doc.analysis.symbol_at(loc) {
+                Some(i) => {
+                    let range = doc.analysis.symbols[i].range;
+
+                    new_location(uri, &doc.text, range)
+                },
+                None => {
+                    Json::Null
+                },
+            };
+
+        Some(result)
+    }
+
+    fn references(&self, params: &Json) -> Option<Json> {
+        let (uri, doc, loc) = self.lookup(params)?;
+
+        let include_decl =
+            params.get("context")
+                .and_then(|ctx| ctx.get("includeDeclaration"))
+                .is_some_and(|v| *v == Json::Bool(true));
+
+        let mut ranges = vec![];
+        if let Some(i) = doc.analysis.symbol_at(loc) {
+            if include_decl {
+                ranges.push(doc.analysis.symbols[i].range);
+            }
+            ranges.extend(doc.analysis.references(i));
+        }
+
+        let locations =
+            ranges.into_iter()
+                .map(|range| new_location(uri, &doc.text, range))
+                .collect();
+
+        Some(Json::List(locations))
+    }
+
+    fn completion(&self, params: &Json) -> Option<Json> {
+        let (_, doc, loc) = self.lookup(params)?;
+
+        let items =
+            if is_after_arrow(&doc.text, loc) {
+                type_function_completions()
+            } else {
+                name_completions(&doc.analysis, loc)
+            };
+
+        Some(Json::List(items))
+    }
+
+    fn hover(&self, params: &Json) -> Option<Json> {
+        let (_, doc, loc) = self.lookup(params)?;
+
+        let maybe_hover =
+            if let Some((word, range)) = word_at(&doc.text, loc) {
+                if let Some(i) = doc.analysis.symbol_at(loc) {
+                    Some((doc.analysis.symbols[i].detail.clone(), range))
+                } else if is_after_arrow(&doc.text, range.0) {
+                    type_function_detail(&word)
+                        .map(|detail| (detail, range))
+                } else if doc.analysis.unresolved_at(loc).is_some()
+                    && is_builtin(&word)
+                {
+                    Some((format!("(builtin function) {word}"), range))
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+
+        let result =
+            match maybe_hover {
+                Some((detail, range)) => {
+                    let value = format!("```seed\n{detail}\n```");
+                    let contents = json::new_object(vec![
+                        ("kind", Json::Str("markdown".to_string())),
+                        ("value", Json::Str(value)),
+                    ]);
+
+                    json::new_object(vec![
+                        ("contents", contents),
+                        ("range", new_range(&doc.text, range)),
+                    ])
+                },
+                None => {
+                    Json::Null
+                },
+            };
+
+        Some(result)
+    }
+}
+
+fn capabilities() -> Json {
+    let completion = json::new_object(vec![
+        ("triggerCharacters", Json::List(vec![Json::Str(">".to_string())])),
+    ]);
+    let capabilities = json::new_object(vec![
+        ("textDocumentSync", Json::Int(TEXT_DOCUMENT_SYNC_FULL)),
+        ("definitionProvider", Json::Bool(true)),
+        ("referencesProvider", Json::Bool(true)),
+        ("completionProvider", completion),
+        ("hoverProvider", Json::Bool(true)),
+    ]);
+    let server_info = json::new_object(vec![
+        ("name", Json::Str("seed".to_string())),
+    ]);
+
+    json::new_object(vec![
+        ("capabilities", capabilities),
+        ("serverInfo", server_info),
+    ])
+}
+
+fn name_completions(analysis: &Analysis, loc: Location) -> Vec<Json> {
+    let mut items = vec![];
+    for i in analysis.visible_at(loc) {
+        let symbol = &analysis.symbols[i];
+        let kind =
+            match symbol.kind {
+                SymbolKind::Function => COMPLETION_KIND_FUNCTION,
+                SymbolKind::Variable | SymbolKind::Parameter => {
+                    COMPLETION_KIND_VARIABLE
+                },
+            };
+
+        items.push(new_completion(&symbol.name, kind, &symbol.detail));
+    }
+
+    let builtin_names =
+        builtin_funcs()
+            .into_iter()
+            .map(|(name, _)| name)
+            .chain(eval_builtin_funcs().into_iter().map(|(name, _)| name));
+    for name in builtin_names {
+        let detail = format!("(builtin function) {name}");
+
+        items.push(new_completion(name, COMPLETION_KIND_FUNCTION, &detail));
+    }
+
+    items
+}
+
+fn type_function_completions() -> Vec<Json> {
+    type_function_names()
+        .into_iter()
+        .map(|(name, types)| {
+            let detail = render_type_function(&name, &types);
+
+            new_completion(&name, COMPLETION_KIND_METHOD, &detail)
+        })
+        .collect()
+}
+
+fn type_function_detail(name: &str) -> Option<String> {
+    type_function_names()
+        .get(name)
+        .map(|types| render_type_function(name, types))
+}
+
+fn render_type_function(name: &str, types: &[&str]) -> String {
+    format!("(type function) {name}: {}", types.join(", "))
+}
+
+// `type_function_names` returns the names of the type functions, along with
+// the types that define each of them.
+fn type_function_names() -> BTreeMap<String, Vec<&'static str>> {
+    let funcs = type_functions::type_functions();
+    let types = [
+        ("bool", &funcs.bools),
+        ("int", &funcs.ints),
+        ("string", &funcs.strs),
+        ("list", &funcs.lists),
+        ("object", &funcs.objects),
+        ("func", &funcs.funcs),
+        ("iter", &funcs.iters),
+    ];
+
+    let mut names: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for (type_name, funcs) in types {
+        let funcs = funcs.lock()
+            .expect("type functions shouldn't be poisoned");
+        for name in funcs.keys() {
+            names.entry(name.clone()).or_default().push(type_name);
+        }
+    }
+
+    names
+}
+
+fn is_builtin(name: &str) -> bool {
+    builtin_funcs().iter().any(|(n, _)| *n == name)
+        || eval_builtin_funcs().iter().any(|(n, _)| *n == name)
+}
+
+// `is_after_arrow` returns `true` if `loc` is directly after `->`, ignoring
+// any part of a name that has already been typed.
+fn is_after_arrow(text: &str, (line, col): Location) -> bool {
+    let line_text = text.lines().nth(line - 1).unwrap_or("");
+    let before: String = line_text.chars().take(col - 1).collect();
+
+    before
+        .trim_end_matches(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        .ends_with("->")
+}
+
+// `word_at` returns the name at `loc`, along with its range.
+fn word_at(text: &str, (line, col): Location) -> Option<(String, Range)> {
+    let chars: Vec<char> = text.lines().nth(line - 1)?.chars().collect();
+    let is_word_char = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+
+    let i = col - 1;
+    let start =
+        chars[..i.min(chars.len())]
+            .iter()
+            .rposition(|c| !is_word_char(c))
+            .map_or(0, |j| j + 1);
+    let end =
+        chars[i.min(chars.len())..]
+            .iter()
+            .position(|c| !is_word_char(c))
+            .map_or(chars.len(), |j| i + j);
+    if start >= end {
+        return None;
+    }
+
+    let word = chars[start..end].iter().collect();
+
+    Some((word, ((line, start + 1), (line, end))))
+}
+
+fn new_completion(label: &str, kind: i64, detail: &str) -> Json {
+    json::new_object(vec![
+        ("label", Json::Str(label.to_string())),
+        ("kind", Json::Int(kind)),
+        ("detail", Json::Str(detail.to_string())),
+    ])
+}
+
+fn new_error_response(id: Json, code: i64, msg: &str) -> Json {
+    let error = json::new_object(vec![
+        ("code", Json::Int(code)),
+        ("message", Json::Str(msg.to_string())),
+    ]);
+
+    json::new_object(vec![
+        ("jsonrpc", Json::Str("2.0".to_string())),
+        ("id", id),
+        ("error", error),
+    ])
+}
+
+fn new_diagnostics_notification(uri: &str, diagnostics: Vec<Json>) -> Json {
+    let params = json::new_object(vec![
+        ("uri", Json::Str(uri.to_string())),
+        ("diagnostics", Json::List(diagnostics)),
+    ]);
+
+    json::new_object(vec![
+        ("jsonrpc", Json::Str("2.0".to_string())),
+        ("method", Json::Str("textDocument/publishDiagnostics".to_string())),
+        ("params", params),
+    ])
+}
+
+fn diagnostic_to_json(text: &str, d: &Diagnostic) -> Json {
+    let range = d.span.unwrap_or(((1, 1), (1, 1)));
+
+    json::new_object(vec![
+        ("range", new_range(text, range)),
+        ("severity", Json::Int(DIAGNOSTIC_SEVERITY_ERROR)),
+        ("code", Json::Str(d.code.to_string())),
+        ("source", Json::Str("seed".to_string())),
+        ("message", Json::Str(d.msg.clone())),
+    ])
+}
+
+fn new_location(uri: &str, text: &str, range: Range) -> Json {
+    json::new_object(vec![
+        ("uri", Json::Str(uri.to_string())),
+        ("range", new_range(text, range)),
+    ])
+}
+
+// `new_range` returns `range` as an LSP range. LSP ranges are exclusive of
+// their end, unlike `Range`.
+fn new_range(text: &str, (start, (end_line, end_col)): Range) -> Json {
+    json::new_object(vec![
+        ("start", new_position(text, start)),
+        ("end", new_position(text, (end_line, end_col + 1))),
+    ])
+}
+
+// `new_position` returns `loc` as an LSP position. LSP lines and characters
+// start at 0, and characters are counted in UTF-16 code units. Lines and
+// columns are clamped to 1, so that locations that aren't in the source, such
+// as those of builtins, can't underflow.
+fn new_position(text: &str, (line, col): Location) -> Json {
+    let line = line.saturating_sub(1);
+    let col = col.saturating_sub(1);
+
+    let line_text = text.lines().nth(line).unwrap_or("");
+    let character: usize =
+        line_text.chars()
+            .take(col)
+            .map(char::len_utf16)
+            .sum::<usize>()
+            + col.saturating_sub(line_text.chars().count());
+
+    json::new_object(vec![
+        ("line", Json::Int(to_i64(line))),
+        ("character", Json::Int(to_i64(character))),
+    ])
+}
+
+// `to_location` returns the `Location` of the LSP position at `line` and
+// `character`.
+fn to_location(text: &str, line: i64, character: i64) -> Location {
+    let line = usize::try_from(line).unwrap_or(0);
+    let character = usize::try_from(character).unwrap_or(0);
+
+    let line_text = text.lines().nth(line).unwrap_or("");
+    let mut units = 0;
+    let mut col = 1;
+    for c in line_text.chars() {
+        if units >= character {
+            break;
+        }
+        units += c.len_utf16();
+        col += 1;
+    }
+
+    (line + 1, col + character.saturating_sub(units))
+}
+
+fn to_i64(n: usize) -> i64 {
+    i64::try_from(n).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Cursor;
+
+    const URI: &str = "file:///test.sd";
+
+    fn decode(src: &str) -> Json {
+        json::decode(src)
+            .expect("couldn't decode message")
+    }
+
+    fn request(id: i64, method: &str, params: &str) -> Json {
+        decode(&format!(
+            "{{\"jsonrpc\": \"2.0\", \"id\": {id}, \"method\": \"{method}\", \
+             \"params\": {params}}}",
+        ))
+    }
+
+    fn notification(method: &str, params: &str) -> Json {
+        decode(&format!(
+            "{{\"jsonrpc\": \"2.0\", \"method\": \"{method}\", \
+             \"params\": {params}}}",
+        ))
+    }
+
+    fn position_params(line: usize, character: usize) -> String {
+        format!(
+            "{{\"textDocument\": {{\"uri\": \"{URI}\"}}, \
+             \"position\": {{\"line\": {line}, \"character\": {character}}}, \
+             \"context\": {{\"includeDeclaration\": true}}}}",
+        )
+    }
+
+    fn open(server: &mut Server, text: &str) -> Vec<String> {
+        server.handle(&request(1, "initialize", "{}"));
+
+        let text = json::encode(&Json::Str(text.to_string()), None);
+        let params = format!(
+            "{{\"textDocument\": {{\"uri\": \"{URI}\", \"text\": {text}}}}}",
+        );
+
+        server.handle(&notification("textDocument/didOpen", &params))
+            .iter()
+            .map(|reply| json::encode(reply, None))
+            .collect()
+    }
+
+    fn result(server: &mut Server, method: &str, params: &str) -> String {
+        let replies = server.handle(&request(2, method, params));
+        assert_eq!(replies.len(), 1, "expected one reply: {replies:?}");
+
+        let result =
+            replies[0].get("result")
+                .expect("expected result");
+
+        json::encode(result, None)
+    }
+
+    #[test]
+    fn test_serve_exit_code() {
+        let shutdown = request(1, "shutdown", "null");
+        let exit = notification("exit", "null");
+        let tests: &[(&[&Json], i32)] = &[
+            (&[&shutdown, &exit], 0),
+            (&[&exit], 1),
+            (&[], 1),
+        ];
+
+        for (msgs, exp) in tests {
+            let mut buf = vec![];
+            rpc::write_message(&mut buf, &request(0, "initialize", "{}"))
+                .expect("couldn't write message");
+            for msg in *msgs {
+                rpc::write_message(&mut buf, msg)
+                    .expect("couldn't write message");
+            }
+
+            let act =
+                serve(&mut Cursor::new(buf), &mut vec![])
+                    .expect("couldn't serve");
+
+            assert_eq!(act, *exp, "incorrect exit code for {msgs:?}");
+        }
+    }
+
+    #[test]
+    fn test_publishes_diagnostics() {
+        let tests = &[
+            (
+                "x := 1;\ny := ;\nz := 2;\n",
+                "{\"code\":\"E0022\",\
+                 \"message\":\"unexpected ':='; expected \
+                 \\\"stmt_end\\\" or \\\"..\\\"\",\
+                 \"range\":{\"end\":{\"character\":4,\"line\":2},\
+                 \"start\":{\"character\":2,\"line\":2}},\
+                 \"severity\":1,\"source\":\"seed\"}",
+            ),
+            (
+                "x := (\n",
+                "{\"code\":\"E0021\",\
+                 \"message\":\"unexpected EOF; expected \\\"identifier\\\", \
+                 \\\"int_literal\\\", \\\"str_literal\\\", \
+                 \\\"interp_str_literal\\\", \\\"false\\\", \\\"fn\\\", \
+                 \\\"null\\\", \\\"true\\\", \\\"{\\\", \\\"[\\\", \
+                 \\\"(\\\" or \\\"-\\\"\",\
+                 \"range\":{\"end\":{\"character\":1,\"line\":1},\
+                 \"start\":{\"character\":0,\"line\":1}},\
+                 \"severity\":1,\"source\":\"seed\"}",
+            ),
+        ];
+
+        for (src, exp_diagnostic) in tests {
+            let mut server = Server::default();
+
+            let act = open(&mut server, src);
+
+            assert_eq!(
+                act,
+                vec![format!(
+                    "{{\"jsonrpc\":\"2.0\",\
+                     \"method\":\"textDocument/publishDiagnostics\",\
+                     \"params\":{{\"diagnostics\":[{exp_diagnostic}],\
+                     \"uri\":\"file:///test.sd\"}}}}",
+                )],
+                "incorrect diagnostics for {src:?}",
+            );
+        }
+    }
+
+    #[test]
+    fn test_requests() {
+        let mut server = Server::default();
+        open(
+            &mut server,
+            "x := 1;\nfn f(a) {\n    return a + x;\n}\nf(x)->type();\n",
+        );
+
+        let tests = &[
+            (
+                "textDocument/definition",
+                position_params(2, 15),
+                "{\"range\":{\"end\":{\"character\":1,\"line\":0},\
+                 \"start\":{\"character\":0,\"line\":0}},\
+                 \"uri\":\"file:///test.sd\"}",
+            ),
+            (
+                "textDocument/definition",
+                position_params(4, 5),
+                "null",
+            ),
+            (
+                "textDocument/references",
+                position_params(1, 5),
+                "[{\"range\":{\"end\":{\"character\":6,\"line\":1},\
+                 \"start\":{\"character\":5,\"line\":1}},\
+                 \"uri\":\"file:///test.sd\"},\
+                 {\"range\":{\"end\":{\"character\":12,\"line\":2},\
+                 \"start\":{\"character\":11,\"line\":2}},\
+                 \"uri\":\"file:///test.sd\"}]",
+            ),
+            (
+                "textDocument/completion",
+                position_params(4, 6),
+                "[{\"detail\":\"(type function) len: string\",\
+                 \"kind\":2,\"label\":\"len\"},\
+                 {\"detail\":\"(type function) type: bool, int, string, \
+                 list, object, func, iter\",\"kind\":2,\"label\":\"type\"}]",
+            ),
+            (
+                "textDocument/hover",
+                position_params(4, 0),
+                "{\"contents\":{\"kind\":\"markdown\",\
+                 \"value\":\"```seed\\nfn f(a)\\n```\"},\
+                 \"range\":{\"end\":{\"character\":1,\"line\":4},\
+                 \"start\":{\"character\":0,\"line\":4}}}",
+            ),
+        ];
+
+        for (method, params, exp) in tests {
+            let act = result(&mut server, method, params);
+
+            assert_eq!(act, *exp, "incorrect result for {method}: {params}");
+        }
+    }
+
+    #[test]
+    fn test_completes_visible_names() {
+        let mut server = Server::default();
+        open(&mut server, "x := 1;\nfn f(a) {\n    \n}\n");
+
+        let act =
+            result(
+                &mut server,
+                "textDocument/completion",
+                &position_params(2, 4),
+            );
+
+        let labels: Vec<&str> =
+            act.split("\"label\":\"")
+                .skip(1)
+                .filter_map(|s| s.split('"').next())
+                .take(4)
+                .collect();
+        assert_eq!(labels, vec!["x", "f", "a", "print"]);
+    }
+}
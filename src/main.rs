@@ -7,9 +7,15 @@ extern crate assert_matches;
 extern crate snafu;
 
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::env;
 use std::fs;
+use std::io;
+use std::io::BufReader;
 use std::io::Error as IoError;
+use std::io::IsTerminal;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process;
@@ -18,25 +24,66 @@ use std::sync::Mutex;
 
 mod ast;
 mod builtins;
+mod coverage;
+mod debug;
+mod diagnostic;
+mod error_codes;
 mod eval;
+mod fmt;
+mod golden;
+mod interpolation;
+mod json;
 mod lexer;
+mod lint;
+mod lsp;
+mod profile;
+mod rpc;
+mod syntax_error;
+mod test_runner;
+mod trace;
 
 use lalrpop_util::ParseError;
 use snafu::ResultExt;
 use snafu::Snafu;
 
+use ast::Prog;
 use ast::RawExpr;
+use builtins::assert as assert_fns;
 use builtins::fns;
+use builtins::fns::Stream;
+use builtins::json as json_fns;
+use builtins::process as process_fns;
+use builtins::stdin as stdin_fns;
 use builtins::type_functions;
+use coverage::Coverage;
+use debug::Debugger;
+use debug::dap;
+use debug::terminal::Terminal;
+use diagnostic::Diagnostic;
+use diagnostic::Frame;
+use diagnostic::Renderer;
+use diagnostic::Severity;
 use eval::builtins::Builtins;
 use eval::EvaluationContext;
 use eval::error::Error as EvalError;
+use eval::error::requested_exit_code;
+use eval::hook::Hook;
+use eval::hook::Hooks;
 use eval::value;
+use eval::value::BuiltinFunc;
+use eval::value::EvalBuiltinFunc;
 use eval::scope::ScopeStack;
 use lexer::Lexer;
 use lexer::LexError;
+use lexer::Location;
+use lexer::Position;
 use lexer::Token;
+use lint::Warning;
+use lint::WarningKind;
 use parser::ProgParser;
+use profile::Profiler;
+use syntax_error::SyntaxError;
+use trace::Tracer;
 
 #[macro_use]
 extern crate lalrpop_util;
@@ -59,54 +106,743 @@ fn main() {
                 process::exit(101);
             },
         };
+    let usage = usage(&prog);
 
-    let raw_cur_rel_script_path =
-        match args.next() {
-            Some(v) => v,
-            None => {
-                eprintln!("usage: {prog} <script-path>");
-                process::exit(102);
+    let mut args = args.peekable();
+    let mut cmd =
+        match args.peek().map(String::as_str) {
+            Some("explain") => {
+                args.next();
+                match (args.next(), args.next()) {
+                    (Some(code), None) => explain(&code.to_uppercase()),
+                    _ => exit_with_usage(&usage),
+                }
+            },
+            Some("lsp") => {
+                args.next();
+                match args.next() {
+                    Some(_) => exit_with_usage(&usage),
+                    None => serve_lsp(),
+                }
+            },
+            Some("check") => {
+                args.next();
+                Command::Check
+            },
+            Some("fmt") => {
+                args.next();
+                Command::Fmt{check: false}
+            },
+            Some("debug") => {
+                args.next();
+                Command::Debug{dap: false}
+            },
+            Some("test") => {
+                args.next();
+                Command::Test
+            },
+            Some("golden") => {
+                args.next();
+                Command::Golden{bless: false}
+            },
+            _ => {
+                Command::Run
             },
         };
 
-    let cur_rel_script_path = Path::new(&raw_cur_rel_script_path);
+    let (opts, maybe_raw_path, script_args) =
+        parse_args(&mut cmd, args, &usage);
 
-    if let Err(e) = run(cur_rel_script_path) {
-        let msg =
-            match e {
-                Error::GetCurrentDirFailed{source} => {
-                    format!(" couldn't get current directory: {source}")
-                },
-                Error::ReadScriptFailed{path, source} => {
-                    let p = path.to_string_lossy();
+    if let Command::Test = cmd {
+        let dir = maybe_raw_path.unwrap_or_else(|| ".".to_string());
 
-                    format!(" couldn't read script at '{p}': {source}")
-                },
-                Error::ParseFailed{src} => {
-                    let ((ln, ch), msg) = render_parse_error(src);
+        run_tests(Path::new(&dir), &opts);
+    }
+
+    let raw_path =
+        match maybe_raw_path {
+            Some(v) => v,
+            None => exit_with_usage(&usage),
+        };
+
+    match cmd {
+        Command::Run => {
+            run_script(&raw_path, &script_args, &opts);
+        },
+        Command::Check => {
+            run_check(&raw_path, &opts);
+        },
+        Command::Fmt{check} => {
+            run_fmt(&raw_path, &opts, check);
+        },
+        Command::Debug{dap} => {
+            run_debug(&raw_path, &script_args, &opts, dap);
+        },
+        Command::Test => {
+            // `Test` was handled above, because it doesn't require a path.
+        },
+        Command::Golden{bless} => {
+            run_golden_tests(Path::new(&raw_path), bless);
+        },
+    }
+}
+
+// `usage` returns the usage message for the program, which was invoked as
+// `prog`.
+fn usage(prog: &str) -> String {
+    format!(
+        "usage: {prog} [<options>] <script-path> [<arg>...]\n       \
+         {prog} check [<options>] <script-path>\n       \
+         {prog} debug [--dap] [<options>] <script-path> [<arg>...]\n       \
+         {prog} fmt [--check] <script-path>\n       \
+         {prog} test [<options>] [<dir>]\n       \
+         {prog} golden [--bless] <dir>\n       \
+         {prog} explain <error-code>\n       \
+         {prog} lsp\n\
+         \n\
+         options:\n    \
+         --error-format=<format>  render errors as 'human' (default) or \
+         'json'\n    \
+         --warnings               enable all warnings\n    \
+         --warn=<warning>         enable a warning\n    \
+         --no-warn=<warning>      disable a warning\n    \
+         --profile[=<format>]     write a profile to stderr as a 'table' \
+         or 'folded'\n    \
+         --coverage=<path>        write an lcov report to <path>\n    \
+         --trace                  write evaluated statements and calls to \
+         stderr\n\
+         \n\
+         'check' and 'debug' only accept --error-format and the warning \
+         options, 'fmt'\n\
+         and 'test' only accept --error-format, and 'golden' accepts no \
+         options.\n\
+         \n\
+         warnings: {}",
+        lint::WarningKind::ALL
+            .iter()
+            .map(|kind| kind.name())
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
+fn exit_with_usage(usage: &str) -> ! {
+    eprintln!("{usage}");
+    process::exit(102);
+}
+
+// `parse_args` parses the arguments that follow the subcommand `cmd`, and
+// returns the options, the script path, if one was given, and the arguments
+// that should be passed to the script. Flags that are specific to `cmd` are
+// set on `cmd`.
+fn parse_args(
+    cmd: &mut Command,
+    args: impl Iterator<Item = String>,
+    usage: &str,
+) -> (Options, Option<String>, Vec<String>) {
+    let mut opts = Options{
+        error_format: ErrorFormat::Human,
+        warnings: HashSet::new(),
+        trace: false,
+        profile: None,
+        coverage_path: None,
+    };
+    let mut maybe_raw_script_path = None;
+    // `script_args` are the arguments after the script path, which are passed
+    // to the script when it's run.
+    let mut script_args = vec![];
+    for arg in args {
+        if let (Command::Run | Command::Debug{..}, Some(_)) =
+                (&cmd, &maybe_raw_script_path) {
+            script_args.push(arg);
+        } else if let (Command::Fmt{check}, "--check") =
+                (&mut *cmd, arg.as_str()) {
+            *check = true;
+        } else if let (Command::Debug{dap}, "--dap") =
+                (&mut *cmd, arg.as_str()) {
+            *dap = true;
+        } else if let (Command::Golden{bless}, "--bless") =
+                (&mut *cmd, arg.as_str()) {
+            *bless = true;
+        } else if arg.starts_with("--") {
+            if let Err(msg) = parse_option(&mut opts, &arg) {
+                eprintln!("{msg}");
+                exit_with_usage(usage);
+            }
+            if !supports_option(cmd, &arg) {
+                eprintln!("'{arg}' isn't supported by this command");
+                exit_with_usage(usage);
+            }
+        } else if maybe_raw_script_path.is_none() {
+            maybe_raw_script_path = Some(arg);
+        } else {
+            exit_with_usage(usage);
+        }
+    }
 
-                    format!("{ln}:{ch}: {msg}")
+    (opts, maybe_raw_script_path, script_args)
+}
+
+// `supports_option` returns `true` if the command-line option `arg` has an
+// effect on `cmd`.
+fn supports_option(cmd: &Command, arg: &str) -> bool {
+    let name = arg.split_once('=').map_or(arg, |(name, _)| name);
+
+    match name {
+        "--error-format" => {
+            !matches!(cmd, Command::Golden{..})
+        },
+        "--warnings" | "--warn" | "--no-warn" => {
+            matches!(cmd, Command::Run | Command::Check | Command::Debug{..})
+        },
+        _ => {
+            matches!(cmd, Command::Run)
+        },
+    }
+}
+
+// `load_script` reads and parses the script at `raw_path`, and returns the
+// directory that it's run from, its source and its AST. It exits if the script
+// can't be read or parsed.
+fn load_script(raw_path: &str, opts: &Options) -> (PathBuf, String, Prog) {
+    let (cur_script_dir, src) =
+        match read_script(Path::new(raw_path)) {
+            Ok(v) => v,
+            Err(e) => exit_with_error(raw_path, "", opts, e),
+        };
+
+    let ast =
+        match parse_script(&src) {
+            Ok(v) => v,
+            Err(e) => exit_with_error(raw_path, &src, opts, e),
+        };
+
+    (cur_script_dir, src, ast)
+}
+
+// `write_warnings` writes the warnings that are enabled in `opts` for `ast`,
+// which was parsed from `src`, to `stderr`.
+fn write_warnings(raw_path: &str, src: &str, ast: &Prog, opts: &Options) {
+    if opts.warnings.is_empty() {
+        return;
+    }
+
+    let Prog::Body{stmts} = ast;
+    let diagnostics: Vec<Diagnostic> =
+        lint::lint(stmts, &opts.warnings)
+            .into_iter()
+            .map(warning_to_diagnostic)
+            .collect();
+
+    eprint!(
+        "{}",
+        render_diagnostics(raw_path, src, opts.error_format, &diagnostics),
+    );
+}
+
+// `run_fmt` prints the script at `raw_path` in its canonical format, or exits
+// with an error if `check` is `true` and the script isn't already formatted.
+fn run_fmt(raw_path: &str, opts: &Options, check: bool) {
+    let (_, src, ast) = load_script(raw_path, opts);
+
+    let formatted = fmt::format_prog(&src, &ast);
+    if !check {
+        print!("{formatted}");
+    } else if formatted != src {
+        eprintln!("'{raw_path}' isn't formatted");
+        process::exit(104);
+    }
+}
+
+// `run_check` reports the warnings and errors in the script at `raw_path`
+// that can be found without evaluating it.
+fn run_check(raw_path: &str, opts: &Options) {
+    let (_, src, ast) = load_script(raw_path, opts);
+    write_warnings(raw_path, &src, &ast, opts);
+
+    if let Err(e) = check_script(Path::new(raw_path), &ast) {
+        exit_with_error(raw_path, &src, opts, e);
+    }
+}
+
+// `run_script` evaluates the script at `raw_path` with `script_args`, and
+// writes any profile or coverage report that's requested by `opts`.
+fn run_script(raw_path: &str, script_args: &[String], opts: &Options) {
+    let (cur_script_dir, src, ast) = load_script(raw_path, opts);
+    write_warnings(raw_path, &src, &ast, opts);
+
+    let mut hooks: Vec<Arc<Mutex<dyn Hook + Send>>> = vec![];
+    if opts.trace {
+        let tracer = Tracer::new(raw_path, &src, io::stderr());
+        hooks.push(Arc::new(Mutex::new(tracer)));
+    }
+    let mut maybe_profiler = None;
+    if let Some(format) = opts.profile {
+        let profiler = Arc::new(Mutex::new(Profiler::new(raw_path)));
+        hooks.push(profiler.clone());
+        maybe_profiler = Some((profiler, format));
+    }
+    let mut maybe_coverage = None;
+    if let Some(path) = &opts.coverage_path {
+        let coverage = Arc::new(Mutex::new(Coverage::new(raw_path, &ast)));
+        hooks.push(coverage.clone());
+        maybe_coverage = Some((coverage, path));
+    }
+    // We only pass a hook if one is needed, so that normal evaluation isn't
+    // slowed down.
+    let maybe_hook: Option<Arc<Mutex<dyn Hook>>> =
+        if hooks.is_empty() {
+            None
+        } else {
+            Some(Arc::new(Mutex::new(Hooks(hooks))))
+        };
+
+    let result =
+        eval_script(
+            cur_script_dir,
+            Path::new(raw_path),
+            script_args,
+            &ast,
+            maybe_hook,
+        );
+
+    if let Some((profiler, format)) = maybe_profiler {
+        let profiler = profiler.try_lock().unwrap();
+        let mut stderr = io::stderr();
+        let write_result =
+            match format {
+                ProfileFormat::Table => profiler.write_table(&mut stderr),
+                ProfileFormat::Folded => profiler.write_folded(&mut stderr),
+            };
+        if let Err(e) = write_result {
+            eprintln!("couldn't write profile: {e}");
+            process::exit(101);
+        }
+    }
+
+    if let Some((coverage, path)) = maybe_coverage {
+        let mut lcov = vec![];
+        let write_result =
+            coverage.try_lock()
+                .unwrap()
+                .write_lcov(&mut lcov)
+                .and_then(|()| fs::write(path, lcov));
+        if let Err(e) = write_result {
+            eprintln!("couldn't write coverage to '{path}': {e}");
+            process::exit(101);
+        }
+    }
+
+    if let Err(e) = result {
+        exit_with_error(raw_path, &src, opts, e);
+    }
+}
+
+enum Command {
+    Run,
+    Check,
+    // `check` is `true` if `Fmt` should only check whether the script is
+    // formatted, instead of printing the formatted script.
+    Fmt{check: bool},
+    // `dap` is `true` if `Debug` should be controlled using the Debug Adapter
+    // Protocol over standard input and output, instead of by terminal
+    // commands.
+    Debug{dap: bool},
+    Test,
+    // `bless` is `true` if `Golden` should replace the expected output of
+    // failing tests with their actual output.
+    Golden{bless: bool},
+}
+
+struct Options {
+    error_format: ErrorFormat,
+    // `warnings` contains the kinds of warnings that should be reported.
+    warnings: HashSet<WarningKind>,
+    // `trace` is `true` if the statements and function calls that are
+    // evaluated should be written to `stderr`.
+    trace: bool,
+    // `profile` is the format that a profile of the script should be written
+    // to `stderr` in, if one should be written.
+    profile: Option<ProfileFormat>,
+    // `coverage_path` is the path that an `lcov` coverage report of the
+    // script should be written to, if one should be written.
+    coverage_path: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum ErrorFormat {
+    Human,
+    Json,
+}
+
+#[derive(Clone, Copy)]
+enum ProfileFormat {
+    Table,
+    // `Folded` is the "folded stacks" format that is used by flamegraph
+    // tools.
+    Folded,
+}
+
+// `parse_option` updates `opts` with the command-line option `arg`, or returns
+// a description of why `arg` isn't valid.
+fn parse_option(opts: &mut Options, arg: &str) -> Result<(), String> {
+    let parse_warning = |name: &str| {
+        WarningKind::from_name(name)
+            .ok_or_else(|| format!("unknown warning '{name}'"))
+    };
+
+    if let Some(raw_format) = arg.strip_prefix("--error-format=") {
+        opts.error_format =
+            match raw_format {
+                "human" => {
+                    ErrorFormat::Human
+                },
+                "json" => {
+                    ErrorFormat::Json
                 },
-                Error::EvalFailed{source, path} => {
-                    let st = eval_err_to_stacktrace(&path, None, source);
-
-                    let mut rendered_stacktrace = String::new();
-                    if !st.stacktrace.is_empty() {
-                        rendered_stacktrace = format!(
-                            "\nStacktrace:\n  {}",
-                            st.stacktrace.join("\n  "),
-                        );
-                    }
-
-                    format!("{}{}", st.msg, rendered_stacktrace)
+                _ => {
+                    return Err(format!(
+                        "unknown error format '{raw_format}'",
+                    ));
                 },
             };
-        eprintln!("{raw_cur_rel_script_path}:{msg}");
-        process::exit(103);
+    } else if arg == "--profile" {
+        opts.profile = Some(ProfileFormat::Table);
+    } else if let Some(raw_format) = arg.strip_prefix("--profile=") {
+        opts.profile =
+            match raw_format {
+                "table" => {
+                    Some(ProfileFormat::Table)
+                },
+                "folded" => {
+                    Some(ProfileFormat::Folded)
+                },
+                _ => {
+                    return Err(format!(
+                        "unknown profile format '{raw_format}'",
+                    ));
+                },
+            };
+    } else if let Some(path) = arg.strip_prefix("--coverage=") {
+        opts.coverage_path = Some(path.to_string());
+    } else if arg == "--trace" {
+        opts.trace = true;
+    } else if arg == "--warnings" {
+        opts.warnings.extend(WarningKind::ALL);
+    } else if let Some(name) = arg.strip_prefix("--warn=") {
+        opts.warnings.insert(parse_warning(name)?);
+    } else if let Some(name) = arg.strip_prefix("--no-warn=") {
+        opts.warnings.remove(&parse_warning(name)?);
+    } else {
+        return Err(format!("unknown option '{arg}'"));
+    }
+
+    Ok(())
+}
+
+// `run_tests` runs the test functions in the scripts in `dir`, and exits with
+// a non-zero exit code if any of the tests failed.
+fn run_tests(dir: &Path, opts: &Options) -> ! {
+    // We collect the output of each test so that it's only shown if the test
+    // fails.
+    let output = Arc::new(Mutex::new(String::new()));
+    let test_output = output.clone();
+    fns::redirect_output(Box::new(move |_, s| {
+        test_output.try_lock().unwrap().push_str(s);
+
+        Ok(())
+    }));
+
+    let mut stdout = io::stdout();
+    let result =
+        test_runner::run_tests(dir, opts.error_format, &output, &mut stdout);
+    match result {
+        Ok(summary) if summary.failed == 0 => {
+            process::exit(0);
+        },
+        Ok(_) => {
+            process::exit(103);
+        },
+        Err(e) => {
+            eprintln!("couldn't run tests in '{}': {e}", dir.display());
+            process::exit(101);
+        },
+    }
+}
+
+// `run_golden_tests` runs the golden tests in `dir` using this executable, and
+// exits with a non-zero exit code if any of the tests failed.
+fn run_golden_tests(dir: &Path, bless: bool) -> ! {
+    let exe =
+        match env::current_exe() {
+            Ok(exe) => exe,
+            Err(e) => {
+                eprintln!("couldn't get path of executable: {e}");
+                process::exit(101);
+            },
+        };
+
+    let mut stdout = io::stdout();
+    match golden::run_golden_tests(&exe, dir, bless, &mut stdout) {
+        Ok(summary) if summary.failed == 0 => {
+            process::exit(0);
+        },
+        Ok(_) => {
+            process::exit(103);
+        },
+        Err(e) => {
+            eprintln!("couldn't run golden tests in '{}': {e}", dir.display());
+            process::exit(101);
+        },
+    }
+}
+
+// `explain` prints the explanation of the error with the given `code`, and
+// exits.
+fn explain(code: &str) -> ! {
+    match error_codes::explanation(code) {
+        Some(explanation) => {
+            print!("{explanation}");
+            process::exit(0);
+        },
+        None => {
+            eprintln!("'{code}' isn't a known error code");
+            process::exit(102);
+        },
+    }
+}
+
+// `serve_lsp` runs a language server over standard input and output, and
+// exits when the client tells it to.
+fn serve_lsp() -> ! {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    match lsp::serve(&mut stdin.lock(), &mut stdout.lock()) {
+        Ok(code) => {
+            process::exit(code);
+        },
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(103);
+        },
+    }
+}
+
+// `run_debug` evaluates the script at `raw_script_path` with `script_args`
+// under a debugger, and exits. The debugger is controlled using the Debug
+// Adapter Protocol if `dap` is `true`.
+fn run_debug(
+    raw_script_path: &str,
+    script_args: &[String],
+    opts: &Options,
+    dap: bool,
+) -> ! {
+    let (cur_script_dir, src, ast) = load_script(raw_script_path, opts);
+    write_warnings(raw_script_path, &src, &ast, opts);
+
+    let script_path = Path::new(raw_script_path);
+
+    // The debugger reads commands from the standard input, so the script
+    // can't also read from it.
+    stdin_fns::detach_input();
+
+    if !dap {
+        let terminal = Terminal::new(
+            raw_script_path,
+            &src,
+            BufReader::new(io::stdin()),
+            io::stderr(),
+        );
+        let debugger = Debugger::new(terminal, BTreeSet::new(), true);
+        let hook = Arc::new(Mutex::new(debugger));
+
+        let result = eval_script(
+            cur_script_dir,
+            script_path,
+            script_args,
+            &ast,
+            Some(hook),
+        );
+        if let Err(e) = result {
+            exit_with_error(raw_script_path, &src, opts, e);
+        }
+
+        process::exit(0);
+    }
+
+    let conn = dap::Connection::new(
+        Box::new(BufReader::new(io::stdin())),
+        Box::new(io::stdout()),
+    );
+    dap::redirect_output(conn.clone());
+
+    // Clients need an absolute path to find the source of the script.
+    let abs_script_path =
+        match fs::canonicalize(script_path) {
+            Ok(path) => path.to_string_lossy().to_string(),
+            Err(_) => raw_script_path.to_string(),
+        };
+
+    let result = dap::serve(&conn, &abs_script_path, |hook| {
+        let result = eval_script(
+            cur_script_dir,
+            script_path,
+            script_args,
+            &ast,
+            Some(hook),
+        );
+        match result {
+            Ok(()) => {
+                0
+            },
+            Err(e) => {
+                if let Some(code) = requested_exit_code(&e) {
+                    return code;
+                }
+
+                let rendered = render_diagnostics(
+                    raw_script_path,
+                    &src,
+                    opts.error_format,
+                    &error_to_diagnostics(e),
+                );
+                // We write the error to `stderr` if it can't be sent to the
+                // client, so that it isn't lost.
+                let sent =
+                    conn.try_lock()
+                        .unwrap()
+                        .output(Stream::Stderr, &rendered);
+                if sent.is_err() {
+                    eprint!("{rendered}");
+                }
+
+                103
+            },
+        }
+    });
+
+    match result {
+        Ok(code) => {
+            process::exit(code);
+        },
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(103);
+        },
+    }
+}
+
+// `exit_with_error` renders `e`, which occurred while running the script at
+// `raw_script_path`, and exits.
+fn exit_with_error(
+    raw_script_path: &str,
+    src: &str,
+    opts: &Options,
+    e: Error,
+) -> ! {
+    if let Some(code) = requested_exit_code(&e) {
+        process::exit(code);
+    }
+
+    let diagnostics = error_to_diagnostics(e);
+
+    eprint!(
+        "{}",
+        render_diagnostics(
+            raw_script_path,
+            src,
+            opts.error_format,
+            &diagnostics,
+        ),
+    );
+    process::exit(103);
+}
+
+// `render_diagnostics` renders `diagnostics`, which occurred in the script at
+// `raw_script_path`, in `error_format`. `src` is used to render excerpts of
+// the script, and ANSI colour codes are only rendered if `stderr` is a
+// terminal and `NO_COLOR` isn't set. JSON diagnostics are rendered one per
+// line.
+fn render_diagnostics(
+    raw_script_path: &str,
+    src: &str,
+    error_format: ErrorFormat,
+    diagnostics: &[Diagnostic],
+) -> String {
+    let mut rendered = String::new();
+    match error_format {
+        ErrorFormat::Human => {
+            let colour =
+                io::stderr().is_terminal()
+                    && env::var_os("NO_COLOR").is_none();
+            let r = Renderer::new(src, colour);
+
+            for d in diagnostics {
+                rendered += &r.render(raw_script_path, d);
+            }
+        },
+        ErrorFormat::Json => {
+            for d in diagnostics {
+                let v = diagnostic::to_json(raw_script_path, src, d);
+                rendered += &json::encode(&v, None);
+                rendered += "\n";
+            }
+        },
+    }
+
+    rendered
+}
+
+fn warning_to_diagnostic(w: Warning) -> Diagnostic {
+    let mut d = Diagnostic::new(error_codes::warning_code(w.kind), w.msg);
+    d.severity = Severity::Warning;
+    d.span = Some(w.span);
+    d.notes = w.notes;
+
+    d
+}
+
+// `error_to_diagnostics` returns the diagnostics that should be reported for
+// `e`. Parse errors are sorted by the order in which they appear in the
+// script.
+fn error_to_diagnostics(e: Error) -> Vec<Diagnostic> {
+    match e {
+        Error::GetCurrentDirFailed{source} => {
+            vec![Diagnostic::new(
+                error_codes::READ_SCRIPT_FAILED,
+                format!("couldn't get current directory: {source}"),
+            )]
+        },
+        Error::ReadScriptFailed{path, source} => {
+            let p = path.to_string_lossy();
+
+            vec![Diagnostic::new(
+                error_codes::READ_SCRIPT_FAILED,
+                format!("couldn't read script at '{p}': {source}"),
+            )]
+        },
+        Error::ParseFailed{errs} => {
+            let mut diagnostics: Vec<_> =
+                errs.into_iter().map(parse_error_to_diagnostic).collect();
+            diagnostics.sort_by_key(|d| d.span);
+
+            diagnostics
+        },
+        Error::EvalFailed{source, path} => {
+            vec![eval_err_to_stacktrace(&path, None, source)]
+        },
+        Error::CheckFailed{errs, path} => {
+            errs.into_iter()
+                .map(|e| eval_err_to_stacktrace(&path, None, e))
+                .collect()
+        },
     }
 }
 
-fn run(cur_rel_script_path: &Path) -> Result<(), Error> {
+fn read_script(cur_rel_script_path: &Path)
+    -> Result<(PathBuf, String), Error>
+{
     let cur_script_dir = env::current_dir()
         .context(GetCurrentDirFailed)?;
     let mut cur_script_path = cur_script_dir.clone();
@@ -115,24 +851,112 @@ fn run(cur_rel_script_path: &Path) -> Result<(), Error> {
     let src = fs::read_to_string(&cur_script_path)
         .context(ReadScriptFailed{path: cur_script_path.clone()})?;
 
-    let global_bindings = vec![
+    Ok((cur_script_dir, src))
+}
+
+// `parse_script` parses `src`, and returns all of the syntax errors in `src`
+// if it couldn't be parsed.
+fn parse_script(src: &str) -> Result<Prog, Error> {
+    let tokens = syntax_error::tokens(Lexer::new(src));
+    let mut recovered_errs = vec![];
+    let result = ProgParser::new().parse(&mut recovered_errs, tokens);
+
+    let mut errs: Vec<_> =
+        recovered_errs
+            .into_iter()
+            .map(|recovery| recovery.error)
+            .map(|e| relocate_eof(src, e))
+            .collect();
+
+    match result {
+        Ok(v) if errs.is_empty() => {
+            Ok(v)
+        },
+        Ok(_) => {
+            Err(Error::ParseFailed{errs})
+        },
+        Err(e) => {
+            errs.push(relocate_eof(src, e));
+
+            Err(Error::ParseFailed{errs})
+        },
+    }
+}
+
+// `relocate_eof` returns `error` with its location moved to the start of the
+// next line if `error` is an unexpected EOF after a token that's followed by a
+// newline in `src`. This reports the missing input after the end of the last
+// line, instead of at the last character of the last token.
+fn relocate_eof(src: &str, error: ParseError<Position, Token, SyntaxError>)
+    -> ParseError<Position, Token, SyntaxError>
+{
+    match error {
+        ParseError::UnrecognizedEof{location, expected}
+            if src.as_bytes().get(location.offset) == Some(&b'\n') =>
+        {
+            ParseError::UnrecognizedEof{
+                location: Position{
+                    line: location.line + 1,
+                    col: 1,
+                    offset: location.offset + 1,
+                },
+                expected,
+            }
+        },
+        _ => {
+            error
+        },
+    }
+}
+
+// `check_script` returns the errors in `ast` that can be found without
+// evaluating it.
+fn check_script(cur_rel_script_path: &Path, ast: &Prog) -> Result<(), Error> {
+    let errs = eval::check::check_prog(ast);
+    if !errs.is_empty() {
+        return Err(Error::CheckFailed{
+            errs,
+            path: cur_rel_script_path.to_path_buf(),
+        });
+    }
+
+    Ok(())
+}
+
+fn eval_script(
+    cur_script_dir: PathBuf,
+    cur_rel_script_path: &Path,
+    script_args: &[String],
+    ast: &Prog,
+    hook: Option<Arc<Mutex<dyn Hook>>>,
+)
+    -> Result<(), Error>
+{
+    let global_bindings =
+        builtin_funcs()
+            .into_iter()
+            .map(|(name, f)| (
+                RawExpr::Var{name: name.to_string()},
+                value::new_built_in_func(name.to_string(), f),
+            ))
+            .chain(eval_builtin_funcs().into_iter().map(|(name, f)| (
+                RawExpr::Var{name: name.to_string()},
+                value::new_eval_built_in_func(name.to_string(), f),
+            )))
+            .collect::<Vec<_>>();
+    // `args` and `env` are declared in an outer scope, rather than with the
+    // other global bindings, so that scripts can declare their own `args` and
+    // `env`.
+    let process_scope = HashMap::from([
         (
-            RawExpr::Var{name: "print".to_string()},
-            value::new_built_in_func("print".to_string(), fns::print),
+            "args".to_string(),
+            (process_fns::new_args(script_args), (0, 0)),
         ),
-    ];
+        ("env".to_string(), (process_fns::new_env(), (0, 0))),
+    ]);
 
-    let mut scopes = ScopeStack::new(vec![]);
-    let lexer = Lexer::new(&src);
-    let ast =
-        match ProgParser::new().parse(lexer) {
-            Ok(v) => {
-                v
-            },
-            Err(e) => {
-                return Err(Error::ParseFailed{src: e});
-            },
-        };
+    let mut scopes =
+        ScopeStack::new(vec![Arc::new(Mutex::new(process_scope))]);
 
     eval::eval_prog(
         &EvaluationContext{
@@ -141,73 +965,145 @@ fn run(cur_rel_script_path: &Path) -> Result<(), Error> {
                 type_functions: type_functions::type_functions(),
             },
             cur_script_dir,
+            hook,
         },
         &mut scopes,
-        global_bindings.clone(),
-        &ast,
+        global_bindings,
+        ast,
     )
         .context(EvalFailed{path: cur_rel_script_path})?;
 
     Ok(())
 }
 
+fn builtin_funcs() -> Vec<(&'static str, BuiltinFunc)> {
+    vec![
+        ("print", fns::print),
+        ("write", fns::write),
+        ("print_sep", fns::print_sep),
+        ("eprint", fns::eprint),
+        ("ewrite", fns::ewrite),
+        ("eprint_sep", fns::eprint_sep),
+        ("repr", fns::repr),
+        ("assert", assert_fns::assert),
+        ("assert_eq", assert_fns::assert_eq),
+        ("json_encode", json_fns::json_encode),
+        ("json_decode", json_fns::json_decode),
+        ("exit", process_fns::exit),
+        ("read_line", stdin_fns::read_line),
+        ("read_all", stdin_fns::read_all),
+        ("lines", stdin_fns::lines),
+    ]
+}
+
+// `eval_builtin_funcs` returns the builtin functions that need access to the
+// evaluation context.
+fn eval_builtin_funcs() -> Vec<(&'static str, EvalBuiltinFunc)> {
+    vec![
+        ("assert_throws", assert_fns::assert_throws),
+    ]
+}
+
 #[derive(Debug, Snafu)]
 #[allow(clippy::enum_variant_names)]
 enum Error {
     GetCurrentDirFailed{source: IoError},
     ReadScriptFailed{path: PathBuf, source: IoError},
-    // We add `ParseError` as a `src` value rather than `source` because it
-    // doesn't satisfy the error constraints required by `Snafu`.
-    ParseFailed{src: ParseError<(usize, usize), Token, LexError>},
+    // We add the `ParseError`s as an `errs` value rather than `source` because
+    // `ParseError` doesn't satisfy the error constraints required by `Snafu`.
+    ParseFailed{errs: Vec<ParseError<Position, Token, SyntaxError>>},
     EvalFailed{source: EvalError, path: PathBuf},
+    // We add the `EvalError`s as an `errs` value rather than `source` because
+    // `check_prog` can return more than one error.
+    CheckFailed{errs: Vec<EvalError>, path: PathBuf},
+}
+
+fn parse_error_to_diagnostic(error: ParseError<Position, Token, SyntaxError>)
+    -> Diagnostic
+{
+    let code = error_codes::parse_error_code(&error);
+    let (start, end, msg) = render_parse_error(error);
+
+    let mut d = Diagnostic::new(code, msg);
+    d.span = Some((start, end));
+
+    d
 }
 
-fn render_parse_error(error: ParseError<(usize, usize), Token, LexError>)
-    -> ((usize, usize), String)
+// `render_parse_error` returns the start and end of the span that caused
+// `error`, along with a description of the error.
+fn render_parse_error(error: ParseError<Position, Token, SyntaxError>)
+    -> (Location, Location, String)
 {
     match error {
         ParseError::InvalidToken{location} => {
-            (location, "invalid token".to_string())
+            (location.loc(), location.loc(), "invalid token".to_string())
         },
-        ParseError::UnrecognizedEof{location, expected} =>
+        ParseError::UnrecognizedEof{location, expected} => {
             (
-                location,
+                location.loc(),
+                location.loc(),
                 format!(
                     "unexpected EOF; expected {}",
                     join_strings(&expected),
                 ),
-            ),
-        ParseError::UnrecognizedToken{token: (loc, tok, _loc), expected} =>
+            )
+        },
+        ParseError::UnrecognizedToken{token: (start, tok, end), expected} => {
             (
-                loc,
+                start.loc(),
+                end.loc(),
                 format!(
                     "unexpected '{}'; expected {}",
                     render_token(tok),
                     join_strings(&expected),
                 ),
+            )
+        },
+        ParseError::ExtraToken{token: (start, tok, end)} => {
+            (
+                start.loc(),
+                end.loc(),
+                format!("encountered extra token '{tok:?}'"),
+            )
+        },
+        ParseError::User{error} => {
+            let (loc, msg) = render_syntax_error(error);
+
+            (loc, loc, msg)
+        },
+    }
+}
+
+fn render_syntax_error(error: SyntaxError) -> (Location, String) {
+    match error {
+        SyntaxError::Lex(e) =>
+            render_lex_error(e),
+        SyntaxError::InvalidFormatSpec(loc, spec, reason) =>
+            (
+                loc,
+                format!("invalid format specifier '{spec}': {reason}"),
+            ),
+    }
+}
+
+fn render_lex_error(error: LexError) -> (Location, String) {
+    match error {
+        LexError::Unexpected(loc, c) =>
+            (loc, format!("unexpected '{c}'")),
+        LexError::IntOverflow(loc, raw_int) =>
+            (loc, format!("'{raw_int}' is too high for an int")),
+        LexError::InvalidEscapeChar(loc, c) =>
+            (loc, format!("'{c}' is not a valid escape character")),
+        LexError::InvalidHexChar(loc, c) =>
+            (loc, format!("'{c}' is not a valid hex character")),
+        LexError::UnescapedDollar(loc) =>
+            (loc, "'$' must be escaped".to_string()),
+        LexError::InvalidInterpolationStart(loc, c) =>
+            (
+                loc,
+                format!("interpolation slots start with '{{', got '{c}'"),
             ),
-        ParseError::ExtraToken{token: (loc, tok, _loc)} =>
-            (loc, format!("encountered extra token '{tok:?}'")),
-        ParseError::User{error} =>
-            match error {
-                LexError::Unexpected(loc, c) =>
-                    (loc, format!("unexpected '{c}'")),
-                LexError::IntOverflow(loc, raw_int) =>
-                    (loc, format!("'{raw_int}' is too high for an int")),
-                LexError::InvalidEscapeChar(loc, c) =>
-                    (loc, format!("'{c}' is not a valid escape character")),
-                LexError::InvalidHexChar(loc, c) =>
-                    (loc, format!("'{c}' is not a valid hex character")),
-                LexError::UnescapedDollar(loc) =>
-                    (loc, "'$' must be escaped".to_string()),
-                LexError::InvalidInterpolationStart(loc, c) =>
-                    (
-                        loc,
-                        format!(
-                            "interpolation slots start with '{{', got '{c}'",
-                        ),
-                    ),
-            },
     }
 }
 
@@ -286,7 +1182,7 @@ fn join_strings(xs: &[String]) -> String {
 
 #[allow(clippy::too_many_lines)]
 fn eval_err_to_stacktrace(path: &Path, func: Option<&str>, error: EvalError)
-    -> StacktracedErrorMsg
+    -> Diagnostic
 {
     match error {
         EvalError::BindFailed{source} |
@@ -295,8 +1191,6 @@ fn eval_err_to_stacktrace(path: &Path, func: Option<&str>, error: EvalError)
         EvalError::BindObjectPairFailed{source} |
         EvalError::BindListItemFailed{source} |
         EvalError::BindNextFailed{source} |
-        EvalError::EvalProgFailed{source} |
-        EvalError::EvalStmtsInNewScopeFailed{source} |
         EvalError::EvalStmtsWithScopeStackFailed{source} |
         EvalError::EvalStmtsFailed{source} |
         EvalError::EvalDeclarationRhsFailed{source} |
@@ -328,7 +1222,6 @@ fn eval_err_to_stacktrace(path: &Path, func: Option<&str>, error: EvalError)
         EvalError::EvalStringIndexFailed{source} |
         EvalError::EvalListIndexFailed{source} |
         EvalError::EvalObjectIndexFailed{source} |
-        EvalError::EvalObjectPropFailed{source} |
         EvalError::EvalIndexToI64Failed{source} |
         EvalError::EvalStartIndexFailed{source} |
         EvalError::EvalEndIndexFailed{source} |
@@ -355,56 +1248,102 @@ fn eval_err_to_stacktrace(path: &Path, func: Option<&str>, error: EvalError)
         EvalError::EvalBuiltinFuncCallFailed{source, func_name, call_loc} => {
             let next_func =
                 func_name.unwrap_or_else(|| "<unnamed function>".to_string());
-            let mut st =
+            let mut d =
                 eval_err_to_stacktrace(path, Some(&next_func), *source);
-            let (line, col) = call_loc;
-            let sep =
-                if let Some(f) = func {
-                    format!(" in '{f}':")
-                } else {
-                    String::new()
-                };
 
-            st.msg = format!("{}:{}:{} {}", line, col, sep, st.msg);
+            d.span = Some((call_loc, call_loc));
+            d.func = func.map(str::to_string);
 
-            st
+            d
         },
 
         EvalError::EvalFuncCallFailed{source, func_name, call_loc} => {
             let next_func =
                 func_name.unwrap_or_else(|| "<unnamed function>".to_string());
-            let mut st =
+            let mut d =
                 eval_err_to_stacktrace(path, Some(&next_func), *source);
-            let p = path.to_string_lossy();
-            let (line, col) = call_loc;
-            let f = func.unwrap_or("<root>");
 
-            st.stacktrace.push(format!("{p}:{line}:{col}: in '{f}'"));
+            d.stacktrace.push(Frame{
+                file: path.to_string_lossy().to_string(),
+                loc: call_loc,
+                func: func.unwrap_or("<root>").to_string(),
+            });
 
-            st
+            d
         },
 
         EvalError::AtLoc{source, line, col} => {
-            let mut st = eval_err_to_stacktrace(path, func, *source);
-            let sep =
-                if let Some(f) = func {
-                    format!(" in '{f}':")
-                } else {
-                    String::new()
-                };
+            let mut d = eval_err_to_stacktrace(path, func, *source);
 
-            st.msg = format!("{}:{}:{} {}", line, col, sep, st.msg);
+            d.span = Some(((line, col), (line, col)));
+            d.func = func.map(str::to_string);
 
-            st
+            d
+        },
+
+        EvalError::AtSpan{source, line, col, span} => {
+            let mut d = eval_err_to_stacktrace(path, func, *source);
+
+            d.span = Some((span.start.loc(), span.end.loc()));
+            d.loc = Some((line, col));
+            d.func = func.map(str::to_string);
+
+            d
         },
 
         _ => {
-            StacktracedErrorMsg{stacktrace: vec![], msg: format!("{error}")}
+            let mut d =
+                Diagnostic::new(
+                    error_codes::eval_error_code(&error),
+                    format!("{error}"),
+                );
+            d.notes = error_notes(&error);
+
+            d
         },
     }
 }
 
-struct StacktracedErrorMsg {
-    stacktrace: Vec<String>,
-    msg: String,
+// `error_notes` returns notes that point to secondary locations related to
+// `error`, such as the previous definition of a name.
+fn error_notes(error: &EvalError) -> Vec<(String, Option<Location>)> {
+    match error {
+        // Builtins are defined at line 0, which isn't in the source.
+        EvalError::AlreadyInScope{name, prev_line: 0, ..} => {
+            vec![(format!("'{name}' is a built-in"), None)]
+        },
+        EvalError::AlreadyInScope{name, prev_line, prev_col} => {
+            vec![(
+                format!("'{name}' was previously defined here"),
+                Some((*prev_line, *prev_col)),
+            )]
+        },
+        EvalError::AlreadyInBinding{name, prev_line, prev_col} => {
+            vec![(
+                format!("'{name}' was previously bound here"),
+                Some((*prev_line, *prev_col)),
+            )]
+        },
+        EvalError::PropAlreadyInDestructure{name, prev_line, prev_col} => {
+            vec![(
+                format!("'{name}' was previously destructured here"),
+                Some((*prev_line, *prev_col)),
+            )]
+        },
+        EvalError::DupParamName{name, line, col} => {
+            vec![(
+                format!("'{name}' was previously declared here"),
+                Some((*line, *col)),
+            )]
+        },
+        EvalError::AssertEqFailed{actual, expected, ..} => {
+            vec![
+                (format!("got {actual}"), None),
+                (format!("expected {expected}"), None),
+            ]
+        },
+        _ => {
+            vec![]
+        },
+    }
 }
@@ -0,0 +1,287 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io;
+use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::ast::Location;
+use crate::ast::Stmt;
+use crate::eval::hook::Hook;
+use crate::eval::scope::ScopeStack;
+use crate::eval::value::SourcedValue;
+
+const ROOT: &str = "<root>";
+
+// `Profiler` is a `Hook` that records how many times each function is called
+// and each line is evaluated, and how much time is spent in each function.
+pub struct Profiler {
+    path: String,
+    // `calls` contains the function calls that are being evaluated, with the
+    // innermost call last. The first call is the root of the program.
+    calls: Vec<Call>,
+    funcs: HashMap<CallSite, FuncStats>,
+    // `lines` maps each line to the number of statements on that line that
+    // have been evaluated.
+    lines: BTreeMap<usize, usize>,
+    // `stacks` maps each stack of function names, separated by `;`, to the
+    // time that was spent in the innermost function of the stack.
+    stacks: HashMap<String, Duration>,
+}
+
+// `CallSite` identifies a function by its name and the location that it was
+// called from.
+type CallSite = (String, Location);
+
+struct Call {
+    site: CallSite,
+    stack: String,
+    start: Instant,
+    // `children` is the time that was spent in calls made by this call.
+    children: Duration,
+}
+
+#[derive(Default)]
+struct FuncStats {
+    calls: usize,
+    // `total` is the time spent in the function, including the time spent in
+    // the functions that it calls.
+    total: Duration,
+    // `own` is the time spent in the function, excluding the time spent in
+    // the functions that it calls.
+    own: Duration,
+}
+
+impl Profiler {
+    // `new` returns a `Profiler` for the script at `path`, which should be
+    // created immediately before the script is evaluated.
+    pub fn new(path: &str) -> Self {
+        let root = Call{
+            site: (ROOT.to_string(), (0, 0)),
+            stack: ROOT.to_string(),
+            start: Instant::now(),
+            children: Duration::ZERO,
+        };
+
+        Profiler{
+            path: path.to_string(),
+            calls: vec![root],
+            funcs: HashMap::new(),
+            lines: BTreeMap::new(),
+            stacks: HashMap::new(),
+        }
+    }
+
+    // `root_times` returns the total time spent evaluating the program so
+    // far, and the time spent outside of function calls.
+    fn root_times(&self) -> (Duration, Duration) {
+        let root = &self.calls[0];
+        let total = root.start.elapsed();
+
+        (total, total.saturating_sub(root.children))
+    }
+
+    // `record` adds the statistics for `call`, which has just returned.
+    fn record(&mut self, call: Call) {
+        let elapsed = call.start.elapsed();
+        let own = elapsed.saturating_sub(call.children);
+
+        if let Some(caller) = self.calls.last_mut() {
+            caller.children += elapsed;
+        }
+        // The time spent in a recursive call is already included in the total
+        // time of the outermost call from the same site, so we don't count it
+        // twice.
+        let recursive = self.calls.iter().any(|c| c.site == call.site);
+
+        let stats = self.funcs.entry(call.site).or_default();
+        stats.calls += 1;
+        stats.own += own;
+        if !recursive {
+            stats.total += elapsed;
+        }
+
+        *self.stacks.entry(call.stack).or_default() += own;
+    }
+
+    // `write_table` writes the statistics for each function to `w`, sorted
+    // by the time spent in each function, followed by the number of times
+    // that each line was evaluated, sorted by the number of times.
+    pub fn write_table<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let (root_total, root_own) = self.root_times();
+        let mut funcs: Vec<(String, usize, Duration, Duration)> =
+            self.funcs.iter()
+                .map(|((name, (line, col)), stats)| {
+                    let site = format!("{name} ({}:{line}:{col})", self.path);
+
+                    (site, stats.calls, stats.total, stats.own)
+                })
+                .collect();
+        funcs.push((ROOT.to_string(), 1, root_total, root_own));
+        funcs.sort_by(|(a, _, _, a_own), (b, _, _, b_own)| {
+            b_own.cmp(a_own).then_with(|| a.cmp(b))
+        });
+
+        writeln!(
+            w,
+            "{:>10}  {:>10}  {:>10}  function",
+            "calls",
+            "total ms",
+            "self ms",
+        )?;
+        for (site, calls, total, own) in funcs {
+            writeln!(
+                w,
+                "{calls:>10}  {:>10.3}  {:>10.3}  {site}",
+                millis(total),
+                millis(own),
+            )?;
+        }
+
+        let mut lines: Vec<(&usize, &usize)> = self.lines.iter().collect();
+        lines.sort_by(|(a, a_hits), (b, b_hits)| {
+            b_hits.cmp(a_hits).then_with(|| a.cmp(b))
+        });
+
+        writeln!(w)?;
+        writeln!(w, "{:>10}  line", "hits")?;
+        for (line, hits) in lines {
+            writeln!(w, "{hits:>10}  {}:{line}", self.path)?;
+        }
+
+        Ok(())
+    }
+
+    // `write_folded` writes the time spent in each stack of function calls to
+    // `w`, in microseconds, in the "folded stacks" format that is used by
+    // flamegraph tools.
+    pub fn write_folded<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let (_, root_own) = self.root_times();
+        let mut stacks: Vec<(&str, Duration)> =
+            self.stacks.iter()
+                .map(|(stack, own)| (stack.as_str(), *own))
+                .collect();
+        stacks.push((ROOT, root_own));
+        stacks.sort_unstable();
+
+        for (stack, own) in stacks {
+            writeln!(w, "{stack} {}", own.as_micros())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+impl Hook for Profiler {
+    fn before_stmt(&mut self, _scopes: &ScopeStack, (_, span): &Stmt) {
+        let (line, _) = span.loc();
+
+        *self.lines.entry(line).or_default() += 1;
+    }
+
+    fn enter_call(
+        &mut self,
+        func_name: Option<&str>,
+        call_loc: Location,
+        _args: &[SourcedValue],
+    ) {
+        let name = func_name.unwrap_or("<unnamed function>");
+        let stack =
+            match self.calls.last() {
+                Some(call) => format!("{};{name}", call.stack),
+                None => name.to_string(),
+            };
+
+        self.calls.push(Call{
+            site: (name.to_string(), call_loc),
+            stack,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    fn exit_call(&mut self, _result: Option<&SourcedValue>) {
+        // We never pop the root call.
+        if self.calls.len() > 1 {
+            if let Some(call) = self.calls.pop() {
+                self.record(call);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_profiler() {
+        let src = "\
+fn fact(n) {
+    if n <= 1 { return 1; }
+    return n * fact(n - 1);
+}
+x := fact(3);
+y := fact(1);
+";
+        let prog = crate::parse_script(src)
+            .expect("couldn't parse source");
+        let profiler = Arc::new(Mutex::new(Profiler::new("test.sd")));
+
+        crate::eval_script(
+            PathBuf::new(),
+            Path::new("test.sd"),
+            &[],
+            &prog,
+            Some(profiler.clone()),
+        )
+            .expect("couldn't evaluate source");
+
+        let profiler =
+            Arc::try_unwrap(profiler)
+                .ok()
+                .expect("profiler shouldn't be shared")
+                .into_inner()
+                .expect("profiler shouldn't be poisoned");
+        let mut calls: Vec<(&str, Location, usize)> =
+            profiler.funcs.iter()
+                .map(|((name, loc), stats)| {
+                    (name.as_str(), *loc, stats.calls)
+                })
+                .collect();
+        calls.sort_unstable();
+        assert_eq!(
+            calls,
+            vec![
+                ("fact", (3, 16), 2),
+                ("fact", (5, 6), 1),
+                ("fact", (6, 6), 1),
+            ],
+        );
+        let lines: Vec<(usize, usize)> =
+            profiler.lines.into_iter().collect();
+        assert_eq!(lines, vec![(1, 1), (2, 6), (3, 2), (5, 1), (6, 1)]);
+        let mut stacks: Vec<&str> =
+            profiler.stacks.keys()
+                .map(String::as_str)
+                .collect();
+        stacks.sort_unstable();
+        assert_eq!(
+            stacks,
+            vec!["<root>;fact", "<root>;fact;fact", "<root>;fact;fact;fact"],
+        );
+    }
+}
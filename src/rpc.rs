@@ -0,0 +1,141 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+// This module implements the framing of messages that is shared by the
+// Language Server Protocol and the Debug Adapter Protocol, where each message
+// is a JSON value that is preceded by a `Content-Length` header.
+
+use std::io::BufRead;
+use std::io::Error as IoError;
+use std::io::Write;
+
+use snafu::ResultExt;
+use snafu::Snafu;
+
+use crate::json;
+use crate::json::Json;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    ReadHeaderFailed{source: IoError},
+    #[snafu(display("invalid header '{}'", header))]
+    InvalidHeader{header: String},
+    #[snafu(display("message has no 'Content-Length' header"))]
+    MissingContentLength,
+    ReadContentFailed{source: IoError},
+    #[snafu(display(
+        "message content is {} bytes, which is more than the maximum of {}",
+        len,
+        MAX_CONTENT_LEN,
+    ))]
+    ContentTooLong{len: usize},
+    WriteMessageFailed{source: IoError},
+}
+
+// `MAX_CONTENT_LEN` is the length of the largest message content that will be
+// read. We check the `Content-Length` against this before allocating a buffer
+// for the content, so that an invalid header can't exhaust memory.
+const MAX_CONTENT_LEN: usize = 64 * 1024 * 1024;
+
+// `read_message` returns the content of the next message in `r`, or `None` if
+// `r` is closed before the start of the next message.
+pub fn read_message<R: BufRead>(r: &mut R) -> Result<Option<String>, Error> {
+    let mut content_len = None;
+    let mut read_header = false;
+    loop {
+        let mut line = String::new();
+        let n = r.read_line(&mut line)
+            .context(ReadHeaderFailed)?;
+        if n == 0 && !read_header {
+            return Ok(None);
+        }
+        read_header = true;
+
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            break;
+        }
+
+        let (name, value) =
+            match line.split_once(':') {
+                Some(v) => v,
+                None => return Err(Error::InvalidHeader{header: line.into()}),
+            };
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            match value.trim().parse::<usize>() {
+                Ok(n) => {
+                    content_len = Some(n);
+                },
+                Err(_) => {
+                    return Err(Error::InvalidHeader{header: line.into()});
+                },
+            }
+        }
+    }
+
+    let content_len =
+        match content_len {
+            Some(n) => n,
+            None => return Err(Error::MissingContentLength),
+        };
+    if content_len > MAX_CONTENT_LEN {
+        return Err(Error::ContentTooLong{len: content_len});
+    }
+
+    let mut content = vec![0; content_len];
+    r.read_exact(&mut content)
+        .context(ReadContentFailed)?;
+
+    Ok(Some(String::from_utf8_lossy(&content).into_owned()))
+}
+
+pub fn write_message<W: Write>(w: &mut W, msg: &Json) -> Result<(), Error> {
+    let content = json::encode(msg, None);
+
+    write!(w, "Content-Length: {}\r\n\r\n{}", content.len(), content)
+        .context(WriteMessageFailed)?;
+    w.flush()
+        .context(WriteMessageFailed)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_and_write_messages() {
+        let msg =
+            json::decode(r#"{"jsonrpc": "2.0", "method": "exit"}"#)
+                .expect("couldn't decode message");
+        let mut buf = vec![];
+        for _ in 0..2 {
+            write_message(&mut buf, &msg)
+                .expect("couldn't write message");
+        }
+
+        let mut r = Cursor::new(buf);
+        for _ in 0..2 {
+            let content =
+                read_message(&mut r)
+                    .expect("couldn't read message")
+                    .expect("expected message");
+
+            assert_eq!(json::decode(&content).ok(), Some(msg.clone()));
+        }
+        assert!(matches!(read_message(&mut r), Ok(None)));
+
+        let mut r = Cursor::new(b"Content-Type: x\r\n\r\n{}".to_vec());
+        let result = read_message(&mut r);
+        assert!(matches!(result, Err(Error::MissingContentLength)));
+
+        let header = format!("Content-Length: {}\r\n\r\n", usize::MAX);
+        let mut r = Cursor::new(header.into_bytes());
+        let result = read_message(&mut r);
+        assert!(matches!(result, Err(Error::ContentTooLong{..})));
+    }
+}
@@ -0,0 +1,26 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use crate::lexer::LexError;
+use crate::lexer::Location;
+use crate::lexer::TokenSpan;
+
+// `SyntaxError` is the type of the user errors that are raised by the parser.
+// Errors that are found while lexing are wrapped in `Lex`, while the other
+// variants are found while parsing.
+#[derive(Debug)]
+pub enum SyntaxError {
+    Lex(LexError),
+    InvalidFormatSpec(Location, String, String),
+}
+
+// `tokens` wraps the errors returned by `lexer` in `SyntaxError`s, so that
+// the tokens of `lexer` can be passed to the parser.
+pub fn tokens<I>(lexer: I)
+    -> impl Iterator<Item = Result<TokenSpan, SyntaxError>>
+where
+    I: Iterator<Item = Result<TokenSpan, LexError>>,
+{
+    lexer.map(|item| item.map_err(SyntaxError::Lex))
+}
@@ -0,0 +1,263 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crate::ast::Prog;
+use crate::ast::RawExpr;
+use crate::ast::RawStmt;
+use crate::ErrorFormat;
+
+// `TEST_PREFIX` is the prefix of the names of top-level functions that are
+// run as tests.
+const TEST_PREFIX: &str = "test_";
+
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+}
+
+// `run_tests` runs the test functions in the scripts in `dir` and its
+// subdirectories, and writes a report of the results to `w`. `output` should
+// collect the output of the scripts; it's included in the report for tests
+// that fail.
+pub fn run_tests<W: Write>(
+    dir: &Path,
+    error_format: ErrorFormat,
+    output: &Arc<Mutex<String>>,
+    w: &mut W,
+)
+    -> io::Result<Summary>
+{
+    let cur_dir = env::current_dir()?;
+    let mut summary = Summary{passed: 0, failed: 0};
+    let mut failures = vec![];
+
+    for path in find_scripts(dir)? {
+        let src = fs::read_to_string(&path)?;
+        // We remove the `./` prefix that paths have if `dir` is `.`.
+        let path = path.strip_prefix(".").unwrap_or(&path);
+        let raw_path = path.to_string_lossy().to_string();
+
+        let prog =
+            match crate::parse_script(&src) {
+                Ok(prog) => {
+                    prog
+                },
+                Err(e) => {
+                    writeln!(w, "test {raw_path} ... FAILED")?;
+                    summary.failed += 1;
+
+                    let diagnostics = crate::error_to_diagnostics(e);
+                    failures.push((
+                        raw_path.clone(),
+                        crate::render_diagnostics(
+                            &raw_path,
+                            &src,
+                            error_format,
+                            &diagnostics,
+                        ),
+                    ));
+
+                    continue;
+                },
+            };
+
+        for name in test_names(&prog) {
+            output.try_lock().unwrap().clear();
+
+            let result =
+                crate::eval_script(
+                    cur_dir.clone(),
+                    path,
+                    &[],
+                    &with_test_call(&prog, &name),
+                    None,
+                );
+
+            let label = format!("{raw_path}::{name}");
+            match result {
+                Ok(()) => {
+                    writeln!(w, "test {label} ... ok")?;
+                    summary.passed += 1;
+                },
+                Err(e) => {
+                    writeln!(w, "test {label} ... FAILED")?;
+                    summary.failed += 1;
+
+                    let diagnostics = crate::error_to_diagnostics(e);
+                    let mut report = output.try_lock().unwrap().clone();
+                    report += &crate::render_diagnostics(
+                        &raw_path,
+                        &src,
+                        error_format,
+                        &diagnostics,
+                    );
+                    failures.push((label, report));
+                },
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        writeln!(w, "\nfailures:")?;
+        for (label, report) in failures {
+            write!(w, "\n---- {label} ----\n{report}")?;
+        }
+    }
+
+    let result = if summary.failed == 0 { "ok" } else { "FAILED" };
+    writeln!(
+        w,
+        "\ntest result: {result}. {} passed; {} failed",
+        summary.passed,
+        summary.failed,
+    )?;
+
+    Ok(summary)
+}
+
+// `find_scripts` returns the paths of the Seed scripts in `dir` and its
+// subdirectories, in order.
+fn find_scripts(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for maybe_entry in fs::read_dir(dir)? {
+        let path = maybe_entry?.path();
+        if path.is_dir() {
+            paths.extend(find_scripts(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "sd") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
+
+// `test_names` returns the names of the top-level functions in `prog` whose
+// names start with `TEST_PREFIX`, in the order that they're defined.
+fn test_names(Prog::Body{stmts}: &Prog) -> Vec<String> {
+    stmts.iter()
+        .filter_map(|(raw_stmt, _)| match raw_stmt {
+            RawStmt::Func{name: (name, _), ..}
+                    if name.starts_with(TEST_PREFIX) => {
+                Some(name.clone())
+            },
+            _ => {
+                None
+            },
+        })
+        .collect()
+}
+
+// `with_test_call` returns a copy of `prog` that calls the test function
+// `name` after the rest of the program has been evaluated. The call is
+// located at the definition of the function, so that errors in the test are
+// reported relative to it.
+fn with_test_call(Prog::Body{stmts}: &Prog, name: &str) -> Prog {
+    let mut stmts = stmts.clone();
+    let maybe_span =
+        stmts.iter()
+            .find_map(|(raw_stmt, span)| match raw_stmt {
+                RawStmt::Func{name: (n, _), ..} if n == name => Some(*span),
+                _ => None,
+            });
+
+    if let Some(span) = maybe_span {
+        let func = (RawExpr::Var{name: name.to_string()}, span);
+        let call = (RawExpr::Call{func: Box::new(func), args: vec![]}, span);
+
+        stmts.push((RawStmt::Expr{expr: call}, span));
+    }
+
+    Prog::Body{stmts}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_run_tests() {
+        let dir = env::temp_dir().join(format!(
+            "seed_test_runner_{}",
+            std::process::id(),
+        ));
+        let nested = dir.join("nested");
+        fs::create_dir_all(&nested)
+            .expect("couldn't create test directory");
+        let scripts = [
+            (
+                dir.join("a.sd"),
+                "\
+count := 0;
+fn inc() {
+    count += 1;
+    return count;
+}
+fn test_isolated() {
+    assert_eq(inc(), 1);
+}
+fn test_isolated_again() {
+    assert_eq(inc(), 1);
+}
+fn helper() {
+    assert(false);
+}
+",
+            ),
+            (
+                nested.join("b.sd"),
+                "\
+fn test_fails() {
+    assert_eq([1, 2], [1, 3]);
+}
+",
+            ),
+            (dir.join("c.txt"), "fn test_ignored() {}\n"),
+        ];
+        for (path, src) in &scripts {
+            fs::write(path, src)
+                .expect("couldn't write test script");
+        }
+        let output = Arc::new(Mutex::new(String::new()));
+        let mut report = vec![];
+
+        let summary =
+            run_tests(&dir, ErrorFormat::Human, &output, &mut report)
+                .expect("couldn't run tests");
+
+        fs::remove_dir_all(&dir)
+            .expect("couldn't remove test directory");
+        assert_eq!((summary.passed, summary.failed), (2, 1));
+        let report = String::from_utf8_lossy(&report);
+        let a = dir.join("a.sd").to_string_lossy().to_string();
+        let b = nested.join("b.sd").to_string_lossy().to_string();
+        assert!(
+            report.starts_with(&format!(
+                "test {a}::test_isolated ... ok\n\
+                 test {a}::test_isolated_again ... ok\n\
+                 test {b}::test_fails ... FAILED\n\
+                 \n\
+                 failures:\n\
+                 \n\
+                 ---- {b}::test_fails ----\n\
+                 {b}:2:5: in 'test_fails': values aren't equal (at [1]) \
+                 [E0154]\n",
+            )),
+            "unexpected report:\n{report}",
+        );
+        assert!(
+            report.ends_with("\ntest result: FAILED. 2 passed; 1 failed\n"),
+            "unexpected report:\n{report}",
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::io::Write;
+
+use crate::ast::Location;
+use crate::ast::Stmt;
+use crate::builtins::fns;
+use crate::eval::hook::Hook;
+use crate::eval::scope::ScopeStack;
+use crate::eval::value::SourcedValue;
+
+// `MAX_STMT_CHARS` is the maximum number of characters of a statement that
+// are shown in a trace.
+const MAX_STMT_CHARS: usize = 60;
+
+const INDENT: &str = "  ";
+
+// `Tracer` is a `Hook` that writes a line to `w` for each statement that is
+// evaluated, and for each entry to and exit from a function, indented by the
+// depth of the call.
+pub struct Tracer<W> {
+    path: String,
+    src: String,
+    w: W,
+    // `calls` contains the name and location of each call that is being
+    // evaluated.
+    calls: Vec<(String, Location)>,
+}
+
+impl<W: Write> Tracer<W> {
+    // `new` returns a `Tracer` for the script at `path`, whose source is
+    // `src`.
+    pub fn new(path: &str, src: &str, w: W) -> Self {
+        Tracer{
+            path: path.to_string(),
+            src: src.to_string(),
+            w,
+            calls: vec![],
+        }
+    }
+
+    // `trace` writes `msg`, which relates to `loc`. Errors are ignored so
+    // that tracing doesn't affect the evaluation of the script.
+    fn trace(&mut self, (line, col): Location, msg: &str) {
+        let indent = INDENT.repeat(self.calls.len());
+
+        let _ = writeln!(self.w, "{indent}{}:{line}:{col}: {msg}", self.path);
+    }
+}
+
+impl<W: Write> Hook for Tracer<W> {
+    fn before_stmt(&mut self, _scopes: &ScopeStack, (_, span): &Stmt) {
+        let text =
+            self.src.get(span.start.offset .. span.end.offset)
+                .unwrap_or("")
+                .trim_end();
+        let first_line = text.lines().next().unwrap_or("");
+
+        let mut rendered: String =
+            first_line.chars().take(MAX_STMT_CHARS).collect();
+        if rendered.len() < text.len() {
+            rendered += " ...";
+        }
+
+        self.trace(span.loc(), &rendered);
+    }
+
+    fn enter_call(
+        &mut self,
+        func_name: Option<&str>,
+        call_loc: Location,
+        args: &[SourcedValue],
+    ) {
+        let name = func_name.unwrap_or("<unnamed function>").to_string();
+        let rendered_args: Vec<String> =
+            args.iter()
+                .map(|arg| fns::render_repr(&arg.v))
+                .collect();
+
+        self.trace(
+            call_loc,
+            &format!("call {name}({})", rendered_args.join(", ")),
+        );
+        self.calls.push((name, call_loc));
+    }
+
+    fn exit_call(&mut self, result: Option<&SourcedValue>) {
+        if let Some((name, call_loc)) = self.calls.pop() {
+            let msg =
+                match result {
+                    Some(v) => {
+                        format!("{name} returned {}", fns::render_repr(&v.v))
+                    },
+                    None => {
+                        format!("{name} failed")
+                    },
+                };
+
+            self.trace(call_loc, &msg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_tracer() {
+        let src = "\
+fn add(a, b) {
+    return a + b;
+}
+x := add(1, [2]);
+";
+        let prog = crate::parse_script(src)
+            .expect("couldn't parse source");
+        let tracer =
+            Arc::new(Mutex::new(Tracer::new("test.sd", src, Vec::new())));
+
+        let result = crate::eval_script(
+            PathBuf::new(),
+            Path::new("test.sd"),
+            &[],
+            &prog,
+            Some(tracer.clone()),
+        );
+
+        assert!(result.is_err(), "expected evaluation to fail");
+        let tracer =
+            Arc::try_unwrap(tracer)
+                .ok()
+                .expect("tracer shouldn't be shared")
+                .into_inner()
+                .expect("tracer shouldn't be poisoned");
+        assert_eq!(
+            String::from_utf8_lossy(&tracer.w),
+            "\
+test.sd:1:1: fn add(a, b) { ...
+test.sd:4:1: x := add(1, [2])
+test.sd:4:6: call add(1, [2])
+  test.sd:2:5: return a + b
+test.sd:4:6: add failed
+",
+        );
+    }
+}
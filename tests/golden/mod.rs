@@ -0,0 +1,60 @@
+// Copyright 2025 Sean Kelleher. All rights reserved.
+// Use of this source code is governed by an MIT
+// licence that can be found in the LICENCE file.
+
+use std::env;
+use std::fs;
+
+use crate::assert_cmd::Command;
+
+#[test]
+fn golden_supports_headers_named_sections_and_wildcards() {
+    let dir = env::temp_dir().join(format!(
+        "seed_golden_format_{}",
+        std::process::id(),
+    ));
+    fs::create_dir_all(&dir)
+        .expect("couldn't create test directory");
+    fs::write(
+        dir.join("cases.xtest"),
+        "\
+================================================== wildcards
+exit_code: 103
+env: SEED_TEST=1
+--------------------------------------------------
+print(\"took 12ms\");
+print(env.get(\"SEED_TEST\"));
+print(read_all());
+print(x);
+--------------------------------------------------
+took [..]ms
+1
+input
+
+--------------------------------------------------
+cases/wildcards.sd:4:7: [..]
+[...]
+-------------------------------------------------- stdin
+input
+-------------------------------------------------- file data/a.txt
+a
+==================================================
+",
+    )
+        .expect("couldn't write test file");
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let assert = cmd.arg("golden").arg(&dir).assert();
+
+    fs::remove_dir_all(&dir)
+        .expect("couldn't remove test directory");
+    let path = dir.join("cases.xtest");
+    assert
+        .code(0)
+        .stdout(format!(
+            "test {}::wildcards ... ok\n\
+             \n\
+             test result: ok. 1 passed; 0 failed\n",
+            path.display(),
+        ));
+}
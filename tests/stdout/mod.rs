@@ -2,4 +2,41 @@
 // Use of this source code is governed by an MIT
 // licence that can be found in the LICENCE file.
 
+use std::fs;
+use std::path::Path;
+
+// The tests in this directory are run using the same logic as `seed golden`.
+#[allow(dead_code)]
+#[path = "../../src/golden/mod.rs"]
+mod golden;
+
+// `run_test` runs the test called `name` in the golden test file at
+// `test_file_path`.
+fn run_test(test_file_path: &str, name: &str) {
+    let path = Path::new(test_file_path);
+    let src = fs::read_to_string(path)
+        .expect("couldn't read test file");
+    let extended = path.extension().is_some_and(|ext| ext == "xtest");
+    let tests = golden::format::parse(&src, extended)
+        .expect("couldn't parse test file");
+    let test = tests.iter()
+        .find(|test| test.name == name)
+        .expect("couldn't find test");
+    let stem = path.file_stem()
+        .expect("couldn't extract file stem from path")
+        .to_string_lossy();
+
+    let outcome =
+        golden::run_case(
+            Path::new(env!("CARGO_BIN_EXE_seed")),
+            &Path::new(env!("OUT_DIR")).join("tests"),
+            &stem,
+            test,
+        )
+            .expect("couldn't run test");
+
+    let report = golden::render_failure(test, &outcome);
+    assert!(report.is_empty(), "{report}");
+}
+
 include!(concat!(env!("OUT_DIR"), "/tests.rs"));